@@ -0,0 +1,66 @@
+// How fast clear_completed_lines() goes on a nearly full board.
+// `cargo bench`. Plain std timing, Criterion isn't among the dependencies
+// this builds with: the clears are timed in SAMPLES runs and the median
+// clears per second is what's reported, with the slowest and fastest run
+// for the spread. A change well outside the spread is worth a look.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tetris::testing::clear_lines;
+use tetris::Game;
+
+const SAMPLES: usize = 10;
+const ITERATIONS: u32 = 10_000;
+
+// 10x20, four rows of air, then a stack where every other row is complete.
+fn board() -> Vec<String> {
+    (0..20)
+        .map(|j| match j {
+            0..=3 => "..........".to_string(),
+            _ if j % 2 == 0 => "IOTJLSZIOT".to_string(),
+            _ => "IOTJ.SZIOT".to_string(),
+        })
+        .collect()
+}
+
+// Clears per second over ITERATIONS clears, and the lines they took out.
+fn sample(rows: &[&str]) -> (f64, usize) {
+    let mut total = Duration::ZERO;
+    let mut lines = 0;
+    for _ in 0..ITERATIONS {
+        // Only the clear is timed, building the board isn't.
+        let mut game = Game::from_matrix(rows).unwrap();
+
+        let start = Instant::now();
+        lines += black_box(clear_lines(black_box(&mut game)));
+        total += start.elapsed();
+    }
+    (ITERATIONS as f64 / total.as_secs_f64(), lines)
+}
+
+fn main() {
+    let rows = board();
+    let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+
+    // One to warm up, not counted.
+    sample(&rows);
+    let mut rates = Vec::with_capacity(SAMPLES);
+    let mut lines = 0;
+    for _ in 0..SAMPLES {
+        let (rate, cleared) = sample(&rows);
+        rates.push(rate);
+        lines += cleared;
+    }
+    rates.sort_by(f64::total_cmp);
+
+    let median = rates[SAMPLES / 2];
+    println!(
+        "line_clear: {} clears ({} lines), median {:.0} clears/s ({:.0} to {:.0}), {:?} per clear",
+        SAMPLES as u32 * ITERATIONS,
+        lines,
+        median,
+        rates[0],
+        rates[SAMPLES - 1],
+        Duration::from_secs_f64(1.0 / median)
+    );
+}
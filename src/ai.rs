@@ -0,0 +1,125 @@
+// Autoplay AI.
+// For each new piece, try every rotation × every column, drop it, score the
+// resulting board with a heuristic and queue up the keys that get the piece
+// to the best spot. The keys go through the same `step` as a human would, so
+// the AI can't cheat.
+// Boards already scored aren't scored again, e.g. an O comes out the same
+// whichever way round it is.
+// Ref: https://codemyroad.wordpress.com/2013/04/14/tetris-ai-the-near-perfect-player/
+use std::collections::{HashMap, VecDeque};
+
+use termion::event::Key;
+
+use crate::board::Board;
+use crate::{analysis, Cell, Point, Tetromino};
+
+// Heuristic weights. Tweak away.
+const AGGREGATE_HEIGHT_WEIGHT: f64 = -0.510066;
+const COMPLETED_LINES_WEIGHT: f64 = 0.760666;
+const HOLES_WEIGHT: f64 = -0.35663;
+const BUMPINESS_WEIGHT: f64 = -0.184483;
+
+#[derive(Default)]
+pub struct Ai {
+    // Keys left to press for the current piece.
+    plan: VecDeque<Key>,
+    // Whether the current falling piece already has a plan.
+    planned: bool,
+}
+
+impl Ai {
+    // Pick the next key to press.
+    pub fn next_key(&mut self, falling: Option<&Tetromino>, board: &Board) -> Option<Key> {
+        let t = match falling {
+            Some(t) => t,
+            None => {
+                // Piece locked. Plan again once the next one shows up.
+                self.planned = false;
+                self.plan.clear();
+                return None;
+            }
+        };
+
+        if !self.planned {
+            self.plan = plan(t, board);
+            self.planned = true;
+        }
+
+        // Once in position, soft drop the rest of the way.
+        Some(self.plan.pop_front().unwrap_or(Key::Down))
+    }
+}
+
+// Find the best placement for `t` and return the keys that get it there.
+fn plan(t: &Tetromino, board: &Board) -> VecDeque<Key> {
+    // (score, rotations, horizontal offset)
+    let mut best: Option<(f64, usize, i16)> = None;
+    // Scores by board fingerprint.
+    let mut seen = HashMap::new();
+
+    let mut rotated = t.clone();
+    for rotations in 0..4 {
+        if rotations > 0 && !board.try_rotate(&mut rotated, false) {
+            break;
+        }
+
+        // Slide all the way to the left, then try each column on the way right.
+        let mut shifted = rotated.clone();
+        let mut dx = 0;
+        while board.try_move(&mut shifted, Point::LEFT) {
+            dx -= 1;
+        }
+
+        loop {
+            let score = evaluate(&shifted, board, &mut seen);
+            if best.is_none_or(|(s, _, _)| score > s) {
+                best = Some((score, rotations, dx));
+            }
+
+            if !board.try_move(&mut shifted, Point::RIGHT) {
+                break;
+            }
+            dx += 1;
+        }
+    }
+
+    let mut keys = VecDeque::new();
+    if let Some((_, rotations, dx)) = best {
+        keys.extend(std::iter::repeat_n(Key::Up, rotations));
+        let side = if dx < 0 { Key::Left } else { Key::Right };
+        keys.extend(std::iter::repeat_n(side, dx.unsigned_abs() as usize));
+    }
+
+    keys
+}
+
+// Drop `t` straight down and score the board it leaves behind.
+fn evaluate(t: &Tetromino, board: &Board, seen: &mut HashMap<u64, f64>) -> f64 {
+    let mut t = t.clone();
+    while board.try_move(&mut t, Point::DOWN) {}
+
+    let mut board = board.clone();
+    for block in t.cells() {
+        board.try_set(block.x, block.y, Cell::Filled(t.shape));
+    }
+    // Before the lines go, so the lines are in there too.
+    let fingerprint = board.fingerprint();
+    if let Some(&score) = seen.get(&fingerprint) {
+        return score;
+    }
+
+    // Take out the completed lines so the rest of the metrics see the board
+    // the player would actually be left with.
+    let lines = board.remove_full_rows();
+
+    let aggregate_height = analysis::aggregate_height(&board);
+    let holes = analysis::holes(&board);
+    let bumpiness = analysis::bumpiness(&board);
+
+    let score = AGGREGATE_HEIGHT_WEIGHT * aggregate_height as f64
+        + COMPLETED_LINES_WEIGHT * lines as f64
+        + HOLES_WEIGHT * holes as f64
+        + BUMPINESS_WEIGHT * bumpiness as f64;
+    seen.insert(fingerprint, score);
+    score
+}
@@ -0,0 +1,105 @@
+// Board analysis.
+// The usual numbers for sizing up a stack: how tall it is, how many holes
+// are buried in it and how ragged the top is. The AI scores its moves with
+// them, and they say something about how tidy a game was.
+use crate::board::Board;
+use crate::Cell;
+
+// Each column's height, counted up from the bottom to its top filled cell.
+pub(crate) fn column_heights(board: &Board) -> Vec<usize> {
+    (0..board.width())
+        .map(|x| {
+            board
+                .rows()
+                .position(|row| row[x] != Cell::Empty)
+                .map_or(0, |y| board.height() - y)
+        })
+        .collect()
+}
+
+// All the column heights added up.
+pub(crate) fn aggregate_height(board: &Board) -> usize {
+    column_heights(board).iter().sum()
+}
+
+// The tallest column.
+pub(crate) fn max_height(board: &Board) -> usize {
+    column_heights(board).into_iter().max().unwrap_or(0)
+}
+
+// Empty cells with something filled above them in the same column.
+pub(crate) fn holes(board: &Board) -> usize {
+    (0..board.width())
+        .map(|x| {
+            board
+                .rows()
+                .skip_while(|row| row[x] == Cell::Empty)
+                .filter(|row| row[x] == Cell::Empty)
+                .count()
+        })
+        .sum()
+}
+
+// How far apart neighbouring columns are, added up.
+pub(crate) fn bumpiness(board: &Board) -> usize {
+    column_heights(board)
+        .windows(2)
+        .map(|pair| pair[0].abs_diff(pair[1]))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows of '#' and '.', top first.
+    fn board(rows: &[&str]) -> Board {
+        let mut board = Board::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    board.set(x, y, Cell::Garbage);
+                }
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn empty_board_is_all_zeros() {
+        let empty = board(&["....", "....", "...."]);
+        assert_eq!(column_heights(&empty), [0, 0, 0, 0]);
+        assert_eq!(aggregate_height(&empty), 0);
+        assert_eq!(max_height(&empty), 0);
+        assert_eq!(holes(&empty), 0);
+        assert_eq!(bumpiness(&empty), 0);
+    }
+
+    #[test]
+    fn ragged_stack_with_holes() {
+        let stack = board(&[
+            ".....", //
+            ".#...", //
+            "##.#.", //
+            "#..##", //
+            "#.###",
+        ]);
+        assert_eq!(column_heights(&stack), [3, 4, 1, 3, 2]);
+        assert_eq!(aggregate_height(&stack), 13);
+        assert_eq!(max_height(&stack), 4);
+        // Both under the one sticking up in the second column.
+        assert_eq!(holes(&stack), 2);
+        assert_eq!(bumpiness(&stack), 1 + 3 + 2 + 1);
+    }
+
+    #[test]
+    fn a_hole_under_an_overhang_counts() {
+        let stack = board(&[
+            "##.", //
+            "#..", //
+            "###",
+        ]);
+        assert_eq!(holes(&stack), 1);
+        assert_eq!(column_heights(&stack), [3, 3, 1]);
+    }
+}
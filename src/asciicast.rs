@@ -0,0 +1,167 @@
+// Asciicast recording.
+// With --record, everything sent to the terminal is also written to an
+// asciicast v2 file, for `asciinema play` or the web player. The first line
+// is the header, then one line per frame, the seconds since the start and
+// what was drawn:
+//
+//     {"version": 2, "width": 80, "height": 24, "timestamp": 1792087491, "title": "tetris"}
+//     [0.016, "o", "\u001b[2J\u001b[1;1H..."]
+//
+// Every line goes out as soon as it's written, so a crash still leaves a file
+// that plays up to there. The size is the terminal's when recording started.
+// Ref: https://docs.asciinema.org/manual/asciicast/v2/
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub struct Asciicast {
+    out: Box<dyn Write>,
+    start: Instant,
+}
+
+impl Asciicast {
+    // Start a recording of a `width` by `height` terminal, header and all.
+    pub fn new(mut out: Box<dyn Write>, width: u16, height: u16, title: &str) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"title\": {}}}",
+            width,
+            height,
+            timestamp,
+            json_string(title)
+        )?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    // `bytes` went to the terminal just now.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(bytes);
+        writeln!(
+            self.out,
+            "[{:.6}, \"o\", {}]",
+            self.start.elapsed().as_secs_f64(),
+            json_string(&text)
+        )?;
+        self.out.flush()
+    }
+}
+
+// Writes go to `out` as usual and, a frame at a time, to the recording.
+// Frames end on flush, which is when they'd reach the terminal anyway.
+pub(crate) struct Tee {
+    out: Box<dyn Write>,
+    // None once writing to it failed. The game goes on, unrecorded.
+    cast: Option<Asciicast>,
+    frame: Vec<u8>,
+}
+
+impl Tee {
+    pub(crate) fn new(out: Box<dyn Write>, cast: Asciicast) -> Self {
+        Self {
+            out,
+            cast: Some(cast),
+            frame: Vec::new(),
+        }
+    }
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.out.write(buf)?;
+        if self.cast.is_some() {
+            self.frame.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(cast) = self.cast.as_mut() {
+            if cast.output(&self.frame).is_err() {
+                self.cast = None;
+            }
+        }
+        self.frame.clear();
+        self.out.flush()
+    }
+}
+
+// Whatever's left over goes in too, e.g. putting the cursor back on the way
+// out.
+impl Drop for Tee {
+    fn drop(&mut self) {
+        if let Some(cast) = self.cast.as_mut() {
+            let _ = cast.output(&self.frame);
+        }
+    }
+}
+
+// `s` as a JSON string, quotes included.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                json.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Capture;
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(
+            json_string("\x1b[1;1H\"a\\b\"\r\n·"),
+            "\"\\u001b[1;1H\\\"a\\\\b\\\"\\r\\n·\""
+        );
+    }
+
+    #[test]
+    fn a_line_per_frame() {
+        let screen = Capture::default();
+        let file = Capture::default();
+        let cast = Asciicast::new(Box::new(file.clone()), 80, 24, "tetris").unwrap();
+        let mut tee = Tee::new(Box::new(screen.clone()), cast);
+
+        write!(tee, "\x1b[2J").unwrap();
+        write!(tee, "hi").unwrap();
+        tee.flush().unwrap();
+        // Nothing drawn, nothing recorded.
+        tee.flush().unwrap();
+        write!(tee, "bye").unwrap();
+        drop(tee);
+
+        assert_eq!(screen.0.take(), b"\x1b[2Jhibye");
+        let file = String::from_utf8(file.0.take()).unwrap();
+        let lines: Vec<&str> = file.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 80, \"height\": 24, "));
+        assert!(lines[0].ends_with(", \"title\": \"tetris\"}"));
+        assert!(lines[1].starts_with('['));
+        assert!(lines[1].ends_with(", \"o\", \"\\u001b[2Jhi\"]"));
+        assert!(lines[2].ends_with(", \"o\", \"bye\"]"));
+    }
+}
@@ -0,0 +1,407 @@
+// The board.
+// Every cell in the one Vec, a row after the other, top first, so (x, y) is
+// at y * width + x. Looking at a cell is no pointer chase per row, and a
+// copy for the AI to try moves on is a single allocation.
+//
+// Each row also has a bitmask of which of its cells are taken, bit x for
+// column x. A full row is then one compare and a collision one bit test.
+// The masks only ever follow the cells, which is why the cells can only be
+// changed through here; debug builds check the two still agree after every
+// change.
+//
+// get, set and row are for when x and y are known to be on the board, and
+// panic when they aren't. get_checked, try_set and is_free are for when they
+// might not be, e.g. a piece poking out of a wall. Indexing by row gives
+// that row as a slice too.
+//
+// Whether a piece fits, and moving and turning it only if it does, is here
+// too, the one place that checks. A move or turn makes the piece it would
+// be, checks that, and only then swaps it in.
+use std::ops::Index;
+use std::slice::ChunksExact;
+
+use crate::{Cell, Point, Tetromino, KICKS};
+
+// As wide as a board goes, a bit a column.
+pub(crate) const MAX_WIDTH: usize = u16::BITS as usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    masks: Vec<u16>,
+    // The mask of a full row.
+    full: u16,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width <= MAX_WIDTH, "{} columns don't fit a row mask", width);
+        Self {
+            width,
+            height,
+            cells: vec![Cell::Empty; width * height],
+            masks: vec![0; height],
+            full: ((1u32 << width) - 1) as u16,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.row(y)[x]
+    }
+
+    // The cell at (x, y), or None off the board.
+    pub fn get_checked(&self, x: i16, y: i16) -> Option<Cell> {
+        self.on_board(x, y).map(|(x, y)| self.get(x, y))
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        self.cells[y * self.width + x] = cell;
+        match cell {
+            Cell::Empty => self.masks[y] &= !(1 << x),
+            _ => self.masks[y] |= 1 << x,
+        }
+        self.check();
+    }
+
+    // Set the cell at (x, y) if it's on the board. Whether it was.
+    pub fn try_set(&mut self, x: i16, y: i16, cell: Cell) -> bool {
+        match self.on_board(x, y) {
+            Some((x, y)) => {
+                self.set(x, y, cell);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // On the board with nothing there, room for a block.
+    pub fn is_free(&self, x: i16, y: i16) -> bool {
+        self.on_board(x, y)
+            .is_some_and(|(x, y)| self.masks[y] & (1 << x) == 0)
+    }
+
+    // Every block of `t` on the board with nothing there already. Past the
+    // end of an i16 is as off the board as it gets.
+    pub fn fits(&self, t: &Tetromino) -> bool {
+        t.checked_cells()
+            .is_some_and(|cells| cells.iter().all(|b| self.is_free(b.x, b.y)))
+    }
+
+    // Move `t` by `offset`, if it fits there. Whether it moved.
+    pub fn try_move(&self, t: &mut Tetromino, offset: Point) -> bool {
+        self.try_place(t, t.moved(offset))
+    }
+
+    // Turn `t` a quarter, kicking it over if it doesn't fit where it is.
+    // Whether it turned.
+    pub fn try_rotate(&self, t: &mut Tetromino, clockwise: bool) -> bool {
+        let turned = t.turned(clockwise);
+        for &kick in KICKS.iter() {
+            if self.try_place(t, turned.moved(kick)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    // `candidate` in place of `t`, if there is one and it fits. If not,
+    // `t` is left just as it was.
+    fn try_place(&self, t: &mut Tetromino, candidate: Option<Tetromino>) -> bool {
+        match candidate {
+            Some(candidate) if self.fits(&candidate) => {
+                *t = candidate;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[Cell] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    // Top to bottom.
+    pub fn rows(&self) -> ChunksExact<'_, Cell> {
+        self.cells.chunks_exact(self.width.max(1))
+    }
+
+    // All of row `y` is `cell`.
+    #[cfg(test)]
+    pub fn fill_row(&mut self, y: usize, cell: Cell) {
+        let w = self.width;
+        self.cells[y * w..(y + 1) * w].fill(cell);
+        self.masks[y] = match cell {
+            Cell::Empty => 0,
+            _ => self.full,
+        };
+        self.check();
+    }
+
+    // Row `y` is `row`.
+    pub fn set_row(&mut self, y: usize, row: &[Cell]) {
+        let w = self.width;
+        self.cells[y * w..(y + 1) * w].copy_from_slice(row);
+        self.masks[y] = mask(row);
+        self.check();
+    }
+
+    pub fn is_full(&self, y: usize) -> bool {
+        self.masks[y] == self.full
+    }
+
+    // Take out the full rows, everything over them comes down and empty
+    // rows fill in at the top. How many went.
+    pub fn remove_full_rows(&mut self) -> usize {
+        // From the bottom up, each row that stays is copied down to `to`.
+        let w = self.width;
+        let mut to = self.height;
+        for y in (0..self.height).rev() {
+            if self.is_full(y) {
+                continue;
+            }
+            to -= 1;
+            if to != y {
+                self.cells.copy_within(y * w..(y + 1) * w, to * w);
+                self.masks[to] = self.masks[y];
+            }
+        }
+        self.cells[..to * w].fill(Cell::Empty);
+        self.masks[..to].fill(0);
+        self.check();
+        to
+    }
+
+    // Everything up `rows` rows, with that many copies of `row` coming in at
+    // the bottom. The top `rows` rows go off the top.
+    pub fn push_up(&mut self, rows: usize, row: &[Cell]) {
+        let rows = rows.min(self.height);
+        self.cells.copy_within(rows * self.width.., 0);
+        self.masks.copy_within(rows.., 0);
+        for y in self.height - rows..self.height {
+            self.set_row(y, row);
+        }
+        self.check();
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::Empty);
+        self.masks.fill(0);
+    }
+
+    // A cheap hash of which cells are taken, never mind by what. Two boards
+    // with the same stack come out the same, so the AI can tell it's seen
+    // one before.
+    pub fn fingerprint(&self) -> u64 {
+        // FNV-1a, a row at a time.
+        self.masks
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, &mask| {
+                (hash ^ mask as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    #[cfg(test)]
+    pub fn mask(&self, y: usize) -> u16 {
+        self.masks[y]
+    }
+
+    fn on_board(&self, x: i16, y: i16) -> Option<(usize, usize)> {
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    // The masks are what the cells say they are.
+    fn check(&self) {
+        debug_assert!(
+            self.rows().map(mask).eq(self.masks.iter().copied()),
+            "row masks out of step with the cells"
+        );
+    }
+}
+
+impl Index<usize> for Board {
+    type Output = [Cell];
+
+    fn index(&self, y: usize) -> &[Cell] {
+        self.row(y)
+    }
+}
+
+// Which of `row`'s cells are taken.
+fn mask(row: &[Cell]) -> u16 {
+    row.iter()
+        .enumerate()
+        .filter(|&(_, &cell)| cell != Cell::Empty)
+        .fold(0, |mask, (x, _)| mask | 1 << x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Shape;
+
+    // Rows of '#' and '.', top first.
+    fn board(rows: &[&str]) -> Board {
+        let mut board = Board::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    board.set(x, y, Cell::Filled(Shape::O));
+                }
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn off_the_board_is_not_free() {
+        let board = board(&["#..", "..."]);
+        assert!(!board.is_free(0, 0));
+        assert!(board.is_free(1, 0));
+        assert!(board.is_free(2, 1));
+        assert!(!board.is_free(-1, 0));
+        assert!(!board.is_free(3, 0));
+        assert!(!board.is_free(0, 2));
+        assert!(!board.is_free(1, -1));
+    }
+
+    #[test]
+    fn checked_access_is_none_off_the_board() {
+        let mut board = board(&["#..", "..."]);
+        assert_eq!(board.get(0, 0), Cell::Filled(Shape::O));
+        assert_eq!(board.get_checked(0, 0), Some(Cell::Filled(Shape::O)));
+        assert_eq!(board.get_checked(2, 1), Some(Cell::Empty));
+        for (x, y) in [(-1, 0), (3, 0), (0, 2), (1, -1), (i16::MAX, i16::MIN)] {
+            assert_eq!(board.get_checked(x, y), None);
+            assert!(!board.try_set(x, y, Cell::Garbage));
+        }
+        assert_eq!(board, self::board(&["#..", "..."]));
+
+        assert!(board.try_set(2, 1, Cell::Garbage));
+        assert_eq!(board.get(2, 1), Cell::Garbage);
+        assert_eq!(board.mask(1), 0b100);
+    }
+
+    #[test]
+    fn pieces_only_go_where_they_fit() {
+        let board = board(&["....", "....", "....", "...#"]);
+        // Upright T, pivot at (0, 1):
+        //     #
+        //     ##
+        //     #
+        let mut t = Tetromino::of(Shape::T);
+        let start = t.clone();
+        assert!(board.fits(&t));
+
+        // Wall to the left, and no room to turn either.
+        assert!(!board.try_move(&mut t, Point::LEFT));
+        assert!(!board.try_rotate(&mut t, true));
+        assert_eq!(t, start);
+
+        assert!(board.try_move(&mut t, Point::RIGHT));
+        assert!(board.try_rotate(&mut t, true));
+        assert_eq!((t.pos, t.rotation), (Point::new(1, 1), 1));
+
+        // Down to the floor, and the block on the right is in the way.
+        assert!(board.try_move(&mut t, Point::DOWN));
+        assert!(board.try_move(&mut t, Point::DOWN));
+        assert!(!board.try_move(&mut t, Point::DOWN));
+        assert!(!board.try_move(&mut t, Point::RIGHT));
+        assert_eq!(t.pos, Point::new(1, 3));
+    }
+
+    #[test]
+    fn rejected_moves_leave_the_piece_as_it_was() {
+        let board = board(&["....", "....", "#...", "...."]);
+        let upright = |x, y| Tetromino {
+            pos: Point::new(x, y),
+            ..Tetromino::of(Shape::I)
+        };
+
+        // Into the block, off the top, into the wall turning, and past the
+        // end of an i16 every way.
+        let near = upright(1, 1);
+        let far = upright(i16::MAX, i16::MIN + 1);
+        assert!(board.fits(&near) && !board.fits(&far));
+        let moves = [
+            (&near, Point::LEFT),
+            (&near, Point::new(0, -1)),
+            (&far, Point::RIGHT),
+            (&far, Point::new(0, -2)),
+        ];
+        for (piece, offset) in moves {
+            let mut t = piece.clone();
+            assert!(!board.try_move(&mut t, offset));
+            assert_eq!(&t, piece);
+        }
+        for piece in [&near, &far] {
+            let mut t = piece.clone();
+            assert!(!board.try_rotate(&mut t, false));
+            assert_eq!(&t, piece);
+        }
+    }
+
+    #[test]
+    fn full_rows_go_and_the_rest_comes_down() {
+        let mut board = board(&["#..", "###", ".#.", "###", "#.#"]);
+        assert_eq!(board.remove_full_rows(), 2);
+        assert_eq!(board, self::board(&["...", "...", "#..", ".#.", "#.#"]));
+        assert_eq!(board.remove_full_rows(), 0);
+    }
+
+    #[test]
+    fn push_up_drops_the_top() {
+        let mut board = board(&["#..", ".#.", "..#"]);
+        let garbage = [Cell::Empty, Cell::Garbage, Cell::Garbage];
+        board.push_up(2, &garbage);
+        let mut want = self::board(&["..#", "...", "..."]);
+        want.set_row(1, &garbage);
+        want.set_row(2, &garbage);
+        assert_eq!(board, want);
+        assert_eq!(board.mask(1), 0b110);
+    }
+
+    #[test]
+    fn masks_follow_the_cells() {
+        let mut board = board(&["#..", ".#.", "###"]);
+        assert_eq!(
+            [board.mask(0), board.mask(1), board.mask(2)],
+            [0b001, 0b010, 0b111]
+        );
+        assert!(board.is_full(2));
+
+        board.set(1, 2, Cell::Empty);
+        assert_eq!(board.mask(2), 0b101);
+        assert!(!board.is_full(2));
+        board.fill_row(0, Cell::Empty);
+        assert_eq!(board.mask(0), 0);
+
+        // The widest board still has room for its full row.
+        let mut wide = Board::new(MAX_WIDTH, 1);
+        wide.fill_row(0, Cell::Garbage);
+        assert!(wide.is_full(0));
+        assert_eq!(wide.mask(0), u16::MAX);
+    }
+
+    #[test]
+    fn fingerprint_is_the_stack() {
+        let a = board(&["#..", ".##"]);
+        let mut b = board(&["#..", ".##"]);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        // What's there doesn't matter, only that something is.
+        b.set(0, 0, Cell::Garbage);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        b.set(0, 1, Cell::Garbage);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+}
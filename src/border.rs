@@ -0,0 +1,77 @@
+// Board borders.
+// The frame around the board, one set of box drawing chars per style. Every
+// piece is one column wide. No border at all is None rather than a style, the
+// board just moves over into the space the frame took.
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Border {
+    pub horizontal: &'static str,
+    pub vertical: &'static str,
+    pub top_left: &'static str,
+    pub top_right: &'static str,
+    pub bottom_left: &'static str,
+    pub bottom_right: &'static str,
+}
+
+pub const SINGLE: Border = Border {
+    horizontal: "─",
+    vertical: "│",
+    top_left: "┌",
+    top_right: "┐",
+    bottom_left: "└",
+    bottom_right: "┘",
+};
+
+pub const DOUBLE: Border = Border {
+    horizontal: "═",
+    vertical: "║",
+    top_left: "╔",
+    top_right: "╗",
+    bottom_left: "╚",
+    bottom_right: "╝",
+};
+
+pub const HEAVY: Border = Border {
+    horizontal: "━",
+    vertical: "┃",
+    top_left: "┏",
+    top_right: "┓",
+    bottom_left: "┗",
+    bottom_right: "┛",
+};
+
+// Single with the corners rounded off.
+pub const ROUNDED: Border = Border {
+    horizontal: "─",
+    vertical: "│",
+    top_left: "╭",
+    top_right: "╮",
+    bottom_left: "╰",
+    bottom_right: "╯",
+};
+
+// For fonts without box drawing chars.
+pub const ASCII: Border = Border {
+    horizontal: "-",
+    vertical: "|",
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+};
+
+pub const NAMES: [&str; 6] = ["single", "double", "heavy", "rounded", "ascii", "none"];
+
+// A style by name. "none" is no border.
+pub fn by_name(name: &str) -> Result<Option<Border>, Error> {
+    match name {
+        "single" => Ok(Some(SINGLE)),
+        "double" => Ok(Some(DOUBLE)),
+        "heavy" => Ok(Some(HEAVY)),
+        "rounded" => Ok(Some(ROUNDED)),
+        "ascii" => Ok(Some(ASCII)),
+        "none" => Ok(None),
+        _ => Err(Error::UnknownBorder(name.to_string())),
+    }
+}
@@ -0,0 +1,178 @@
+// Config file.
+// $XDG_CONFIG_HOME/tetris/config, `key = value` a line, # for comments:
+//
+//     theme = pastel
+//     color.L = 255, 140, 0
+//     color.ghost = light_black
+//     colors = 256
+//     emoji = off
+//     scale = 1
+//     hint = off
+//     ascii = on
+//     title = on
+//     countdown = off
+//     ghost = off
+//     hold = off
+//     snapshot = ~/tetris.png
+//     empty_glyph = ". "
+//     soft_drop = 2
+//
+// `theme` picks the preset to start from, `color.*` changes single colors on
+// top of it (see Theme::set for the keys). `colors` is what the terminal can
+// do, for when the guess is wrong (see ColorDepth). `emoji`, `hint` (the
+// keys help under the board), `ascii` (nothing else on screen), `title`
+// (score in the terminal's title), `countdown` (3-2-1 before a game),
+// `ghost` and `hold` are on or off. Both off plays it the old way.
+// `scale` is the cell size, 1 or 2, or auto for 2 on a big enough terminal.
+// `snapshot` is where to save the board as a PNG when a game ends, a ~ at
+// the start being $HOME.
+// `empty_glyph` is what empty cells look like, two columns wide. It can be
+// in double quotes, for a space at either end.
+// `soft_drop` is the points for each row a soft drop goes down, 0 for none.
+// Flags on the command line win over the file.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::glyphs;
+use crate::theme::{ColorDepth, Theme};
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Config {
+    // None if the file doesn't touch the theme.
+    pub theme: Option<Theme>,
+    // None to guess.
+    pub colors: Option<ColorDepth>,
+    // None for the default.
+    pub emoji: Option<bool>,
+    pub hint: Option<bool>,
+    pub ascii: Option<bool>,
+    pub title: Option<bool>,
+    pub countdown: Option<bool>,
+    pub ghost: Option<bool>,
+    pub hold: Option<bool>,
+    // None if the file doesn't say. Some(None) is auto.
+    pub scale: Option<Option<u16>>,
+    pub snapshot: Option<PathBuf>,
+    pub empty_glyph: Option<String>,
+    pub soft_drop: Option<u32>,
+}
+
+impl Config {
+    // The config file, or the defaults if there isn't one.
+    pub fn load() -> Result<Self, Error> {
+        match default_path().and_then(|path| fs::read_to_string(path).ok()) {
+            Some(contents) => Self::parse(&contents),
+            None => Ok(Self::default()),
+        }
+    }
+
+    // Errors say which line (from 1) they came from.
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        let mut config = Self::default();
+
+        // The theme goes first wherever it is in the file, the colors are
+        // changes to it.
+        let mut colors = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let at_line = |error| Error::Config {
+                line: i + 1,
+                error: Box::new(error),
+            };
+
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(at_line(Error::UnknownSetting(line.to_string()))),
+            };
+
+            match key {
+                "theme" => config.theme = Some(Theme::preset(value).map_err(at_line)?),
+                "colors" => config.colors = Some(ColorDepth::by_name(value).map_err(at_line)?),
+                "emoji" => config.emoji = Some(on_off(value).map_err(at_line)?),
+                "hint" => config.hint = Some(on_off(value).map_err(at_line)?),
+                "ascii" => config.ascii = Some(on_off(value).map_err(at_line)?),
+                "title" => config.title = Some(on_off(value).map_err(at_line)?),
+                "countdown" => config.countdown = Some(on_off(value).map_err(at_line)?),
+                "ghost" => config.ghost = Some(on_off(value).map_err(at_line)?),
+                "hold" => config.hold = Some(on_off(value).map_err(at_line)?),
+                "scale" => config.scale = Some(scale_by_name(value).map_err(at_line)?),
+                "snapshot" => config.snapshot = Some(expand_home(value)),
+                "empty_glyph" => config.empty_glyph = Some(glyph(value).map_err(at_line)?),
+                "soft_drop" => config.soft_drop = Some(number(value).map_err(at_line)?),
+                _ => match key.strip_prefix("color.") {
+                    Some(name) => colors.push((i + 1, name, value)),
+                    None => return Err(at_line(Error::UnknownSetting(key.to_string()))),
+                },
+            }
+        }
+
+        for (line, name, value) in colors {
+            config
+                .theme
+                .get_or_insert_with(Theme::default)
+                .set(name, value)
+                .map_err(|error| Error::Config {
+                    line,
+                    error: Box::new(error),
+                })?;
+        }
+
+        Ok(config)
+    }
+}
+
+// auto, 1 or 2. None is auto. --scale takes the same.
+pub fn scale_by_name(value: &str) -> Result<Option<u16>, Error> {
+    match value {
+        "auto" => Ok(None),
+        "1" => Ok(Some(1)),
+        "2" => Ok(Some(2)),
+        _ => Err(Error::UnknownScale(value.to_string())),
+    }
+}
+
+fn on_off(value: &str) -> Result<bool, Error> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(Error::NotOnOff(value.to_string())),
+    }
+}
+
+fn number(value: &str) -> Result<u32, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::NotANumber(value.to_string()))
+}
+
+// A glyph, quotes and all taken off, if it's fit for a cell.
+fn glyph(value: &str) -> Result<String, Error> {
+    let glyph = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    glyphs::check(glyph)?;
+    Ok(glyph.to_string())
+}
+
+// ~/ at the start of a path is $HOME. The shell isn't there to do it.
+fn expand_home(value: &str) -> PathBuf {
+    match (value.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(value),
+    }
+}
+
+// $XDG_CONFIG_HOME/tetris/config, falling back to ~/.config.
+fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(dir.join("tetris").join("config"))
+}
@@ -0,0 +1,123 @@
+// Frame timing, for the debug overlay.
+// The last second of frames: how far apart they came, how long update and
+// render took in each, and how much went out to the terminal. Recorded every
+// frame whether the overlay is up or not, it's a few adds.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    // Since the frame before, sleep included.
+    dt: Duration,
+    update: Duration,
+    render: Duration,
+    bytes: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    frames: VecDeque<Frame>,
+    // The dts in `frames` added up.
+    span: Duration,
+}
+
+impl FrameStats {
+    pub fn record(&mut self, dt: Duration, update: Duration, render: Duration, bytes: u64) {
+        self.frames.push_back(Frame {
+            dt,
+            update,
+            render,
+            bytes,
+        });
+        self.span += dt;
+
+        // Keep the last one however long it took, or a slow frame empties
+        // the lot.
+        while self.span > WINDOW && self.frames.len() > 1 {
+            let old = self.frames.pop_front().unwrap();
+            self.span -= old.dt;
+        }
+    }
+
+    pub fn fps(&self) -> f64 {
+        match self.span.is_zero() {
+            true => 0.0,
+            false => self.frames.len() as f64 / self.span.as_secs_f64(),
+        }
+    }
+
+    // Time spent working (update and render) per frame, on average and at
+    // worst. The rest is sleeping.
+    pub fn avg_work(&self) -> Duration {
+        self.average(|frame| frame.update + frame.render)
+    }
+
+    pub fn max_work(&self) -> Duration {
+        self.frames
+            .iter()
+            .map(|frame| frame.update + frame.render)
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn avg_update(&self) -> Duration {
+        self.average(|frame| frame.update)
+    }
+
+    pub fn avg_render(&self) -> Duration {
+        self.average(|frame| frame.render)
+    }
+
+    pub fn avg_bytes(&self) -> u64 {
+        match self.frames.len() as u64 {
+            0 => 0,
+            n => self.frames.iter().map(|frame| frame.bytes).sum::<u64>() / n,
+        }
+    }
+
+    fn average(&self, of: impl Fn(&Frame) -> Duration) -> Duration {
+        match self.frames.len() as u32 {
+            0 => Duration::ZERO,
+            n => self.frames.iter().map(of).sum::<Duration>() / n,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn nothing_yet() {
+        let stats = FrameStats::default();
+        assert_eq!(stats.fps(), 0.0);
+        assert_eq!(stats.max_work(), Duration::ZERO);
+        assert_eq!(stats.avg_bytes(), 0);
+    }
+
+    #[test]
+    fn keeps_the_last_second() {
+        let mut stats = FrameStats::default();
+        stats.record(ms(20), ms(9), ms(9), 5000);
+        for _ in 0..50 {
+            stats.record(ms(20), ms(1), ms(2), 100);
+        }
+
+        // The slow first frame has gone out the back.
+        assert_eq!(stats.fps(), 50.0);
+        assert_eq!(stats.max_work(), ms(3));
+        assert_eq!(stats.avg_update(), ms(1));
+        assert_eq!(stats.avg_render(), ms(2));
+        assert_eq!(stats.avg_bytes(), 100);
+
+        // One long frame is still a frame.
+        stats.record(ms(1500), ms(1), ms(1), 0);
+        assert_eq!(stats.frames.len(), 1);
+    }
+}
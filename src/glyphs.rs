@@ -0,0 +1,162 @@
+// Cell glyphs.
+// What a filled cell, an empty cell and the ghost piece look like. Every cell
+// on screen is two columns, so every glyph has to be exactly two columns too
+// or the whole row slides over.
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyphs {
+    filled: String,
+    empty: String,
+    ghost: String,
+}
+
+// Colorblind mode: a glyph for each shape, in Shape::ALL order, so S and Z
+// (or I and J) don't come down to red vs green.
+pub const PER_SHAPE: [&str; 7] = ["[]", "##", "()", "{}", "<>", "%%", "@@"];
+
+// Presets, by name.
+pub const PRESETS: [&str; 3] = ["brackets", "solid", "shaded"];
+
+impl Glyphs {
+    // Custom glyphs. Each one has to be two columns wide.
+    pub fn new(filled: &str, empty: &str, ghost: &str) -> Result<Self, Error> {
+        for glyph in [filled, empty, ghost] {
+            check(glyph)?;
+        }
+
+        Ok(Self {
+            filled: filled.to_string(),
+            empty: empty.to_string(),
+            ghost: ghost.to_string(),
+        })
+    }
+
+    // One of the PRESETS.
+    pub fn preset(name: &str) -> Result<Self, Error> {
+        match name {
+            "brackets" => Self::new("[]", "· ", "::"),
+            "solid" => Self::new("██", "· ", "░░"),
+            "shaded" => Self::new("▒▒", "· ", "░░"),
+            _ => Err(Error::UnknownGlyphs(name.to_string())),
+        }
+    }
+
+    // The same, with empty cells left blank rather than dotted.
+    pub fn without_grid(&self) -> Self {
+        self.with_empty("  ")
+    }
+
+    // The same, with `empty` for empty cells. Already checked, see check().
+    pub fn with_empty(&self, empty: &str) -> Self {
+        Self {
+            empty: empty.to_string(),
+            ..self.clone()
+        }
+    }
+
+    // ASCII only, for terminals and fonts with nothing else: any glyph
+    // that isn't goes back to the brackets preset's.
+    pub fn ascii(&self) -> Self {
+        let keep = |glyph: &str, plain: &str| match glyph.is_ascii() {
+            true => glyph.to_string(),
+            false => plain.to_string(),
+        };
+        Self {
+            filled: keep(&self.filled, "[]"),
+            empty: keep(&self.empty, ". "),
+            ghost: keep(&self.ghost, "::"),
+        }
+    }
+
+    pub fn filled(&self) -> &str {
+        &self.filled
+    }
+
+    pub fn empty(&self) -> &str {
+        &self.empty
+    }
+
+    pub fn ghost(&self) -> &str {
+        &self.ghost
+    }
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Self::preset("brackets").unwrap()
+    }
+}
+
+// Ok if `glyph` is two columns wide, fit to be a cell.
+pub fn check(glyph: &str) -> Result<(), Error> {
+    match display_width(glyph) {
+        Some(2) => Ok(()),
+        _ => Err(Error::GlyphWidth(glyph.to_string())),
+    }
+}
+
+// `s` with everything the game draws that isn't ASCII swapped for
+// something that is, as many columns wide. Anything else not ASCII is a `?`.
+pub fn to_ascii(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_ascii() => out.push(c),
+            '·' => out.push('.'),
+            '←' => out.push('<'),
+            '→' => out.push('>'),
+            '↑' => out.push('^'),
+            '↓' => out.push('v'),
+            '—' => out.push('-'),
+            '¦' => out.push('|'),
+            '█' => out.push('#'),
+            '▉'..='▏' | '░' => out.push('-'),
+            // The frowny, as wide as the emoji.
+            '☹' => {
+                chars.next_if_eq(&'\u{fe0f}');
+                out.push_str(":(");
+            }
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+// Columns `s` takes up in a terminal, or None if it has control chars, which
+// move the cursor around rather than take up room.
+// Not the full Unicode width tables, but it knows the usual suspects: wide
+// CJK and emoji are two, combining marks and variation selectors are zero,
+// everything else (box drawing and block elements included) is one. Except
+// that a char asking to be drawn as an emoji (U+FE0F after it, like ☹️) is
+// two, it gets drawn that way by most terminals.
+pub fn display_width(s: &str) -> Option<usize> {
+    let mut chars = s.chars().peekable();
+    let mut width = 0;
+    while let Some(c) = chars.next() {
+        width += match chars.peek() {
+            Some('\u{fe0f}') => char_width(c)?.max(2),
+            _ => char_width(c)?,
+        };
+    }
+    Some(width)
+}
+
+fn char_width(c: char) -> Option<usize> {
+    match c as u32 {
+        0x00..=0x1f | 0x7f..=0x9f => None,
+        0x0300..=0x036f | 0x200b..=0x200f | 0xfe00..=0xfe0f => Some(0),
+        0x1100..=0x115f
+        | 0x2e80..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => Some(2),
+        _ => Some(1),
+    }
+}
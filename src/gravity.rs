@@ -0,0 +1,176 @@
+// Gravity after a line clear.
+// Naive is the usual: the rows above a cleared line come down by one, holes
+// and overhangs and all. Cascade (sticky) lets each lump of blocks fall on its
+// own until it lands on something, which can fill more lines, which clear as
+// a chain.
+// Ref: https://tetris.wiki/Line_clear
+use crate::board::Board;
+use crate::{Cell, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Gravity {
+    #[default]
+    Naive,
+    Cascade,
+}
+
+impl Gravity {
+    pub const NAMES: [&'static str; 2] = ["naive", "cascade"];
+
+    pub fn by_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "naive" => Ok(Gravity::Naive),
+            "cascade" => Ok(Gravity::Cascade),
+            _ => Err(Error::UnknownGravity(name.to_string())),
+        }
+    }
+}
+
+// Drop every lump of blocks (cells touching side to side or up and down) as
+// far as it goes, lowest first, until nothing moves. True if anything did.
+pub(crate) fn settle(board: &mut Board) -> bool {
+    let mut moved = false;
+    loop {
+        let mut groups = groups(board);
+        // Bottom ones first, so what's on top of them has somewhere to go.
+        groups.sort_by_key(|group| std::cmp::Reverse(group.iter().map(|&(_, y)| y).max()));
+
+        let mut any = false;
+        for group in groups {
+            let mut group = group;
+            while can_fall(board, &group) {
+                let cells: Vec<Cell> = group.iter().map(|&(x, y)| board.get(x, y)).collect();
+                for &(x, y) in &group {
+                    board.set(x, y, Cell::Empty);
+                }
+                for (&(x, y), &cell) in group.iter().zip(&cells) {
+                    board.set(x, y + 1, cell);
+                }
+                for block in group.iter_mut() {
+                    block.1 += 1;
+                }
+                any = true;
+            }
+        }
+        // Lumps that landed on each other are one lump now, go again.
+        if !any {
+            return moved;
+        }
+        moved = true;
+    }
+}
+
+// The lumps on the board, each a list of (x, y).
+fn groups(board: &Board) -> Vec<Vec<(usize, usize)>> {
+    let (width, height) = (board.width(), board.height());
+    let mut seen = vec![vec![false; width]; height];
+    let mut groups = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if seen[y][x] || board.get(x, y) == Cell::Empty {
+                continue;
+            }
+            // Flood fill from here.
+            let mut group = Vec::new();
+            let mut todo = vec![(x, y)];
+            seen[y][x] = true;
+            while let Some((x, y)) = todo.pop() {
+                group.push((x, y));
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx < width
+                        && ny < height
+                        && !seen[ny][nx]
+                        && board.get(nx, ny) != Cell::Empty
+                    {
+                        seen[ny][nx] = true;
+                        todo.push((nx, ny));
+                    }
+                }
+            }
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+// Room under every block of the lump, bar where it's sitting on itself.
+fn can_fall(board: &Board, group: &[(usize, usize)]) -> bool {
+    group.iter().all(|&(x, y)| {
+        y + 1 < board.height()
+            && (board.get(x, y + 1) == Cell::Empty || group.contains(&(x, y + 1)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Shape;
+
+    // Rows of '#' and '.', top first.
+    fn board(rows: &[&str]) -> Board {
+        let mut board = Board::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    board.set(x, y, Cell::Filled(Shape::O));
+                }
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn lumps_fall_on_their_own() {
+        let mut cells = board(&[
+            "##..", //
+            ".#..", //
+            "....", //
+            "..#.", //
+            "#.##",
+        ]);
+        assert!(settle(&mut cells));
+        assert_eq!(
+            cells,
+            board(&[
+                "....", //
+                "....", //
+                "....", //
+                "###.", //
+                "####",
+            ])
+        );
+
+        // All one lump now, and it's on the floor.
+        assert!(!settle(&mut cells));
+    }
+
+    #[test]
+    fn lumps_keep_their_shape() {
+        // The T comes down as one, the holes under its arms stay holes.
+        let mut cells = board(&[
+            "###.", //
+            ".#..", //
+            "....", //
+            "....", //
+            "...#",
+        ]);
+        settle(&mut cells);
+        assert_eq!(
+            cells,
+            board(&[
+                "....", //
+                "....", //
+                "....", //
+                "###.", //
+                ".#.#",
+            ])
+        );
+    }
+}
@@ -0,0 +1,227 @@
+// High scores.
+// The top scores with who got them, when and in what mode, kept in a plain
+// text file under the user's data dir. One `score<TAB>date<TAB>mode<TAB>name`
+// per line, best first. Older `score<TAB>name` files still load, just without
+// dates and modes.
+//
+// Read and written by hand rather than with serde: the crate builds with
+// rand and termion alone, and a line per score is all the table needs.
+use std::cmp::Reverse;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// How many scores make the table.
+pub const MAX_ENTRIES: usize = 10;
+
+pub struct Entry {
+    pub name: String,
+    pub score: i64,
+    // YYYY-MM-DD, UTC. Empty for scores from before dates were kept.
+    pub date: String,
+    // Empty for scores from before modes were kept.
+    pub mode: String,
+}
+
+pub struct HighScores {
+    // Where the table lives. None keeps it in memory only (tests).
+    path: Option<PathBuf>,
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    // Table that is never written anywhere.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Vec::new(),
+        }
+    }
+
+    // Load the table from the user's data dir. A missing file is just an
+    // empty table.
+    pub fn load() -> Self {
+        Self::load_from(default_path())
+    }
+
+    // The daily challenge's table for `date`, one file a day next to the
+    // usual table.
+    pub fn load_daily(date: &str) -> Self {
+        let path = default_path().and_then(|path| Some(path.parent()?.join("daily").join(date)));
+        Self::load_from(path)
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| parse(&contents))
+            .unwrap_or_default();
+
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    // Whether `score` would make the table.
+    pub fn qualifies(&self, score: i64) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    // Add a score from today, keeping the table sorted and trimmed. Returns
+    // the rank it landed at, if it made it.
+    pub fn insert(&mut self, name: &str, score: i64, mode: &str) -> Option<usize> {
+        if !self.qualifies(score) {
+            return None;
+        }
+
+        // Ties go below the existing entries. First come, first served.
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            rank,
+            Entry {
+                name: name.to_string(),
+                score,
+                date: today(),
+                mode: mode.to_string(),
+            },
+        );
+        self.entries.truncate(MAX_ENTRIES);
+
+        Some(rank)
+    }
+
+    // Write the table back.
+    pub fn save(&self) -> io::Result<()> {
+        let path = match self.path.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{}\t{}\t{}\t{}\n",
+                    entry.score, entry.date, entry.mode, entry.name
+                )
+            })
+            .collect();
+        fs::write(path, contents)
+    }
+}
+
+// $XDG_DATA_HOME/tetris/highscores, falling back to ~/.local/share.
+fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+
+    Some(dir.join("tetris").join("highscores"))
+}
+
+// Lines that don't parse are skipped rather than failing the whole table, so
+// a mangled file is at worst an emptier table.
+fn parse(contents: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = contents
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(4, '\t').collect();
+            let (score, date, mode, name) = match fields[..] {
+                [score, date, mode, name] => (score, date, mode, name),
+                [score, name] => (score, "", "", name),
+                _ => return None,
+            };
+            Some(Entry {
+                name: name.to_string(),
+                score: score.parse().ok()?,
+                date: date.to_string(),
+                mode: mode.to_string(),
+            })
+        })
+        .collect();
+
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    entries.truncate(MAX_ENTRIES);
+    entries
+}
+
+// Today's date as YYYY-MM-DD, UTC.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Days since 1970-01-01 to a (year, month, day).
+// Ref: https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_old_and_new_lines_and_skips_junk() {
+        let entries = parse("120\tann\n\n300\t2026-10-15\tMarathon\tbob\nnope\tx\n200\n");
+
+        let rows: Vec<_> = entries
+            .iter()
+            .map(|e| (e.score, e.name.as_str(), e.date.as_str(), e.mode.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            [(300, "bob", "2026-10-15", "Marathon"), (120, "ann", "", ""),]
+        );
+    }
+
+    #[test]
+    fn insert_keeps_the_top_entries() {
+        let mut scores = HighScores::in_memory();
+        for score in 1..=MAX_ENTRIES as i64 {
+            scores.insert("a", score * 10, "Marathon");
+        }
+
+        assert!(!scores.qualifies(10));
+        assert_eq!(scores.insert("b", 55, "Marathon"), Some(5));
+        assert_eq!(scores.entries().len(), MAX_ENTRIES);
+        assert_eq!(scores.entries().last().unwrap().score, 20);
+        assert_eq!(scores.entries()[5].date.len(), "YYYY-MM-DD".len());
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20741), (2026, 10, 15));
+    }
+}
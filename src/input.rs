@@ -0,0 +1,63 @@
+// Where the keys come from.
+// The game only ever asks for the next key, so the keyboard can be swapped
+// for anything that can answer that, e.g. a script in tests.
+use std::collections::VecDeque;
+
+use termion::event::Key;
+use termion::input::{Keys, TermRead};
+use termion::{async_stdin, AsyncReader};
+
+pub trait InputSource {
+    // The next key pressed, or None if there isn't one right now. Never
+    // blocks.
+    fn next_key(&mut self) -> Option<Key>;
+}
+
+// The keyboard.
+pub struct StdinInput {
+    keys: Keys<AsyncReader>,
+}
+
+impl StdinInput {
+    pub fn new() -> Self {
+        Self {
+            keys: async_stdin().keys(),
+        }
+    }
+}
+
+impl Default for StdinInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputSource for StdinInput {
+    fn next_key(&mut self) -> Option<Key> {
+        match self.keys.next() {
+            Some(Ok(key)) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+// A fixed sequence of keys, one per call. A None in the script is a frame
+// without a key press. Once it runs out, nothing is pressed anymore.
+#[derive(Default)]
+pub struct ScriptedInput {
+    keys: VecDeque<Option<Key>>,
+}
+
+impl ScriptedInput {
+    pub fn new<I: IntoIterator<Item = Option<Key>>>(keys: I) -> Self {
+        Self {
+            keys: keys.into_iter().collect(),
+        }
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn next_key(&mut self) -> Option<Key> {
+        self.keys.pop_front().flatten()
+    }
+}
@@ -0,0 +1,200 @@
+// Key bindings.
+// What the keys do in play, in one table, so the game and the help line under
+// the board can't disagree. The first key of a binding is the one the help
+// shows. The ones only some modes have are in here too, the game just
+// ignores them elsewhere.
+use termion::event::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Left,
+    Right,
+    SoftDrop,
+    RotateLeft,
+    RotateRight,
+    HardDrop,
+    Hold,
+    Pause,
+    Restart,
+    Quit,
+    // Row and column numbers on and off.
+    Labels,
+    // The frame time overlay on and off.
+    Debug,
+    // Invisible: a look at the stack.
+    Reveal,
+    // Practice: take back the last piece.
+    Undo,
+    // Practice: this one of Shape::ALL next.
+    Pick(usize),
+    // Screen reader: read out the stack heights.
+    Heights,
+}
+
+impl Action {
+    // The same move on a mirrored board.
+    pub fn mirrored(self) -> Self {
+        match self {
+            Action::Left => Action::Right,
+            Action::Right => Action::Left,
+            Action::RotateLeft => Action::RotateRight,
+            Action::RotateRight => Action::RotateLeft,
+            other => other,
+        }
+    }
+}
+
+pub const BINDINGS: [(Action, &[Key]); 22] = [
+    (Action::Left, &[Key::Left, Key::Char('a')]),
+    (Action::Right, &[Key::Right, Key::Char('d')]),
+    (Action::SoftDrop, &[Key::Down, Key::Char('s')]),
+    (Action::RotateLeft, &[Key::Up, Key::Char('w')]),
+    (Action::RotateRight, &[Key::Char('e')]),
+    (Action::HardDrop, &[Key::Char(' ')]),
+    (Action::Hold, &[Key::Char('c')]),
+    (Action::Pause, &[Key::Char('p')]),
+    (Action::Restart, &[Key::Char('r')]),
+    (Action::Quit, &[Key::Char('q')]),
+    (Action::Labels, &[Key::Char('l')]),
+    (Action::Debug, &[Key::Char('F'), Key::F(3)]),
+    (Action::Reveal, &[Key::Char('v')]),
+    (Action::Undo, &[Key::Char('u')]),
+    (Action::Pick(0), &[Key::Char('1')]),
+    (Action::Pick(1), &[Key::Char('2')]),
+    (Action::Pick(2), &[Key::Char('3')]),
+    (Action::Pick(3), &[Key::Char('4')]),
+    (Action::Pick(4), &[Key::Char('5')]),
+    (Action::Pick(5), &[Key::Char('6')]),
+    (Action::Pick(6), &[Key::Char('7')]),
+    (Action::Heights, &[Key::Char('k')]),
+];
+
+// Every piece pick, for the help line.
+pub const PICKS: [Action; 7] = [
+    Action::Pick(0),
+    Action::Pick(1),
+    Action::Pick(2),
+    Action::Pick(3),
+    Action::Pick(4),
+    Action::Pick(5),
+    Action::Pick(6),
+];
+
+// What's on the help line, in order. Actions sharing an entry get their keys
+// run together, "←→ move". Heights aren't on it, screen reader mode doesn't
+// draw it.
+const HINT: [(&[Action], &str); 12] = [
+    (&[Action::Left, Action::Right], "move"),
+    (&[Action::RotateLeft], "rotate"),
+    (&[Action::SoftDrop], "soft drop"),
+    (&[Action::HardDrop], "drop"),
+    (&[Action::Hold], "hold"),
+    (&[Action::Pause], "pause"),
+    (&[Action::Quit], "quit"),
+    (&PICKS, "piece"),
+    (&[Action::Undo], "undo"),
+    (&[Action::Reveal], "reveal"),
+    (&[Action::Labels], "labels"),
+    (&[Action::Debug], "debug"),
+];
+
+// What `key` does, if anything.
+pub fn action(key: Key) -> Option<Action> {
+    BINDINGS
+        .iter()
+        .find(|(_, keys)| keys.contains(&key))
+        .map(|&(action, _)| action)
+}
+
+// The key as it's written on the help line.
+pub fn name(key: Key) -> String {
+    match key {
+        Key::Left => "←".to_string(),
+        Key::Right => "→".to_string(),
+        Key::Up => "↑".to_string(),
+        Key::Down => "↓".to_string(),
+        Key::Char(' ') => "space".to_string(),
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::F(n) => format!("F{}", n),
+        Key::Esc => "esc".to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+// "←→ move", "p pause" and so on, one per entry on the help line. Entries
+// for anything in `left_out` aren't there, they'd do nothing.
+pub fn hint(left_out: &[Action]) -> Vec<String> {
+    HINT.iter()
+        .filter(|(actions, _)| !actions.iter().all(|action| left_out.contains(action)))
+        .map(|(actions, what)| {
+            let keys: String = actions
+                .iter()
+                .filter_map(|&action| {
+                    BINDINGS
+                        .iter()
+                        .find(|&&(a, _)| a == action)
+                        .and_then(|(_, keys)| keys.first())
+                })
+                .map(|&key| name(key))
+                .collect();
+            format!("{} {}", keys, what)
+        })
+        .collect()
+}
+
+// The help entries joined up with " · ", as many lines as it takes to fit in
+// `cols`. An entry too long for a line by itself gets cut short.
+pub fn wrap(entries: &[String], cols: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for entry in entries {
+        let entry: String = entry.chars().take(cols).collect();
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 3 + entry.chars().count() <= cols => {
+                line.push_str(" · ");
+                line.push_str(&entry);
+            }
+            _ => lines.push(entry),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_comes_from_the_bindings() {
+        assert_eq!(action(Key::Char('a')), Some(Action::Left));
+        assert_eq!(action(Key::Up), Some(Action::RotateLeft));
+        assert_eq!(action(Key::Char('x')), None);
+
+        let hint = hint(&[]);
+        assert_eq!(hint[0], "←→ move");
+        assert!(hint.contains(&"space drop".to_string()));
+        assert!(hint.contains(&"c hold".to_string()));
+        assert!(hint.contains(&"q quit".to_string()));
+        assert!(!super::hint(&[Action::Hold]).contains(&"c hold".to_string()));
+
+        // The extras are bindings like any other.
+        assert_eq!(action(Key::Char('1')), Some(Action::Pick(0)));
+        assert_eq!(action(Key::Char('7')), Some(Action::Pick(6)));
+        assert_eq!(action(Key::F(3)), Some(Action::Debug));
+        assert_eq!(action(Key::Char('v')), Some(Action::Reveal));
+        assert!(hint.contains(&"1234567 piece".to_string()));
+        assert!(hint.contains(&"l labels".to_string()));
+        assert!(hint.contains(&"F debug".to_string()));
+        assert!(!super::hint(&PICKS)
+            .iter()
+            .any(|entry| entry.ends_with("piece")));
+    }
+
+    #[test]
+    fn help_wraps_to_fit() {
+        let entries = ["←→ move", "↑ rotate", "q quit"].map(String::from);
+        assert_eq!(wrap(&entries, 80), ["←→ move · ↑ rotate · q quit"]);
+        assert_eq!(wrap(&entries, 20), ["←→ move · ↑ rotate", "q quit"]);
+        assert_eq!(wrap(&entries, 5), ["←→ mo", "↑ rot", "q qui"]);
+    }
+}
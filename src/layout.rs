@@ -0,0 +1,162 @@
+// Screen layout.
+// Where everything goes on screen, worked out in one go from the board and
+// what's around it, rather than every draw adding up its own offsets. The
+// whole lot (labels, board, HUD and preview) is centered in the room there is.
+use crate::{ANNOUNCE_WIDTH, HUD_ROWS, HUD_WIDTH, PIECE_STATS_ROWS, PREVIEW_ROWS};
+
+// Columns the preview takes up: a piece is up to four cells.
+const PREVIEW_WIDTH: u16 = 8;
+
+// Rows the keys help can wrap onto.
+pub const HINT_ROWS: u16 = 2;
+
+// Everything that decides the layout.
+pub struct Room {
+    // Board size in cells.
+    pub width: usize,
+    pub height: usize,
+    pub labels: bool,
+    pub frame: bool,
+    pub preview_count: usize,
+    // The held piece goes under the preview.
+    pub hold: bool,
+    // The keys help under everything.
+    pub hint: bool,
+    // 1, or 2 for every cell at twice the size.
+    pub scale: u16,
+    // Screen column to start from, and the screen size.
+    pub origin: u16,
+    pub screen: (u16, u16),
+}
+
+// Screen positions, 1-based like Goto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Layout {
+    // The board's top-left corner (the border glyph, or where it would be).
+    pub left: u16,
+    pub top: u16,
+    // Last row the board takes up, border included.
+    pub bottom: u16,
+    // Screen columns and rows one cell takes up, and the whole board inside
+    // the frame.
+    pub cell: (u16, u16),
+    pub inner: (u16, u16),
+    // First column of the row numbers.
+    pub label_x: u16,
+    // The terminal is too narrow for the sidebar, the HUD goes under the
+    // board.
+    pub hud_below: bool,
+    // Top-left of the HUD, and of the "Next" header.
+    pub hud: (u16, u16),
+    pub preview: (u16, u16),
+    // Preview pieces there's room for, up to preview_count.
+    pub preview_shown: usize,
+    // Top-left of the "Hold" header, if there's room for it.
+    pub hold: Option<(u16, u16)>,
+    // Room for the piece stats under the sidebar HUD.
+    pub show_piece_stats: bool,
+    // First row of the keys help, if it's on and there's room for it.
+    pub hint: Option<u16>,
+    // The room there is, and the least it takes for the board and HUD with
+    // the HUD beside it. False if it doesn't fit that way or under the board
+    // either, and everything would run off the edge.
+    pub screen: (u16, u16),
+    pub need: (u16, u16),
+    pub fits: bool,
+}
+
+impl Layout {
+    pub fn new(room: &Room) -> Self {
+        let (cols, rows) = room.screen;
+        let cols = cols.saturating_sub(room.origin);
+        let frame = room.frame as u16;
+        let labels = match room.labels {
+            true => (room.height - 1).to_string().len() as u16 + 1,
+            false => 0,
+        };
+        let cell = (2 * room.scale, room.scale);
+        let inner = (room.width as u16 * cell.0, room.height as u16 * cell.1);
+        let board_cols = labels + frame * 2 + inner.0;
+        let board_rows = frame * 2 + inner.1 + room.labels as u16;
+
+        // Sidebar if it fits, with the preview right of it (clear of the
+        // announcements, those run wider than the rest) if that fits too.
+        let sidebar = HUD_WIDTH.max(ANNOUNCE_WIDTH as u16);
+        let hud_below = board_cols + 2 + HUD_WIDTH > cols;
+        let with_preview = board_cols + 2 + sidebar + 2 + PREVIEW_WIDTH;
+        let preview_fits = !hud_below && with_preview <= cols;
+        // The board and HUD have to fit one way or the other. The rest goes
+        // where there's room left over.
+        let beside = (board_cols + 2 + HUD_WIDTH, board_rows.max(HUD_ROWS + 1));
+        let under = (board_cols.max(HUD_WIDTH + 1), board_rows + HUD_ROWS + 1);
+        let big_enough = [beside, under]
+            .iter()
+            .any(|&(need_cols, need_rows)| need_cols <= cols && need_rows <= rows);
+
+        let (block_cols, block_rows) = match (hud_below, preview_fits) {
+            (true, _) => (board_cols, board_rows + HUD_ROWS + 1),
+            (false, true) => (with_preview, board_rows),
+            (false, false) => (board_cols + 2 + sidebar, board_rows),
+        };
+
+        // The keys help goes under all that, if there are rows to spare.
+        let hint_rows = match room.hint && block_rows + HINT_ROWS <= rows {
+            true => HINT_ROWS,
+            false => 0,
+        };
+
+        let x = room.origin + cols.saturating_sub(block_cols) / 2;
+        let y = rows.saturating_sub(block_rows + hint_rows) / 2;
+        let hint = (hint_rows > 0).then_some(y + block_rows + 1);
+        let left = x + labels + frame;
+        let top = y + frame;
+        let bottom = top + inner.1 + frame;
+
+        let hud = match hud_below {
+            // Skip the bottom border, and the column labels if they are on.
+            true => (left + 1, bottom + 1 + room.labels as u16),
+            false => (left + inner.0 + 4, top + 1),
+        };
+        let preview = (hud.0 + sidebar + 2, hud.1);
+
+        // Whatever runs down the side stops short of the keys help.
+        let last_row = hint.map_or(rows, |hint| hint - 1);
+
+        // The piece stats go under the sidebar, so they need the sidebar and
+        // the rows for it.
+        let stats_bottom = hud.1 + HUD_ROWS + PIECE_STATS_ROWS;
+        let show_piece_stats = !hud_below && stats_bottom <= last_row;
+
+        // As many preview pieces as there are rows for. The held piece
+        // takes the place of one, as long as that leaves the next one.
+        let fits = match preview_fits && preview.1 + PREVIEW_ROWS <= last_row {
+            true => ((last_row - preview.1 - 1) / PREVIEW_ROWS) as usize,
+            false => 0,
+        };
+        let hold_shown = room.hold && fits >= 2;
+        let preview_shown = room.preview_count.min(fits - hold_shown as usize);
+        let hold = hold_shown.then_some((
+            preview.0,
+            preview.1 + 1 + preview_shown as u16 * PREVIEW_ROWS,
+        ));
+
+        Self {
+            left,
+            top,
+            bottom,
+            cell,
+            inner,
+            label_x: x + 1,
+            hud_below,
+            hud,
+            preview,
+            preview_shown,
+            hold,
+            show_piece_stats,
+            hint,
+            screen: (cols, rows),
+            need: beside,
+            fits: big_enough,
+        }
+    }
+}
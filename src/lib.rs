@@ -1,36 +1,169 @@
-use std::io::{self, Stdout, Write};
+use std::collections::VecDeque;
+use std::io::{self, Write};
 use std::time::{Duration, Instant};
-use std::{ops, thread};
+use std::{error, fmt, thread};
 
 use rand::prelude::*;
 use termion::event::Key;
-use termion::input::{Keys, TermRead};
-use termion::raw::{IntoRawMode, RawTerminal};
-use termion::{async_stdin, clear, color, cursor, style, AsyncReader};
-
-/// The upper and lower boundary char.
-const HORZ_BOUNDARY: &'static str = "─";
-/// The left and right boundary char.
-const VERT_BOUNDARY: &'static str = "│";
-
-/// The top-left corner
-const TOP_LEFT_CORNER: &'static str = "┌";
-/// The top-right corner
-const TOP_RIGHT_CORNER: &'static str = "┐";
-/// The bottom-left corner
-const BOTTOM_LEFT_CORNER: &'static str = "└";
-/// The bottom-right corner
-const BOTTOM_RIGHT_CORNER: &'static str = "┘";
-
-/// The empty cell
-const EMPTY_CELL: &'static str = "· ";
+
+mod ai;
+mod analysis;
+pub mod asciicast;
+mod board;
+pub mod border;
+pub mod config;
+mod frame_stats;
+pub mod glyphs;
+pub mod gravity;
+pub mod highscore;
+pub mod input;
+pub mod keys;
+mod layout;
+pub mod log;
+mod narrator;
+pub mod randomizer;
+pub mod renderer;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+pub mod stats_csv;
+mod terminal;
+pub mod testing;
+pub mod theme;
+pub mod versus;
+
+use ai::Ai;
+use asciicast::Asciicast;
+use board::Board;
+use border::Border;
+use frame_stats::FrameStats;
+use glyphs::Glyphs;
+use gravity::Gravity;
+use highscore::HighScores;
+use input::{InputSource, ScriptedInput, StdinInput};
+use keys::Action;
+use layout::{Layout, Room};
+use log::Logger;
+use narrator::Narrator;
+use randomizer::{Dealer, Randomizer};
+use renderer::{Renderer, Style, Styled, TermionRenderer};
+use stats_csv::{GameRecord, StatsCsv};
+use theme::{Color, ColorDepth, Palette, Theme};
 
 // Board size
 const BOARD_WIDTH: usize = 10;
 const BOARD_HEIGHT: usize = 20;
 
+// Largest cell size: 4x2 rather than 2x1.
+const MAX_SCALE: u16 = 2;
+
+// Hidden rows over the top of the board. Pieces come in up here, just
+// poking into view, and the stack can reach up into them without it being
+// over yet.
+const BUFFER_ROWS: usize = 2;
+
+// Smallest board every piece fits on, in any rotation.
+const MIN_BOARD_WIDTH: usize = 4;
+const MIN_BOARD_HEIGHT: usize = 4;
+// Widest board a row's bitmask has room for.
+const MAX_BOARD_WIDTH: usize = board::MAX_WIDTH;
+
 const FRAME_RATE: u8 = 60; // 60 FPS
-const FALL_RATE_MS: u128 = 400; // 0.5 sec
+const FALL_RATE_MS: u128 = 400; // 0.4 sec
+                                // Each level falls this much faster than the last, down to one frame.
+const FALL_RATE_SPEEDUP: f64 = 0.85;
+const LINES_PER_LEVEL: usize = 10;
+// Levels a game can start on. Past the last one pieces are already falling a
+// row a frame.
+const MAX_START_LEVEL: usize = 20;
+
+// How long completed lines flash for before they are cleared, unless set
+// otherwise, and how fast they blink while they do.
+const LINE_CLEAR_DELAY: Duration = Duration::from_millis(64);
+const LINE_CLEAR_BLINK: Duration = Duration::from_millis(100);
+
+// Points for each row a soft drop moves the piece down, unless set
+// otherwise. The guideline's 1.
+const SOFT_DROP_POINTS: u32 = 1;
+
+// Frames the hard drop trail and thud last.
+const TRAIL_FRAMES: u8 = 2;
+const SHAKE_FRAMES: u8 = 1;
+
+// Score popups float up a row every POPUP_DRIFT and are gone after
+// POPUP_DURATION, fading for the last third.
+const POPUP_DURATION: Duration = Duration::from_secs(1);
+const POPUP_DRIFT: Duration = Duration::from_millis(250);
+
+// How long the last scoring event stays up under the HUD, and the room it
+// gets there, enough for "Back-to-Back T-Spin Triple!".
+const ANNOUNCE_DURATION: Duration = Duration::from_secs(2);
+const ANNOUNCE_WIDTH: usize = 27;
+
+// The border goes red once the stack gets into the top this many rows.
+const DANGER_ROWS: usize = 4;
+
+// Pause menu entries, top to bottom.
+const PAUSE_MENU: [&str; 4] = ["Resume", "Restart", "Mirror", "Quit"];
+
+// Longest name that goes in the high score table.
+const MAX_NAME_LEN: usize = 10;
+
+// How long the NEW BEST marker stays up.
+const NEW_BEST_DURATION: Duration = Duration::from_secs(2);
+
+// Sidebar width, enough for a label and a healthy score.
+const HUD_WIDTH: u16 = 18;
+// Rows the HUD stats take up.
+const HUD_ROWS: u16 = 6;
+// The bar under the level, in chars, and how long it flashes on a level up
+// and how fast.
+const LEVEL_BAR_WIDTH: usize = 6;
+const LEVEL_UP_FLASH: Duration = Duration::from_secs(1);
+const LEVEL_UP_BLINK: Duration = Duration::from_millis(250);
+// GAME OVER pulses, bold red then dim, for four goes before it settles on
+// red.
+const GAME_OVER_FLASH: Duration = Duration::from_millis(2400);
+const GAME_OVER_BLINK: Duration = Duration::from_millis(300);
+// The combo and back to back readouts right of the score. The sidebar has
+// this much to spare next to the HUD.
+const STREAK_WIDTH: u16 = ANNOUNCE_WIDTH as u16 - HUD_WIDTH;
+// Rows under the HUD for the piece stats: one a shape, then pieces placed and
+// pieces per second.
+const PIECE_STATS_ROWS: u16 = 7 + 2;
+
+// How many upcoming pieces can be shown, and the rows each one gets in the
+// side panel (the tallest piece plus a gap).
+const MIN_PREVIEW: usize = 1;
+const MAX_PREVIEW: usize = 5;
+const PREVIEW_ROWS: u16 = 5;
+
+// Survival garbage comes every SURVIVAL_START to begin with, a second
+// quicker every SURVIVAL_SPEEDUP, never quicker than SURVIVAL_FASTEST.
+const SURVIVAL_START: Duration = Duration::from_secs(10);
+const SURVIVAL_SPEEDUP: Duration = Duration::from_secs(30);
+const SURVIVAL_FASTEST: Duration = Duration::from_secs(2);
+
+// Debug overlay width, "frame max 16.00ms" with room to spare.
+const DEBUG_WIDTH: u16 = 19;
+
+// Practice: how many locks undo can go back.
+const UNDO_DEPTH: usize = 20;
+
+// Invisible: how long a piece stays in sight once it's locked, how many
+// times `v` shows the whole stack in a game and for how long.
+const INVISIBLE_DELAY: Duration = Duration::from_millis(500);
+const REVEALS: u8 = 3;
+const REVEAL_DURATION: Duration = Duration::from_secs(2);
+
+// 3, 2, 1 in the middle of the board before a game starts, each up for
+// COUNTDOWN_STEP. Any key gets on with it.
+const COUNTDOWN_FROM: u32 = 3;
+const COUNTDOWN_STEP: Duration = Duration::from_millis(700);
+
+// Game over panel width. Fits the keys line with a space either side.
+const GAME_OVER_WIDTH: u16 = 18;
+// Fewest high scores worth showing under the game over panel.
+const MIN_TABLE_ENTRIES: u16 = 3;
 
 // Point struct
 // The default board size is 20x10. x requires 5 bits & y requires 4 bits.
@@ -41,580 +174,5488 @@ const FALL_RATE_MS: u128 = 400; // 0.5 sec
 // be to check if offset < 0 and (x or y) == 0 for invalid offset. That way, I
 // can still use u8.
 // TODO: Maybe a different way to pack into u8?
-struct Point {
-    x: i16,
-    y: i16,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Point {
+    // One over, each way a piece moves.
+    pub const LEFT: Point = Point { x: -1, y: 0 };
+    pub const RIGHT: Point = Point { x: 1, y: 0 };
+    pub const DOWN: Point = Point { x: 0, y: 1 };
+
+    pub fn new(x: i16, y: i16) -> Self {
+        Self { x, y }
+    }
+
+    // None past the end of an i16, rather than wrapping around into
+    // something valid looking.
+    pub fn checked_add(self, other: Point) -> Option<Point> {
+        Some(Point {
+            x: self.x.checked_add(other.x)?,
+            y: self.y.checked_add(other.y)?,
+        })
+    }
 }
 
-impl ops::AddAssign<&Point> for Point {
-    fn add_assign(&mut self, other: &Point) {
-        *self = Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
+// Tetromino shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    I,
+    O,
+    T,
+    J,
+    L,
+    S,
+    Z,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 7] = [
+        Shape::I,
+        Shape::O,
+        Shape::T,
+        Shape::J,
+        Shape::L,
+        Shape::S,
+        Shape::Z,
+    ];
+
+    pub fn letter(self) -> char {
+        match self {
+            Shape::I => 'I',
+            Shape::O => 'O',
+            Shape::T => 'T',
+            Shape::J => 'J',
+            Shape::L => 'L',
+            Shape::S => 'S',
+            Shape::Z => 'Z',
         }
     }
 }
 
+// A board cell. Occupied cells remember which shape put them there so they
+// can be colored at draw time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Filled(Shape),
+    // Pushed up from below rather than placed.
+    Garbage,
+}
+
 // Tetromino blocks
-// Positioning:
-// 00 01 02 03
-// 10 11 12 13
-// 20 21 22 23
-// 30 31 32 33
-// Each tetromino occupies 4 positions in the above sparse array.
-// The struct stores xy for each block in the tetromino.
+// A tetromino is its shape, which way round it is and where its pivot is on
+// the board. The blocks come out of BLOCKS, four offsets from the pivot for
+// each shape and rotation. Moving it is moving the pivot, rotating it is
+// looking up the next rotation.
 // Ref: https://en.wikipedia.org/wiki/Tetromino#One-sided_tetrominoes
-struct Tetromino {
-    blocks: [Point; 4],
-    // Color is a trait. I got no idea what that is and instead of putting the
-    // project on hold till I finish the book or keep going into my google
-    // search hole, I'm hacking this to store the string.
-    color: String,
+// Equal when it's the same shape the same way round in the same place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tetromino {
+    shape: Shape,
+    // Quarter turns clockwise from the spawn rotation, 0 to 3.
+    rotation: u8,
+    // Where the pivot, (0, 0) in BLOCKS, is on the board.
+    pos: Point,
 }
 
+type Offsets = [(i16, i16); 4];
+
+// Block offsets from the pivot, by shape (in Shape::ALL order) and
+// rotation. Each rotation is the one before turned a quarter clockwise,
+// (x, y) to (y, -x), around the pivot. The pivot is always the second
+// block, it's what everything turned around before there were tables.
+// The spawn rotations, with the top left at (0, 0):
+//
+//     I  O   T   J    L    S   Z
+//     #  ##  #   ..#  ###  .#  #.
+//     #  ##  ##  ###  ..#  ##  ##
+//     #      #             #.  .#
+//     #
+const BLOCKS: [[Offsets; 4]; 7] = [
+    // I
+    [
+        [(0, -1), (0, 0), (0, 1), (0, 2)],
+        [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        [(0, 1), (0, 0), (0, -1), (0, -2)],
+        [(1, 0), (0, 0), (-1, 0), (-2, 0)],
+    ],
+    // O
+    [
+        [(0, -1), (0, 0), (1, -1), (1, 0)],
+        [(-1, 0), (0, 0), (-1, -1), (0, -1)],
+        [(0, 1), (0, 0), (-1, 1), (-1, 0)],
+        [(1, 0), (0, 0), (1, 1), (0, 1)],
+    ],
+    // T
+    [
+        [(0, -1), (0, 0), (0, 1), (1, 0)],
+        [(-1, 0), (0, 0), (1, 0), (0, -1)],
+        [(0, 1), (0, 0), (0, -1), (-1, 0)],
+        [(1, 0), (0, 0), (-1, 0), (0, 1)],
+    ],
+    // J
+    [
+        [(-1, 0), (0, 0), (1, -1), (1, 0)],
+        [(0, 1), (0, 0), (-1, -1), (0, -1)],
+        [(1, 0), (0, 0), (-1, 1), (-1, 0)],
+        [(0, -1), (0, 0), (1, 1), (0, 1)],
+    ],
+    // L
+    [
+        [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        [(0, 1), (0, 0), (0, -1), (1, -1)],
+        [(1, 0), (0, 0), (-1, 0), (-1, -1)],
+        [(0, -1), (0, 0), (0, 1), (-1, 1)],
+    ],
+    // S
+    [
+        [(0, -1), (0, 0), (1, -2), (1, -1)],
+        [(-1, 0), (0, 0), (-2, -1), (-1, -1)],
+        [(0, 1), (0, 0), (-1, 2), (-1, 1)],
+        [(1, 0), (0, 0), (2, 1), (1, 1)],
+    ],
+    // Z
+    [
+        [(0, -1), (0, 0), (1, 0), (1, 1)],
+        [(-1, 0), (0, 0), (0, -1), (1, -1)],
+        [(0, 1), (0, 0), (-1, 0), (-1, -1)],
+        [(1, 0), (0, 0), (0, 1), (-1, 1)],
+    ],
+];
+
+// Where a rotation that doesn't fit tries next, in order, from where the
+// pivot was. Only in place for now; a wall kick is one more entry here.
+const KICKS: [Point; 1] = [Point { x: 0, y: 0 }];
+
 impl Tetromino {
     // Get a random tetromino.
     pub fn random() -> Self {
         let mut rng = rand::thread_rng();
-        match rng.gen_range(0..7) {
-            0 => Self::i(),
-            1 => Self::o(),
-            2 => Self::t(),
-            3 => Self::j(),
-            4 => Self::l(),
-            5 => Self::s(),
-            _ => Self::z(),
-        }
+        Self::of(Shape::ALL[rng.gen_range(0..Shape::ALL.len())])
     }
 
-    // I tetromino.
-    fn i() -> Self {
-        Tetromino {
-            blocks: [
-                Point { x: 0, y: 0 },
-                Point { x: 0, y: 1 },
-                Point { x: 0, y: 2 },
-                Point { x: 0, y: 3 },
-            ],
-            color: format!("{}", color::Fg(color::Cyan)),
+    // A `shape` in its spawn rotation, top-left at (0, 0).
+    pub fn of(shape: Shape) -> Self {
+        let blocks = &BLOCKS[shape as usize][0];
+        let x = blocks.iter().map(|&(x, _)| x).min().unwrap();
+        let y = blocks.iter().map(|&(_, y)| y).min().unwrap();
+        Self {
+            shape,
+            rotation: 0,
+            pos: Point { x: -x, y: -y },
         }
     }
 
-    // O tetromino.
-    fn o() -> Self {
-        Tetromino {
-            blocks: [
-                Point { x: 0, y: 0 },
-                Point { x: 0, y: 1 },
-                Point { x: 1, y: 0 },
-                Point { x: 1, y: 1 },
-            ],
-            color: format!("{}", color::Fg(color::Yellow)),
-        }
+    // Where its four blocks are on the board. Always fits an i16, moving
+    // and rotating make sure of that.
+    pub fn cells(&self) -> [Point; 4] {
+        self.checked_cells().unwrap()
     }
 
-    // T tetromino.
-    fn t() -> Self {
-        Tetromino {
-            blocks: [
-                Point { x: 0, y: 0 },
-                Point { x: 0, y: 1 },
-                Point { x: 0, y: 2 },
-                Point { x: 1, y: 1 },
-            ],
-            color: format!("{}", color::Fg(color::Magenta)),
-        }
+    pub fn shape(&self) -> Shape {
+        self.shape
     }
 
-    // J tetromino.
-    fn j() -> Self {
-        Tetromino {
-            blocks: [
-                Point { x: 0, y: 1 },
-                Point { x: 1, y: 1 },
-                Point { x: 2, y: 0 },
-                Point { x: 2, y: 1 },
-            ],
-            color: format!("{}", color::Fg(color::Blue)),
+    pub fn rotation(&self) -> u8 {
+        self.rotation
+    }
+
+    // The pivot, the block it turns around.
+    pub fn pos(&self) -> Point {
+        self.pos
+    }
+
+    // The blocks on the board, or None if any of them would be past the
+    // end of an i16. Only a piece that's being tried out can be.
+    fn checked_cells(&self) -> Option<[Point; 4]> {
+        let blocks = &BLOCKS[self.shape as usize][self.rotation as usize];
+        let mut cells = [self.pos; 4];
+        for (cell, &(x, y)) in cells.iter_mut().zip(blocks) {
+            *cell = self.pos.checked_add(Point { x, y })?;
         }
+        Some(cells)
     }
 
-    // L tetromino.
-    fn l() -> Self {
+    // The same piece with its pivot `offset` over, or None if that's past
+    // the end of an i16.
+    fn moved(&self, offset: Point) -> Option<Tetromino> {
+        Some(Tetromino {
+            pos: self.pos.checked_add(offset)?,
+            ..self.clone()
+        })
+    }
+
+    // The same piece a quarter turn round, either way, pivot where it was.
+    fn turned(&self, clockwise: bool) -> Tetromino {
+        let rotation = match clockwise {
+            true => (self.rotation + 1) % 4,
+            false => (self.rotation + 3) % 4,
+        };
         Tetromino {
-            blocks: [
-                Point { x: 0, y: 0 },
-                Point { x: 1, y: 0 },
-                Point { x: 2, y: 0 },
-                Point { x: 2, y: 1 },
-            ],
-            color: format!("{}", color::Fg(color::Rgb(255, 165, 0))),
+            rotation,
+            ..self.clone()
         }
     }
 
-    // S tetromino.
-    fn s() -> Self {
-        Tetromino {
-            blocks: [
-                Point { x: 0, y: 1 },
-                Point { x: 0, y: 2 },
-                Point { x: 1, y: 0 },
-                Point { x: 1, y: 1 },
-            ],
-            color: format!("{}", color::Fg(color::Green)),
+    // Number of columns the tetromino spans.
+    fn width(&self) -> i16 {
+        let xs = self.cells().map(|b| b.x);
+        xs.iter().max().unwrap() - xs.iter().min().unwrap() + 1
+    }
+
+    // Number of rows it spans.
+    fn height(&self) -> i16 {
+        let ys = self.cells().map(|b| b.y);
+        ys.iter().max().unwrap() - ys.iter().min().unwrap() + 1
+    }
+}
+
+// Something a lock set off that's worth telling the player about. The lock
+// hands these out and whoever cares (the HUD for now) turns them into text,
+// sounds or stats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    // One piece cleared `lines` lines. Back to back is a Tetris or T-spin
+    // clear right after another one.
+    Clear {
+        lines: usize,
+        t_spin: bool,
+        back_to_back: bool,
+    },
+    // A T-spin that didn't clear anything.
+    TSpin,
+    // Clearing pieces in a row, counted from the second one.
+    Combo(usize),
+}
+
+impl Event {
+    // When one lock sets off several events, the highest goes up on the HUD.
+    // A back to back beats everything, then the hard clears, then a combo
+    // going on, then the everyday clears.
+    fn priority(self) -> u8 {
+        match self {
+            Event::Clear {
+                back_to_back: true, ..
+            } => 4,
+            Event::Clear { lines: 4, .. } | Event::Clear { t_spin: true, .. } => 3,
+            Event::Combo(_) => 2,
+            Event::Clear { .. } => 1,
+            Event::TSpin => 0,
         }
     }
 
-    // Z tetromino.
-    fn z() -> Self {
-        Tetromino {
-            blocks: [
-                Point { x: 0, y: 0 },
-                Point { x: 0, y: 1 },
-                Point { x: 1, y: 1 },
-                Point { x: 1, y: 2 },
-            ],
-            color: format!("{}", color::Fg(color::Red)),
+    fn text(self) -> String {
+        match self {
+            Event::Clear {
+                lines,
+                t_spin,
+                back_to_back,
+            } => {
+                let name = match lines {
+                    1 => "Single",
+                    2 => "Double",
+                    3 => "Triple",
+                    _ => "Tetris",
+                };
+                let t_spin = if t_spin && lines < 4 { "T-Spin " } else { "" };
+                let b2b = if back_to_back { "Back-to-Back " } else { "" };
+                format!("{}{}{}!", b2b, t_spin, name)
+            }
+            Event::TSpin => "T-Spin!".to_string(),
+            Event::Combo(n) => format!("{} Combo!", n),
         }
     }
 }
 
+// What one step did, for whoever is driving the game (sounds, tests, the
+// versus garbage) to look at after the fact instead of diffing getters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepResult {
+    // The falling piece went onto the stack.
+    pub locked: bool,
+    // Lines that went this step. That's once they're done flashing, not
+    // when the piece that finished them locked.
+    pub lines_cleared: u32,
+    // The lock was a T-spin, cleared anything or not.
+    pub t_spin: bool,
+    // Topped out this step.
+    pub game_over: bool,
+}
+
+// "+300" floating up from where a clear happened.
+struct Popup {
+    text: String,
+    // Board row it started at.
+    row: usize,
+    age: Duration,
+}
+
+impl Popup {
+    // Board row it has drifted up to, None once it's off the top.
+    fn row(&self) -> Option<usize> {
+        let drift = (self.age.as_millis() / POPUP_DRIFT.as_millis()) as usize;
+        self.row.checked_sub(drift)
+    }
+}
+
+// Practice: everything a lock changes, from just before it, so undo can put
+// it all back.
+struct Snapshot {
+    board: Board,
+    score: i64,
+    lines_cleared: usize,
+    piece_counts: [usize; 7],
+    pieces_placed: u64,
+    back_to_back: bool,
+    combo: usize,
+    queue: VecDeque<Tetromino>,
+    held: Option<Shape>,
+    hold_used: bool,
+    // The piece that locked. It comes back from the top.
+    shape: Shape,
+}
+
 // GameState represents all the state the game can be in.
 // Yeah, yeah, I know. Ideally, I'd like to have a start screen state,
-// pause state, maybe win? (but what really is winning in tetris?).
+// maybe win? (but what really is winning in tetris?).
 enum GameState {
-    PLAY,
-    LOSE,
+    // Before the first game. Shows the keys, or the high score table.
+    Title,
+    Play,
+    Pause,
+    // Made the high score table, typing in a name.
+    NameEntry,
+    Lose,
+    Quit,
+}
+
+// What kind of game it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    // The usual. Clear lines, level up, go until you top out.
+    #[default]
+    Marathon,
+    // Garbage rises from the bottom faster and faster. Last as long as you
+    // can.
+    Survival,
+    // No pressure, and no high scores. 1 to 7 picks the piece, `u` takes
+    // back the last one.
+    Practice,
+    // Marathon, but the stack disappears a moment after each lock. `v`
+    // shows it again for a bit, REVEALS times a game.
+    Invisible,
+    // Marathon with no game over. Topping out sweeps the board and play
+    // goes on, score and all. For warming up, or leaving the AI to it.
+    Zen,
+}
+
+impl GameMode {
+    pub const NAMES: [&'static str; 5] = ["marathon", "survival", "practice", "invisible", "zen"];
+
+    pub fn by_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "marathon" => Ok(GameMode::Marathon),
+            "survival" => Ok(GameMode::Survival),
+            "practice" => Ok(GameMode::Practice),
+            "invisible" => Ok(GameMode::Invisible),
+            "zen" => Ok(GameMode::Zen),
+            _ => Err(Error::UnknownMode(name.to_string())),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Marathon",
+            GameMode::Survival => "Survival",
+            GameMode::Practice => "Practice",
+            GameMode::Invisible => "Invisible",
+            GameMode::Zen => "Zen",
+        }
+    }
+}
+
+// `done` out of `of` as a bar `width` chars wide, down to an eighth of a
+// char, on a shaded track so an empty one still shows.
+fn progress_bar(done: usize, of: usize, width: usize) -> String {
+    const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+    let eighths = (done * width * 8 / of.max(1)).min(width * 8);
+    let bar = "█".repeat(eighths / 8) + EIGHTHS[eighths % 8];
+    let track = width - bar.chars().count();
+    bar + &"░".repeat(track)
+}
+
+// 12400 as "12,400".
+fn thousands(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    match n < 0 {
+        true => format!("-{}", out),
+        false => out,
+    }
+}
+
+// mm:ss.cc
+fn format_time(d: Duration) -> String {
+    let centis = d.as_millis() / 10;
+    format!(
+        "{:02}:{:02}.{:02}",
+        centis / 6000,
+        (centis / 100) % 60,
+        centis % 100
+    )
+}
+
+// Things that can go wrong setting up a game.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    BoardTooSmall { width: usize, height: usize },
+    BoardTooWide { width: usize },
+    PreviewCount(usize),
+    StartLevel(usize),
+    // A cell glyph that isn't two columns wide.
+    GlyphWidth(String),
+    UnknownGlyphs(String),
+    UnknownRandomizer(String),
+    UnknownGravity(String),
+    GarbageHole { hole_col: usize, width: usize },
+    UnknownBorder(String),
+    UnknownMode(String),
+    UnknownTheme(String),
+    UnknownColor(String),
+    UnknownColorDepth(String),
+    // Cell size other than auto, 1 or 2.
+    UnknownScale(String),
+    // A config key nobody reads.
+    UnknownSetting(String),
+    // A setting that's on or off, set to something else.
+    NotOnOff(String),
+    // A setting that's a number, set to something else.
+    NotANumber(String),
+    // Something wrong on a line of the config file.
+    Config { line: usize, error: Box<Error> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BoardTooSmall { width, height } => write!(
+                f,
+                "board is {}x{}, needs to be at least {}x{}",
+                width, height, MIN_BOARD_WIDTH, MIN_BOARD_HEIGHT
+            ),
+            Error::BoardTooWide { width } => write!(
+                f,
+                "board is {} wide, can be at most {}",
+                width, MAX_BOARD_WIDTH
+            ),
+            Error::PreviewCount(count) => write!(
+                f,
+                "can preview {} to {} pieces, not {}",
+                MIN_PREVIEW, MAX_PREVIEW, count
+            ),
+            Error::StartLevel(level) => write!(
+                f,
+                "can start on level 1 to {}, not {}",
+                MAX_START_LEVEL, level
+            ),
+            Error::GlyphWidth(glyph) => {
+                write!(f, "glyph {:?} isn't two columns wide", glyph)
+            }
+            Error::UnknownGlyphs(name) => write!(
+                f,
+                "no glyphs called {:?}, try one of {}",
+                name,
+                glyphs::PRESETS.join(", ")
+            ),
+            Error::UnknownRandomizer(name) => write!(
+                f,
+                "no randomizer called {:?}, try one of {}",
+                name,
+                Randomizer::NAMES.join(", ")
+            ),
+            Error::UnknownGravity(name) => write!(
+                f,
+                "no gravity called {:?}, try one of {}",
+                name,
+                Gravity::NAMES.join(", ")
+            ),
+            Error::UnknownMode(name) => write!(
+                f,
+                "no mode called {:?}, try one of {}",
+                name,
+                GameMode::NAMES.join(", ")
+            ),
+            Error::UnknownTheme(name) => write!(
+                f,
+                "no theme called {:?}, try one of {}",
+                name,
+                theme::PRESETS.join(", ")
+            ),
+            Error::UnknownColor(color) => write!(
+                f,
+                "{:?} isn't a color, try a name like light_blue or r, g, b",
+                color
+            ),
+            Error::UnknownColorDepth(name) => write!(
+                f,
+                "no colors setting called {:?}, try one of {}",
+                name,
+                ColorDepth::NAMES.join(", ")
+            ),
+            Error::UnknownSetting(key) => write!(f, "no setting called {:?}", key),
+            Error::UnknownScale(scale) => {
+                write!(f, "no scale {:?}, try auto, 1 or {}", scale, MAX_SCALE)
+            }
+            Error::NotOnOff(value) => write!(f, "expected on or off, not {:?}", value),
+            Error::NotANumber(value) => write!(f, "expected a number, not {:?}", value),
+            Error::Config { line, error } => write!(f, "line {} of the config: {}", line, error),
+            Error::UnknownBorder(name) => write!(
+                f,
+                "no border called {:?}, try one of {}",
+                name,
+                border::NAMES.join(", ")
+            ),
+            Error::GarbageHole { hole_col, width } => write!(
+                f,
+                "garbage hole at column {} is off a {} wide board",
+                hole_col, width
+            ),
+        }
+    }
 }
 
+impl error::Error for Error {}
+
 pub struct Game {
-    // Bad design aravind, very bad.
-    // Now that the board is a str, every freaking el is on the heap and every
-    // comparison is expensive. Each cell stores two info: occupied and color.
-    // Could have compressed into a single u8.
-    board: Vec<Vec<String>>,
+    board: Board,
     score: i64,
+    // Best score since the process started. Survives restarts.
+    session_best: i64,
+    // Whether this game already went past the session best, and for how much
+    // longer to say so.
+    beat_best: bool,
+    new_best_timer: Duration,
+    // Flashing the level bar after a level up, for how much longer.
+    level_up_timer: Duration,
+    // Same for GAME OVER.
+    game_over_timer: Duration,
+    lines_cleared: usize,
+    // Time spent actually playing. This only accumulates the ticks that are
+    // played, from the first spawn on, so pauses don't count. Anything that
+    // shows or scores time should read this.
+    play_time: Duration,
+    clock_started: bool,
+    // Left of the countdown before the first piece, zero once it's done.
+    countdown: Duration,
     width: usize,
     height: usize,
-    stdout: RawTerminal<Stdout>,
-    stdin: Keys<AsyncReader>,
+    // What the game draws through, see renderer.rs. The terminal's draws on
+    // the alternate screen so whatever the user had in the terminal comes
+    // back when we drop this. Its guard makes sure that happens even if we
+    // panic.
+    renderer: Box<dyn Renderer>,
+    input: Box<dyn InputSource>,
     falling: Option<Tetromino>,
     state: GameState,
+    // Time since the falling piece last moved down on its own.
+    fall_timer: Duration,
+    // Entry delay (ARE): how long the stack sits still after a lock before
+    // the next piece comes in, and how much of it is left.
+    entry_delay: Duration,
+    entry_timer: Duration,
+    // Points a row for soft dropping. Gravity pulling it down gets nothing.
+    soft_drop_points: u32,
+    // Autoplay. When set, the AI drives the moves instead of the keyboard.
+    ai: Option<Ai>,
+    // Draw the locked stack dimmer than the falling piece.
+    dim_locked: bool,
+    // Dots in the empty cells. Off leaves them blank, whatever the glyphs.
+    grid: bool,
+    // Empty cells in place of the glyphs' own, e.g. ". " for a plainer dot.
+    empty_glyph: Option<String>,
+    // Every other column of empty cells shaded, for keeping track of columns.
+    stripes: bool,
+    // Where the piece would land, drawn under it. Off for the old way.
+    ghost_on: bool,
+    // The board drawn back to front, and the keys swapped to match. The
+    // board itself doesn't know, only drawing and the keys do.
+    mirror: bool,
+    // Show row and column numbers around the board.
+    labels: bool,
+    // The keys help under the board.
+    hint: bool,
+    // Hard drop trail and thud, score popups. Purely cosmetic.
+    effects: bool,
+    // Debug overlay, `F` or F3 toggles it. The numbers are over the last
+    // second, or they flicker too fast to read.
+    debug: bool,
+    frame_stats: FrameStats,
+    trail: Vec<Point>,
+    trail_shape: Shape,
+    trail_frames: u8,
+    shake_frames: u8,
+    popups: Vec<Popup>,
+    // What the last lock set off, and the one on the HUD for how much longer.
+    events: Vec<Event>,
+    announcement: Option<Event>,
+    announce_timer: Duration,
+    // Whether the last move of the falling piece was a rotation, for T-spins.
+    rotated_last: bool,
+    // Last clear was a Tetris or T-spin, the next hard one is back to back.
+    back_to_back: bool,
+    // Clearing pieces in a row so far.
+    combo: usize,
+    // The longest combo this game, counted like the readout does.
+    max_combo: usize,
+    mode: GameMode,
+    // The piece put aside with hold, and whether it's been swapped already
+    // for the piece that's falling. Once a piece, or it could be held
+    // forever.
+    held: Option<Shape>,
+    hold_used: bool,
+    // Off, the key does nothing and there's no panel for it.
+    hold_on: bool,
+    // Practice: the last UNDO_DEPTH locks, oldest first. Undo takes the
+    // drop points back too, so it needs the score from before those.
+    undo: VecDeque<Snapshot>,
+    spawn_score: i64,
+    // Invisible: locked blocks still in sight and for how much longer, and
+    // the reveals left and how long the one going has left.
+    fading: Vec<(Point, Duration)>,
+    reveals: u8,
+    reveal_timer: Duration,
+    // Zen: how many times the stack's been swept.
+    sweeps: usize,
+    // Survival: time since the last garbage row, and where the holes go.
+    garbage_timer: Duration,
+    garbage_rng: StdRng,
+    // Frame around the board. None for no frame.
+    border: Option<Border>,
+    // Border is currently drawn red.
+    danger_shown: bool,
+    // Where everything goes on screen.
+    layout: Layout,
+    // Screen size to lay out for. None asks the terminal.
+    screen_size: Option<(u16, u16)>,
+    // How big a cell is drawn, 1 or 2. None picks 2 when there's room to
+    // spare.
+    scale: Option<u16>,
+    // The "too small" message is up, for a screen this size.
+    too_small_shown: Option<(u16, u16)>,
+    // Screen column the board's area starts at, after whatever is left of it.
+    origin: u16,
+    // One of the two boards in a versus match. The screen is shared, so it
+    // never gets cleared from here, and topping out is losing, not a shot at
+    // the high scores.
+    versus: bool,
+    // Completed rows held before they get cleared, how long they're held
+    // for and how much of that is left.
+    clearing: Vec<usize>,
+    line_clear_delay: Duration,
+    clear_timer: Duration,
+    // How many of each shape were dealt this game, in Shape::ALL order.
+    piece_counts: [usize; 7],
+    // Pieces that made it onto the stack this game.
+    pieces_placed: u64,
+    // Upcoming pieces, always at least MAX_PREVIEW deep so the preview never
+    // runs dry.
+    queue: VecDeque<Tetromino>,
+    // Where the queue gets its pieces, and what it was set up with.
+    dealer: Dealer,
+    randomizer: Randomizer,
+    // What comes down after a line clear.
+    gravity: Gravity,
+    seed: u64,
+    // Picked with set_seed() (or the daily's), kept for every game rather
+    // than rolled again on a restart.
+    seeded: bool,
+    // The daily challenge's date. Restarts deal the same pieces again.
+    daily: Option<String>,
+    // Pieces to show in the preview. The layout knows how many fit.
+    preview_count: usize,
+    // The level before any lines are cleared.
+    start_level: usize,
+    // Title screen is showing the high score table rather than the keys.
+    title_scores: bool,
+    // What cells look like.
+    glyphs: Glyphs,
+    theme: Theme,
+    // Off, and not a single color escape goes out. Pieces are told apart
+    // by their letters instead.
+    color: bool,
+    // What the terminal can show. Colors it can't get swapped for ones it
+    // can.
+    color_depth: ColorDepth,
+    // Every shape gets its own glyph, for telling them apart without color.
+    colorblind: bool,
+    // ☹️ after GAME OVER. Some terminals make a mess of it.
+    emoji: bool,
+    // Nothing but ASCII on screen, for bare consoles.
+    ascii: bool,
+    // 3-2-1 before each game.
+    countdown_on: bool,
+    // --log: what happened, for bug reports.
+    log: Logger,
+    // --screen-reader: what happens, said in words instead of drawn.
+    narrator: Narrator,
+    // --stats-csv: a row per finished game.
+    stats_csv: Option<StatsCsv>,
+    // --snapshot: where the last board goes as a PNG.
+    #[cfg(feature = "snapshot")]
+    snapshot: Option<std::path::PathBuf>,
+    // Score and level in the terminal's title, and the last one sent. Off
+    // unless asked for, some terminals print unknown escapes as is.
+    title: bool,
+    title_shown: Option<String>,
+    // The theme, glyphs and all that, as the styles and cells to draw.
+    palette: Palette,
+    // Highlighted pause menu entry.
+    menu_selection: usize,
+    // The whole screen needs to be drawn again, e.g. after a restart.
+    needs_redraw: bool,
+    high_scores: HighScores,
+    // Name being typed in for the high score table.
+    name: String,
+    // Where this game landed in the high score table, if it made it.
+    rank: Option<usize>,
 }
 
-impl Game {
+impl Default for Game {
     // default constructor
-    pub fn default() -> Self {
-        Self::new(BOARD_WIDTH, BOARD_HEIGHT)
+    fn default() -> Self {
+        Self::new(BOARD_WIDTH, BOARD_HEIGHT).unwrap()
     }
+}
 
+impl Game {
     // constructor
-    pub fn new(width: usize, height: usize) -> Self {
-        Self {
-            board: vec![vec![String::from(EMPTY_CELL); width]; height],
+    pub fn new(width: usize, height: usize) -> Result<Self, Error> {
+        Self::validate_size(width, height)?;
+        let mut game = Self::build(
+            width,
+            height,
+            Box::new(TermionRenderer::stdout()),
+            Box::new(StdinInput::new()),
+            HighScores::load(),
+        );
+        game.detect_colors();
+        Ok(game)
+    }
+
+    // Today's daily challenge: the date (UTC, same as the high score
+    // dates) is the seed, so everyone gets the same pieces that day, and
+    // the scores go in a table of their own for the day.
+    pub fn daily() -> Self {
+        let date = highscore::today();
+        let mut game = Self::default();
+        game.start_daily(&date);
+        game.high_scores = HighScores::load_daily(&date);
+        game
+    }
+
+    // YYYY-MM-DD, seeded as YYYYMMDD.
+    fn start_daily(&mut self, date: &str) {
+        let seed = date
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .unwrap_or(0);
+        self.set_seed(seed);
+        self.daily = Some(date.to_string());
+        self.needs_redraw = true;
+    }
+
+    // Draw somewhere other than the terminal. There's no keyboard either,
+    // nothing is pressed until set_input() says otherwise. Laid out for an
+    // 80x24 screen whatever the real terminal is, so the output is the same
+    // everywhere.
+    pub fn with_output(width: usize, height: usize, out: Box<dyn Write>) -> Result<Self, Error> {
+        Self::with_renderer(width, height, Box::new(TermionRenderer::new(out)))
+    }
+
+    // Same, drawn by any renderer at all.
+    pub fn with_renderer(
+        width: usize,
+        height: usize,
+        renderer: Box<dyn Renderer>,
+    ) -> Result<Self, Error> {
+        Self::validate_size(width, height)?;
+        let mut game = Self::build(
+            width,
+            height,
+            renderer,
+            Box::new(ScriptedInput::default()),
+            HighScores::in_memory(),
+        );
+        game.screen_size = Some((80, 24));
+        game.relayout();
+        Ok(game)
+    }
+
+    // Start on the title screen rather than straight into a game.
+    pub fn show_title(&mut self) {
+        self.state = GameState::Title;
+        self.needs_redraw = true;
+    }
+
+    // Take keys from somewhere else.
+    pub fn set_input(&mut self, input: Box<dyn InputSource>) {
+        self.input = input;
+    }
+
+    // Anything smaller and pieces can't spawn (or worse, the spawn column
+    // underflows). Any wider and the row masks run out of bits.
+    fn validate_size(width: usize, height: usize) -> Result<(), Error> {
+        if width < MIN_BOARD_WIDTH || height < MIN_BOARD_HEIGHT {
+            return Err(Error::BoardTooSmall { width, height });
+        }
+        if width > MAX_BOARD_WIDTH {
+            return Err(Error::BoardTooWide { width });
+        }
+
+        Ok(())
+    }
+
+    fn build(
+        width: usize,
+        height: usize,
+        renderer: Box<dyn Renderer>,
+        input: Box<dyn InputSource>,
+        high_scores: HighScores,
+    ) -> Self {
+        let seed = rand::thread_rng().gen();
+        let mut game = Self {
+            board: Board::new(width, height + BUFFER_ROWS),
             score: 0,
-            width: width,
+            session_best: 0,
+            beat_best: false,
+            new_best_timer: Duration::ZERO,
+            level_up_timer: Duration::ZERO,
+            game_over_timer: Duration::ZERO,
+            lines_cleared: 0,
+            play_time: Duration::ZERO,
+            clock_started: false,
+            countdown: Duration::ZERO,
+            width,
             height,
-            stdin: async_stdin().keys(),
-            stdout: io::stdout().into_raw_mode().unwrap(),
+            input,
+            renderer,
             falling: None,
-            state: GameState::PLAY,
-        }
+            state: GameState::Play,
+            fall_timer: Duration::ZERO,
+            entry_delay: Duration::ZERO,
+            entry_timer: Duration::ZERO,
+            soft_drop_points: SOFT_DROP_POINTS,
+            ai: None,
+            dim_locked: true,
+            grid: true,
+            empty_glyph: None,
+            stripes: false,
+            ghost_on: true,
+            mirror: false,
+            labels: false,
+            hint: true,
+            effects: true,
+            trail: Vec::new(),
+            trail_shape: Shape::I,
+            trail_frames: 0,
+            shake_frames: 0,
+            popups: Vec::new(),
+            events: Vec::new(),
+            announcement: None,
+            announce_timer: Duration::ZERO,
+            rotated_last: false,
+            back_to_back: false,
+            combo: 0,
+            max_combo: 0,
+            mode: GameMode::default(),
+            held: None,
+            hold_used: false,
+            hold_on: true,
+            undo: VecDeque::new(),
+            spawn_score: 0,
+            fading: Vec::new(),
+            reveals: REVEALS,
+            reveal_timer: Duration::ZERO,
+            sweeps: 0,
+            garbage_timer: Duration::ZERO,
+            garbage_rng: StdRng::seed_from_u64(seed),
+            border: Some(border::SINGLE),
+            danger_shown: false,
+            layout: Layout::default(),
+            screen_size: None,
+            scale: None,
+            too_small_shown: None,
+            origin: 0,
+            versus: false,
+            clearing: Vec::new(),
+            line_clear_delay: LINE_CLEAR_DELAY,
+            clear_timer: Duration::ZERO,
+            piece_counts: [0; 7],
+            pieces_placed: 0,
+            title_scores: false,
+            debug: false,
+            frame_stats: FrameStats::default(),
+            glyphs: Glyphs::default(),
+            theme: Theme::default(),
+            color: true,
+            color_depth: ColorDepth::default(),
+            colorblind: false,
+            emoji: true,
+            ascii: false,
+            countdown_on: true,
+            log: Logger::default(),
+            narrator: Narrator::default(),
+            stats_csv: None,
+            #[cfg(feature = "snapshot")]
+            snapshot: None,
+            title: false,
+            title_shown: None,
+            palette: Palette::new(&Theme::default(), &Glyphs::default(), true, false),
+            queue: VecDeque::new(),
+            dealer: Dealer::new(Randomizer::default(), seed),
+            randomizer: Randomizer::default(),
+            gravity: Gravity::default(),
+            seed,
+            seeded: false,
+            daily: None,
+            preview_count: MIN_PREVIEW,
+            start_level: 1,
+            menu_selection: 0,
+            needs_redraw: false,
+            high_scores,
+            name: String::new(),
+            rank: None,
+        };
+        game.refill_queue();
+        game.relayout();
+        game
     }
 
-    // Print the game board.
-    fn print_box(&mut self) {
-        // Top row
-        write!(self.stdout, "{}", TOP_LEFT_CORNER).unwrap();
-        for _ in 0..(self.width * 2) {
-            write!(self.stdout, "{}", HORZ_BOUNDARY).unwrap();
+    // Print the game board, framed with `border` if there is one.
+    fn print_box(&mut self, border: Option<Border>) {
+        self.danger_shown = false;
+        if let Some(border) = border {
+            self.draw_border(border, self.palette.border());
         }
-        write!(self.stdout, "{}\n\r", TOP_RIGHT_CORNER).unwrap();
 
         // Body
-        for _ in 0..self.height {
-            write!(self.stdout, "{}", VERT_BOUNDARY).unwrap();
-            for _ in 0..self.width {
-                write!(self.stdout, "{}", EMPTY_CELL).unwrap();
+        let row: Vec<Styled> = (0..self.width).map(|i| self.empty_text(i)).collect();
+        for j in 0..self.height {
+            self.put_row(j as u16, &row);
+        }
+    }
+
+    // Just the border, in `style`. Safe to run again over a live board.
+    fn draw_border(&mut self, border: Border, style: Style) {
+        let border = match self.ascii {
+            true => border::ASCII,
+            false => border,
+        };
+        let (left, top) = (self.layout.left, self.layout.top);
+        let (cols, rows) = self.layout.inner;
+        let across = border.horizontal.repeat(cols as usize);
+
+        // Top row
+        let line = format!("{}{}{}", border.top_left, across, border.top_right);
+        self.put_text(left, top, &line, style);
+
+        // Daily: which day's, in the top border if there's room.
+        if let Some(date) = self.daily.as_ref() {
+            let label = format!(" Daily {} ", date);
+            let len = label.len() as u16;
+            if len <= cols {
+                self.put_text(left + 1 + (cols - len) / 2, top, &label, style);
             }
-            write!(self.stdout, "{}\n\r", VERT_BOUNDARY).unwrap();
         }
 
-        // Bottom row
-        write!(self.stdout, "{}", BOTTOM_LEFT_CORNER).unwrap();
-        for _ in 0..(self.width * 2) {
-            write!(self.stdout, "{}", HORZ_BOUNDARY).unwrap();
+        // Sides
+        let right = left + 1 + cols;
+        for j in 0..rows {
+            let y = top + 1 + j;
+            self.put_text(left, y, border.vertical, style);
+            self.put_text(right, y, border.vertical, style);
         }
-        write!(self.stdout, "{}\n\r", BOTTOM_RIGHT_CORNER).unwrap();
+
+        // Bottom row
+        let line = format!("{}{}{}", border.bottom_left, across, border.bottom_right);
+        self.put_text(left, top + 1 + rows, &line, style);
     }
 
-    // Move mouse to x, y.
-    fn goto(&mut self, x: u16, y: u16) {
-        write!(self.stdout, "{}", termion::cursor::Goto(x, y)).unwrap();
+    // Anything locked in the top DANGER_ROWS rows, or above them.
+    fn in_danger(&self) -> bool {
+        self.board
+            .rows()
+            .take(BUFFER_ROWS + DANGER_ROWS)
+            .flatten()
+            .any(|&cell| cell != Cell::Empty)
     }
 
-    // Write current score.
-    fn print_score(&mut self) {
-        // Move to bottom row
-        self.goto(3, (self.height as u16) + 2);
+    // Red border while the stack is near the top. Only redrawn when that
+    // changes.
+    fn draw_danger(&mut self) {
+        let border = match self.border {
+            Some(border) => border,
+            None => return,
+        };
+        let danger = self.in_danger();
+        if danger == self.danger_shown {
+            return;
+        }
 
-        // Write score
-        write!(
-            self.stdout,
-            "{} Score: {}{}",
-            style::Bold,
-            self.score,
-            style::Reset
-        )
-        .unwrap();
+        self.danger_shown = danger;
+        let style = if danger {
+            self.fg(Color::RED)
+        } else {
+            self.palette.border()
+        };
+        self.draw_border(border, style);
     }
 
-    // Init game screen.
-    fn init_screen(&mut self) {
-        // Hide cursor
-        write!(self.stdout, "{}", cursor::Hide).unwrap();
+    // Print row numbers left of the board and column numbers under it.
+    fn print_labels(&mut self) {
+        if !self.labels {
+            return;
+        }
 
-        // Clear display.
-        write!(self.stdout, "{}", clear::All).unwrap();
-        self.goto(1, 1);
+        // Rows, right aligned so the digits line up against the border.
+        let label_width = (self.height - 1).to_string().len();
+        let cols = self.layout.cell.0;
+        for j in 0..self.height {
+            let (_, y) = self.board_to_screen(0, j as u16);
+            let label = format!("{:>1$}", j, label_width);
+            self.put_text(self.layout.label_x, y, &label, Style::default());
+        }
 
-        // Print box.
-        self.print_box();
+        // Columns. Each cell is at least two chars wide, so indices up to 99
+        // fit without throwing off the alignment.
+        let labels: String = (0..self.width)
+            .map(|i| format!("{:<1$}", i, cols as usize))
+            .collect();
+        let (x, y) = (self.layout.left + 1, self.layout.bottom + 1);
+        self.put_text(x, y, &labels, Style::default());
+    }
 
-        // Print score.
-        self.print_score();
+    // Show or hide the coordinate labels. This moves the board, so the
+    // whole screen is redrawn.
+    fn toggle_labels(&mut self) {
+        self.set_labels(!self.labels);
+        self.init_screen();
     }
 
-    fn insert_falling(&mut self) {
-        if let Some(t) = self.falling.as_ref() {
-            let format = format!("{}[]{}", t.color, style::Reset);
-            for block in t.blocks.iter() {
-                self.board[block.y as usize][block.x as usize] = format.clone();
-            }
+    // Drawing in `color`. Plain with colors off.
+    fn fg(&self, color: Color) -> Style {
+        match self.color {
+            true => Style::color(color),
+            false => Style::default(),
         }
+    }
 
-        self.falling = None; // The board absorbs the falling piece.
+    // Whatever the terminal can do, and the theme that goes with it.
+    fn detect_colors(&mut self) {
+        let depth = ColorDepth::detect();
+        self.set_color_depth(depth);
+        self.set_theme(Theme::default_for(depth));
     }
 
-    // Translate tetromino.
-    // ik, ik, w, h, and board is repeated params. And this can be moved to the tetromino struct.
-    // thenks for you opinion.
-    /// Oh, and note: the board's (x, y) and the screen's (x, y) is different.
-    /// I figured I messed up half way through but I was too lazy to fix it so
-    /// we are going to live with this.
-    fn translate(
-        t: &mut Tetromino,
-        offset: Point,
-        w: usize,
-        h: usize,
-        board: &Vec<Vec<String>>,
-    ) -> bool {
-        // Don't translate if any block fails bound check.
-        // TODO: extract validation into a fn.
-        for block in t.blocks.iter() {
-            let new_x = block.x + offset.x;
-            let new_y = block.y + offset.y;
-
-            if new_x < 0
-                || new_x >= (w as i16)
-                || new_y < 0
-                || new_y >= (h as i16)
-                || board[new_y as usize][new_x as usize] != EMPTY_CELL
-            {
-                return false;
-            }
-        }
+    // Work the theme out into styles again, after something it depends on
+    // changed.
+    fn refresh_palette(&mut self) {
+        let glyphs = match (self.grid, &self.empty_glyph) {
+            (false, _) => self.glyphs.without_grid(),
+            (true, Some(empty)) => self.glyphs.with_empty(empty),
+            (true, None) => self.glyphs.clone(),
+        };
+        let glyphs = match self.ascii {
+            true => glyphs.ascii(),
+            false => glyphs,
+        };
+        self.palette = Palette::new(&self.theme, &glyphs, self.color, self.colorblind);
+        self.renderer.set_color_depth(self.color_depth);
+        self.needs_redraw = true;
+    }
+
+    // Move mouse to x, y.
+    fn goto(&mut self, x: u16, y: u16) {
+        self.renderer.move_to(x, y).unwrap();
+    }
+
+    // `text` in `style` at screen position (x, y).
+    fn put_text(&mut self, x: u16, y: u16, text: &str, style: Style) {
+        self.renderer.draw_text(x, y, text, style).unwrap();
+    }
 
-        // Translate
-        for i in 0..t.blocks.len() {
-            t.blocks[i] += &offset;
+    // A line made of `spans` side by side, from (x, y) on.
+    fn put_spans(&mut self, x: u16, y: u16, spans: &[(&str, Style)]) {
+        let mut x = x;
+        for &(text, style) in spans {
+            self.put_text(x, y, text, style);
+            x += glyphs::display_width(text).unwrap_or(0) as u16;
         }
+    }
 
-        return true;
+    // Screen position of the top-left of board cell (x, y), y counted from
+    // the top of the buffer. None for a cell up in the buffer.
+    fn cell_pos(&self, x: i16, y: i16) -> Option<(u16, u16)> {
+        let y = (y as usize).checked_sub(BUFFER_ROWS)? as u16;
+        let x = match self.mirror {
+            true => self.width as i16 - 1 - x,
+            false => x,
+        };
+        Some(self.board_to_screen(x as u16, y))
     }
 
-    // Translate tetromino left.
-    fn left(t: &mut Tetromino, w: usize, h: usize, board: &Vec<Vec<String>>) -> bool {
-        Self::translate(t, Point { x: -1, y: 0 }, w, h, board)
+    // Screen position of the top-left of the cell in screen column `x` and
+    // visible row `y` of the board, from the top-left inside the border. All
+    // the cell offsets on screen come through here.
+    fn board_to_screen(&self, x: u16, y: u16) -> (u16, u16) {
+        let (cols, rows) = self.layout.cell;
+        (
+            self.layout.left + 1 + x * cols,
+            self.layout.top + 1 + y * rows,
+        )
     }
 
-    // Translate tetromino right.
-    fn right(t: &mut Tetromino, w: usize, h: usize, board: &Vec<Vec<String>>) -> bool {
-        Self::translate(t, Point { x: 1, y: 0 }, w, h, board)
+    // Draw `cell`, a glyph and its style, over board cell (x, y). Big cells
+    // get it twice across and twice down.
+    fn put_cell(&mut self, x: i16, y: i16, cell: &Styled) {
+        let (left, top) = match self.cell_pos(x, y) {
+            Some(pos) => pos,
+            None => return,
+        };
+        let scale = self.layout.cell.1;
+        let cell = Styled::new(cell.text.repeat(scale as usize), cell.style);
+        for row in 0..scale {
+            self.renderer.draw_cell(left, top + row, &cell).unwrap();
+        }
     }
 
-    // Translate tetromino down.
-    fn down(t: &mut Tetromino, w: usize, h: usize, board: &Vec<Vec<String>>) -> bool {
-        Self::translate(t, Point { x: 0, y: 1 }, w, h, board)
+    // A whole board row, `cells` being one cell each, at visible row `y`
+    // (not counting the buffer).
+    fn put_row(&mut self, y: u16, cells: &[Styled]) {
+        let scale = self.layout.cell.1 as usize;
+        let big = |cell: &Styled| Styled::new(cell.text.repeat(scale), cell.style);
+        let line: Vec<Styled> = match self.mirror {
+            true => cells.iter().rev().map(big).collect(),
+            false => cells.iter().map(big).collect(),
+        };
+        let (left, top) = self.board_to_screen(0, y);
+        for row in 0..scale as u16 {
+            self.renderer.draw_row(left, top + row, &line).unwrap();
+        }
     }
 
-    fn rotate_counter_clockwise(t: &mut Tetromino, w: usize, h: usize, board: &Vec<Vec<String>>) {
-        // Center piece. So, here's the thing -- we need some center point to
-        // rotate around. For now, we just assume the 2nd piece to the rotation
-        // center. There is 4 blocks per tetromino now this works but maybe
-        // consider a size-agnostic way?
-        let cx = t.blocks[1].x;
-        let cy = t.blocks[1].y;
+    // Draw the HUD. One stat per row, so adding one is adding a row here.
+    fn draw_hud(&mut self) {
+        let (x, y) = self.layout.hud;
+        let stats: [(&str, String); HUD_ROWS as usize] = [
+            ("Score", self.score.to_string()),
+            ("Best", self.session_best.to_string()),
+            ("Level", self.level().to_string()),
+            ("", {
+                let (done, of) = self.level_progress();
+                format!(
+                    "{} {}/{}",
+                    self.plain(&progress_bar(done, of, LEVEL_BAR_WIDTH)),
+                    done,
+                    of
+                )
+            }),
+            ("Lines", self.lines_cleared.to_string()),
+            ("Time", format_time(self.play_time)),
+        ];
 
-        // Validate if rotation is within the board.
-        // yeah, yeah, I know having duplicate checks within validate and update.
-        // And I should probably create a transformed tetromino, validate, and
-        // if that passes replace the ref.
-        // TODO: Maybe do this? DRY ftw!
-        for block in t.blocks.iter() {
-            // To y'all who say programmers don't need math, check this out.
-            // So, lets go into what's going on. We know basic geometry.
-            // For a point (x, y) with center (0, 0), the counter-clockwise
-            // rotation would be (-y, x). I'm basically using this here.
-            // First, offset (x, y) by (-cx, -cy) a.k.a the center piece to
-            // get the block relative to a (0, 0) center. Then do the rotation,
-            // i.e., (-y, x) and then add back the offset (cx, cy).
-            let x = block.x - cx;
-            let y = block.y - cy;
-            let new_x = -y + cx;
-            let new_y = x + cy;
+        for (i, (label, value)) in stats.iter().enumerate() {
+            let y = y + i as u16;
 
-            if new_x < 0
-                || new_x >= (w as i16)
-                || new_y < 0
-                || new_y >= (h as i16)
-                || board[new_y as usize][new_x as usize] != EMPTY_CELL
-            {
-                return;
+            // Just beat the session best, the whole row gets the spotlight.
+            // The full width, so the wider label is fully replaced both
+            // ways.
+            if *label == "Best" && !self.new_best_timer.is_zero() {
+                let yellow = self.fg(Color::YELLOW).bold();
+                self.put_field(x, y, yellow, "NEW BEST!", HUD_WIDTH as usize);
+                continue;
             }
-        }
 
-        // Rotate
-        for i in 0..t.blocks.len() {
-            let x = t.blocks[i].x - cx;
-            let y = t.blocks[i].y - cy;
+            // Level up, the bar starts over and blinks for a bit.
+            let flashed = LEVEL_UP_FLASH.saturating_sub(self.level_up_timer);
+            let blink = (flashed.as_millis() / LEVEL_UP_BLINK.as_millis()).is_multiple_of(2);
+            if label.is_empty() && !self.level_up_timer.is_zero() && blink {
+                let flash = self.fg(Color::YELLOW).bold().invert();
+                self.put_field(x, y, Style::default(), "", 7);
+                self.put_field(x + 7, y, flash, value, HUD_WIDTH as usize - 7);
+                continue;
+            }
 
-            t.blocks[i].x = -y + cx;
-            t.blocks[i].y = x + cy;
+            let text = self.palette.text();
+            self.put_field(x, y, text.bold(), label, 7);
+            self.put_field(x + 7, y, text, value, HUD_WIDTH as usize - 7);
         }
     }
 
-    // clears completed lines and updates score.
-    // Scoring mechanism:
-    //  For now, each completed line adds 100 pts.
-    // Each press of the down key and make the fall faster adds 1 pt.
-    // TODO: clearing multiple lines together should have score multiple.
-    fn clear_completed_lines(&mut self) {
-        for i in (0..self.height).rev() {
-            // Check if the whole row is occupied.
-            let mut occupied = 0;
-            for j in 0..self.width {
-                if self.board[i][j] != EMPTY_CELL {
-                    occupied += 1;
-                }
-            }
+    // `text` in `style` at (x, y), padded out to `width` so whatever was
+    // there before is covered, however long it was. Everything that changes
+    // in place (the HUD, the messages around it) goes through here.
+    fn put_field(&mut self, x: u16, y: u16, style: Style, text: &str, width: usize) {
+        self.put_text(x, y, &format!("{:<width$}", text), style);
+    }
 
-            // If yes, update score.
-            if occupied == self.width {
-                self.score += 100;
-            }
+    // The last scoring event, on the spare row under the HUD. Always padded
+    // out so the longest message is covered when it goes.
+    fn draw_announcement(&mut self) {
+        let (x, y) = self.layout.hud;
+        let text = match self.announcement {
+            Some(event) if !self.announce_timer.is_zero() => event.text(),
+            _ => String::new(),
+        };
+        let cyan = self.fg(Color::CYAN).bold();
+        self.put_field(x, y + HUD_ROWS, cyan, &text, ANNOUNCE_WIDTH);
+    }
 
-            // Clear row if its all occupied or all free.
-            if occupied == 0 || occupied == self.width {
-                // If not row above, just clear the row.
-                if i == 0 {
-                    for j in 0..self.width {
-                        self.board[i][j] = String::from(EMPTY_CELL);
-                    }
-                } else {
-                    // fallllll
-                    for j in 0..self.width {
-                        self.board[i][j] = self.board[i - 1][j].clone();
-                        self.board[i - 1][j] = String::from(EMPTY_CELL);
-                    }
-                }
-            }
+    // The combo and back to back going right now, next to the score and
+    // the best. Padded out like the HUD so they're gone once the streak
+    // ends.
+    fn draw_streaks(&mut self) {
+        let (x, y) = self.layout.hud;
+        let x = x + HUD_WIDTH;
+        if x + STREAK_WIDTH - 1 > self.origin + self.layout.screen.0 {
+            return;
+        }
+
+        // Matches the announcement: the first clear doesn't count yet.
+        let combo = match self.combo > 1 {
+            true => format!("COMBO x{}", self.combo - 1),
+            false => String::new(),
+        };
+        let b2b = match self.back_to_back {
+            true => "B2B",
+            false => "",
+        };
+        let mode = match self.mode {
+            GameMode::Invisible => format!("v x{}", self.reveals),
+            GameMode::Zen if self.sweeps > 0 => format!("SWEPT x{}", self.sweeps),
+            _ => String::new(),
+        };
+        let yellow = self.fg(Color::YELLOW).bold();
+        for (i, text) in [combo.as_str(), b2b, mode.as_str()].iter().enumerate() {
+            self.put_field(x, y + i as u16, yellow, text, STREAK_WIDTH as usize);
         }
     }
 
-    fn draw(&mut self) {
-        // Draw the board.
-        for (j, row) in self.board.iter().enumerate() {
-            // Goto line.
-            write!(self.stdout, "{}", termion::cursor::Goto(2, (j as u16) + 2)).unwrap();
+    // How many of each piece were dealt, NES style. Each piece is drawn in
+    // its own color next to its count.
+    fn draw_piece_stats(&mut self) {
+        if !self.layout.show_piece_stats {
+            return;
+        }
 
-            // Write line.
-            for cell in row.iter() {
-                write!(self.stdout, "{}", cell).unwrap();
-            }
+        let (x, y) = self.layout.hud;
+        let y = y + HUD_ROWS + 1;
+        for (i, &shape) in Shape::ALL.iter().enumerate() {
+            // Practice picks pieces by number, so the numbers go in front.
+            let number = match self.mode {
+                GameMode::Practice => format!("{} ", i + 1),
+                _ => String::new(),
+            };
+            let count = format!(" {} {:<4}", shape.letter(), self.piece_counts[i]);
+            let cell = self.palette.cell(shape).to_string();
+            let spans = [
+                (number.as_str(), Style::default()),
+                (cell.as_str(), self.palette.piece(shape)),
+                (count.as_str(), Style::default()),
+            ];
+            self.put_spans(x, y + i as u16, &spans);
         }
 
-        // Reset cursor
-        write!(self.stdout, "{}", termion::cursor::Goto(1, 1)).unwrap();
+        // Then the pace.
+        let y = y + Shape::ALL.len() as u16;
+        let pace = [
+            ("Pieces", self.pieces_placed.to_string()),
+            ("PPS", format!("{:.2}", self.pps())),
+        ];
+        for (i, (label, value)) in pace.iter().enumerate() {
+            let y = y + i as u16;
+            let text = self.palette.text();
+            self.put_field(x, y, text.bold(), label, 7);
+            self.put_field(x + 7, y, text, value, HUD_WIDTH as usize - 7);
+        }
     }
 
-    // draw the falling piece.
-    fn draw_falling(&mut self) {
-        if let Some(t) = self.falling.as_ref() {
-            for block in t.blocks.iter() {
-                // Goto position.
-                write!(
-                    self.stdout,
-                    "{}",
-                    termion::cursor::Goto((block.x as u16) * 2 + 2, (block.y as u16) + 2)
-                )
-                .unwrap();
+    // Init game screen.
+    fn init_screen(&mut self) {
+        self.needs_redraw = false;
+        if self.narrator.is_on() {
+            return;
+        }
 
-                // Draw block.
-                write!(self.stdout, "{}[]{}", t.color, style::Reset).unwrap();
-            }
+        // Hide cursor
+        self.renderer.hide_cursor().unwrap();
+
+        // Clear display. Unless half of it is the other player's.
+        if !self.versus {
+            self.renderer.clear().unwrap();
+        }
+
+        // Print box.
+        self.print_box(self.border);
+
+        // Print coordinate labels.
+        self.print_labels();
+
+        // Print HUD.
+        self.relayout();
+        if !matches!(self.state, GameState::Title) {
+            self.draw_hud();
         }
+
+        // Print keys help.
+        self.draw_hint();
     }
 
-    // Draw game over
-    fn draw_game_over(&mut self) {
-        if matches!(self.state, GameState::LOSE) {
-            // Goto middle
-            self.goto(4, (self.width / 2 + 2) as u16);
-
-            // Draw
-            write!(
-                self.stdout,
-                "{}{}GAME OVER ☹️{}",
-                style::Bold,
-                color::Fg(color::Red),
-                color::Fg(color::Reset)
-            )
-            .unwrap();
+    // What the keys do, from the bindings, centered under everything else.
+    // Wraps onto a second line on a narrow terminal and is cut off after
+    // that.
+    fn draw_hint(&mut self) {
+        let y = match self.layout.hint {
+            Some(y) => y,
+            None => return,
+        };
+
+        // Only what does something in this game.
+        let cols = self.layout.screen.0 as usize;
+        let mut left_out = Vec::new();
+        if !self.hold_on {
+            left_out.push(Action::Hold);
+        }
+        if self.mode != GameMode::Practice {
+            left_out.extend(keys::PICKS);
+            left_out.push(Action::Undo);
+        }
+        if self.mode != GameMode::Invisible {
+            left_out.push(Action::Reveal);
+        }
+        let lines = keys::wrap(&keys::hint(&left_out), cols);
+        for (i, line) in lines.iter().take(layout::HINT_ROWS as usize).enumerate() {
+            let x = (cols - line.chars().count()) / 2;
+            let line = self.plain(line);
+            let faint = Style::default().faint();
+            self.put_text(self.origin + x as u16 + 1, y + i as u16, &line, faint);
         }
     }
 
-    // Validate if done falling.
-    fn done_falling(&self) -> bool {
-        if let Some(t) = &self.falling.as_ref() {
-            // If any of the blocks sit on another block/ground, the block is done
-            // falling.
-            for block in t.blocks.iter() {
-                if block.y >= (self.height as i16) - 1
-                    || self.board[(block.y + 1) as usize][block.x as usize] != EMPTY_CELL
-                {
-                    return true;
-                }
+    // A T that got into place by rotating, with at least three of the four
+    // corners around its middle taken. Walls and floor count as taken.
+    fn is_t_spin(&self) -> bool {
+        let t = match self.falling.as_ref() {
+            Some(t) if t.shape == Shape::T && self.rotated_last => t,
+            _ => return false,
+        };
+
+        let center = t.pos;
+        let taken = [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .iter()
+            .filter(|(dx, dy)| !self.board.is_free(center.x + dx, center.y + dy))
+            .count();
+
+        taken >= 3
+    }
+
+    // Work out what the piece that just locked set off. The lines themselves
+    // go after the flash, but the events are known now.
+    fn score_lock(&mut self, t_spin: bool) {
+        let lines = self.completed_lines().len();
+        self.events.clear();
+
+        if lines == 0 {
+            self.combo = 0;
+            if t_spin {
+                self.events.push(Event::TSpin);
+            }
+        } else {
+            let hard = lines == 4 || t_spin;
+            self.events.push(Event::Clear {
+                lines,
+                t_spin,
+                back_to_back: hard && self.back_to_back,
+            });
+            self.back_to_back = hard;
+
+            self.combo += 1;
+            if self.combo > 1 {
+                self.events.push(Event::Combo(self.combo - 1));
+                self.max_combo = self.max_combo.max(self.combo - 1);
             }
         }
 
-        return false;
+        if let Some(top) = self.events.iter().copied().max_by_key(|e| e.priority()) {
+            self.announcement = Some(top);
+            self.announce_timer = ANNOUNCE_DURATION;
+        }
+        if !self.events.is_empty() {
+            let said: Vec<String> = self.events.iter().map(|e| e.text()).collect();
+            self.narrator.say(format_args!("{}", said.join(" ")));
+        }
+    }
+
+    // Put `shape` in at the top as the falling piece, in place of whatever
+    // was falling. False, and nothing changes, if it doesn't fit.
+    pub fn spawn(&mut self, shape: Shape) -> bool {
+        self.spawn_piece(Tetromino::of(shape))
     }
 
-    fn update_game_state(&mut self) {
-        // let's keep it stupid simple -- if board[0][center] is occupied, it's
-        // game over. Is it hacky if it works?
-        if self.board[0][(self.width / 2) - 1] != EMPTY_CELL
-            || self.board[1][(self.width / 2) - 1] != EMPTY_CELL
-        {
-            self.state = GameState::LOSE;
+    // Center `t` at the top and make it the falling piece, if it fits.
+    fn spawn_piece(&mut self, mut t: Tetromino) -> bool {
+        if !self.move_to_spawn(&mut t) {
+            return false;
         }
+
+        self.piece_counts[t.shape as usize] += 1;
+        self.log
+            .event(self.play_time, format_args!("spawn {}", t.shape.letter()));
+        self.narrator.say(format_args!(
+            "{}, column {}",
+            t.shape.letter(),
+            Self::column(&t) + 1
+        ));
+        self.falling = Some(t);
+        self.rotated_last = false;
+        self.clock_started = true;
+        self.spawn_score = self.score;
+        true
     }
 
-    // Start the game.
-    pub fn run(&mut self) {
-        self.init_screen();
+    // Practice: remember how things were before the falling piece locks.
+    fn save_undo(&mut self) {
+        let shape = match self.falling.as_ref() {
+            Some(t) => t.shape,
+            None => return,
+        };
+        if self.undo.len() == UNDO_DEPTH {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(Snapshot {
+            board: self.board.clone(),
+            score: self.spawn_score,
+            lines_cleared: self.lines_cleared,
+            piece_counts: self.piece_counts,
+            pieces_placed: self.pieces_placed,
+            back_to_back: self.back_to_back,
+            combo: self.combo,
+            queue: self.queue.clone(),
+            held: self.held,
+            hold_used: self.hold_used,
+            shape,
+        });
+    }
 
-        let mut old_time = Instant::now();
-        'game: loop {
-            // Game Over :(
-            if matches!(self.state, GameState::LOSE) {
-                self.draw_game_over();
-                break;
-            }
-
-            if let Some(t) = self.falling.as_mut() {
-                // This block handles the tetrominos falling. This works independent of the current frame rate.
-                // Maybe there are better ways of handling this but hey, this works.
-                if old_time.elapsed().as_millis() >= FALL_RATE_MS {
-                    // fall.
-                    Self::down(t, self.width, self.height, &self.board);
-
-                    // Reset clock.
-                    old_time = Instant::now();
-                }
+    // Practice: take back the last lock, clear and all, and drop the piece
+    // in again from the top.
+    fn undo(&mut self) {
+        let snapshot = match self.undo.pop_back() {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        self.board = snapshot.board;
+        self.score = snapshot.score;
+        self.spawn_score = snapshot.score;
+        self.lines_cleared = snapshot.lines_cleared;
+        self.piece_counts = snapshot.piece_counts;
+        self.pieces_placed = snapshot.pieces_placed;
+        self.back_to_back = snapshot.back_to_back;
+        self.combo = snapshot.combo;
+        self.queue = snapshot.queue;
+        self.held = snapshot.held;
+        self.hold_used = snapshot.hold_used;
+        self.clearing.clear();
+        self.popups.clear();
+        self.trail.clear();
+        self.announcement = None;
 
-                // Next move.
-                // Bad design aravind, bad design.
-                // users can't quit if there is no block!
-                match self.stdin.next() {
-                    Some(Ok(key)) => {
-                        match key {
-                            Key::Char('q') => break 'game, // Quit
-                            Key::Char('a') | Key::Left => {
-                                Self::left(t, self.width, self.height, &self.board);
-                            }
-                            Key::Char('s') | Key::Down => {
-                                Self::down(t, self.width, self.height, &self.board);
-                                self.score += 1;
-                            }
-                            Key::Char('d') | Key::Right => {
-                                Self::right(t, self.width, self.height, &self.board);
-                            }
-                            Key::Char('w') | Key::Up => Self::rotate_counter_clockwise(
-                                t,
-                                self.width,
-                                self.height,
-                                &self.board,
-                            ),
-                            _ => (),
-                        };
-                    }
-                    _ => {}
-                }
-            } else {
-                // Create a new falling piece if there isn't one currently.
-                let mut t = Tetromino::random();
-
-                // center it.
-                // If center fails since the piece overlaps, the game is over.
-                if !Self::translate(
-                    &mut t,
-                    Point {
-                        x: ((self.width / 2) as i16) - 1,
-                        y: 0,
-                    },
-                    self.width,
-                    self.height,
-                    &self.board,
-                ) {
-                    self.state = GameState::LOSE;
-                }
+        // Whatever came after it goes back in the queue.
+        if let Some(t) = self.falling.take() {
+            self.queue.push_front(Tetromino::of(t.shape));
+        }
+        let mut t = Tetromino::of(snapshot.shape);
+        if self.move_to_spawn(&mut t) {
+            self.falling = Some(t);
+            self.rotated_last = false;
+        } else {
+            self.queue.push_front(t);
+        }
+    }
 
-                self.falling = Some(t);
+    fn insert_falling(&mut self) {
+        if let Some(t) = self.falling.as_ref() {
+            for block in t.cells() {
+                self.board.try_set(block.x, block.y, Cell::Filled(t.shape));
             }
+            self.pieces_placed += 1;
 
-            // All the game checks here.
-            // Check if done falling, i.e., touches the ground or another block.
-            if self.done_falling() {
-                self.insert_falling();
+            if self.log.is_on() {
+                let blocks: Vec<String> = t
+                    .cells()
+                    .iter()
+                    .map(|block| format!("{},{}", block.x, block.y - BUFFER_ROWS as i16))
+                    .collect();
+                self.log.event(
+                    self.play_time,
+                    format_args!(
+                        "lock {} {} +{}",
+                        t.shape.letter(),
+                        blocks.join(" "),
+                        self.score - self.spawn_score
+                    ),
+                );
             }
 
-            // Clear completed lines
-            self.clear_completed_lines();
-
-            // Draw board.
-            self.draw();
+            self.narrator
+                .say(format_args!("{} locked", t.shape.letter()));
 
-            // Draw score
-            self.print_score();
-
-            // Draw falling.
-            self.draw_falling();
+            if self.mode == GameMode::Invisible {
+                self.fading
+                    .extend(t.cells().iter().map(|&block| (block, INVISIBLE_DELAY)));
+            }
+        }
 
-            // Flush stdout
-            self.stdout.flush().unwrap();
+        self.falling = None; // The board absorbs the falling piece.
+        self.hold_used = false;
+    }
 
-            // Update game state
-            self.update_game_state();
+    // Rows that are fully occupied, top to bottom.
+    fn completed_lines(&self) -> Vec<usize> {
+        (0..self.board.height())
+            .filter(|&i| self.board.is_full(i))
+            .collect()
+    }
+
+    // clears completed lines and updates score.
+    // Scoring mechanism:
+    //  Each completed line is 100 pts times the level it was cleared on, so
+    //  starting higher pays more from the first line.
+    // Soft drops score on their own, per row.
+    fn clear_completed_lines(&mut self) {
+        let rows = self.completed_lines();
+        let completed = rows.len();
+        let level = self.level();
+        let points = 100 * completed as i64 * level as i64;
+        self.score += points;
+        self.lines_cleared += completed;
+        if completed > 0 {
+            self.log.event(
+                self.play_time,
+                format_args!("clear {} +{}", completed, points),
+            );
+            self.narrator
+                .say(format_args!("{} lines, score {}", completed, self.score));
+        }
+
+        if self.effects && completed > 0 {
+            let text = match completed {
+                4 => format!("+{} TETRIS", points),
+                _ => format!("+{}", points),
+            };
+            self.popups.push(Popup {
+                text,
+                row: rows[0],
+                age: Duration::ZERO,
+            });
+        }
+
+        // fallllll
+        self.board.remove_full_rows();
+
+        // Whatever is still in sight comes down with the rest.
+        self.fading
+            .retain(|(block, _)| !rows.contains(&(block.y as usize)));
+        for (block, _) in self.fading.iter_mut() {
+            block.y += rows.iter().filter(|&&row| row > block.y as usize).count() as i16;
+        }
+
+        // Cascade: the lumps that are left fall as far as they go, and any
+        // lines that fills go too, each link of the chain worth more.
+        if self.gravity == Gravity::Cascade && completed > 0 {
+            let mut chain = 1;
+            while gravity::settle(&mut self.board) {
+                // Nothing's where it was, in sight or not.
+                self.fading.clear();
+                let rows = self.completed_lines();
+                if rows.is_empty() {
+                    break;
+                }
+                chain += 1;
+                let points = 100 * rows.len() as i64 * level as i64 * chain;
+                self.score += points;
+                self.lines_cleared += rows.len();
+                self.log.event(
+                    self.play_time,
+                    format_args!("clear {} +{} chain x{}", rows.len(), points, chain),
+                );
+                if self.effects {
+                    self.popups.push(Popup {
+                        text: format!("+{} CHAIN x{}", points, chain),
+                        row: rows[0],
+                        age: Duration::ZERO,
+                    });
+                }
+                self.board.remove_full_rows();
+            }
+        }
+
+        if self.level() > level {
+            self.level_up_timer = LEVEL_UP_FLASH;
+            self.narrator.say(format_args!("level {}", self.level()));
+        }
+    }
+
+    // The cell as it's drawn. Invisible hides the stack, bar what's only
+    // just locked, unless it's being revealed or the game's over.
+    fn shown(&self, x: usize, y: usize) -> Cell {
+        let cell = self.board.get(x, y);
+        let hidden = self.mode == GameMode::Invisible
+            && matches!(cell, Cell::Filled(_))
+            && self.reveal_timer.is_zero()
+            && !self.is_over()
+            && !self
+                .fading
+                .iter()
+                .any(|(block, _)| (block.x as usize, block.y as usize) == (x, y));
+        match hidden {
+            true => Cell::Empty,
+            false => cell,
+        }
+    }
+
+    // An empty cell in column `x`, as it's drawn. With stripes on, every
+    // other column gets a dim background, or `.` for `·` without colors.
+    fn empty_text(&self, x: usize) -> Styled {
+        let striped = self.stripes && x % 2 == 1;
+        let empty = self.palette.empty_cell();
+        match (striped, self.palette.stripe()) {
+            (true, Some(stripe)) => Styled::new(empty, self.palette.empty().background(stripe)),
+            (true, None) => Styled::new(empty.replace('·', "."), Style::default()),
+            (false, _) => Styled::new(empty, self.palette.empty()),
+        }
+    }
+
+    // A board cell in column `x`, as it's drawn. `faded` is for under a
+    // panel.
+    fn cell_text(&self, cell: Cell, x: usize, faded: bool) -> Styled {
+        match cell {
+            Cell::Empty => self.empty_text(x),
+            // Locked cells are drawn faint so the falling piece stands
+            // out. Once it's over, or paused, everything goes faint under
+            // the panel.
+            Cell::Filled(shape) if self.dim_locked || faded => {
+                Styled::new(self.palette.cell(shape), self.palette.piece(shape).faint())
+            }
+            Cell::Filled(shape) => Styled::new(self.palette.cell(shape), self.palette.piece(shape)),
+            Cell::Garbage => Styled::new(self.palette.garbage_cell(), self.palette.garbage()),
+        }
+    }
+
+    // The glyph `cell` is drawn with, whatever style it's in.
+    fn glyph(&self, cell: Cell) -> &str {
+        match cell {
+            Cell::Empty => self.palette.empty_cell(),
+            Cell::Filled(shape) => self.palette.cell(shape),
+            Cell::Garbage => self.palette.garbage_cell(),
+        }
+    }
+
+    fn draw(&mut self) {
+        // Thud. Right after a hard drop the whole stack sits a row lower for
+        // a frame. The bottom row would land on the border so it sits this
+        // one out, and the top row is drawn empty.
+        let shake = (self.shake_frames > 0) as u16;
+        if shake > 0 {
+            let row: Vec<Styled> = (0..self.width).map(|i| self.empty_text(i)).collect();
+            self.put_row(0, &row);
+        }
+
+        // Draw the board. Only what's under the buffer, j counts from the
+        // top of the buffer. It all goes faint under the pause menu too.
+        let faded = matches!(self.state, GameState::Lose | GameState::Pause);
+        for j in BUFFER_ROWS..self.board.height() {
+            if shake > 0 && j == self.board.height() - 1 {
+                continue;
+            }
+
+            // Completed rows flash before they go. Blink, with the effects
+            // on, if they are held long enough for it.
+            let held = self.line_clear_delay.saturating_sub(self.clear_timer);
+            let blink_off =
+                self.effects && (held.as_millis() / LINE_CLEAR_BLINK.as_millis()) % 2 == 1;
+            let cells: Vec<Styled> = match self.clearing.contains(&j) && !blink_off {
+                true => {
+                    let flash = self.fg(Color::WHITE).invert();
+                    (0..self.width)
+                        .map(|i| Styled::new(self.glyph(self.board.get(i, j)), flash))
+                        .collect()
+                }
+                false => (0..self.width)
+                    .map(|i| self.cell_text(self.shown(i, j), i, faded))
+                    .collect(),
+            };
+            self.put_row((j - BUFFER_ROWS) as u16 + shake, &cells);
+        }
+
+        // Reset cursor
+        self.goto(1, 1);
+    }
+
+    // Draw the hard drop trail and count down the hard drop effects. The
+    // trail only goes on empty cells, and the next draw() paints over all of
+    // it, so nothing is left behind once they run out.
+    fn draw_effects(&mut self) {
+        // Trail shakes with the stack.
+        let shake = (self.shake_frames > 0) as u16;
+        if self.trail_frames > 0 {
+            self.trail_frames -= 1;
+
+            let trail = std::mem::take(&mut self.trail);
+            for p in trail.iter() {
+                if (p.y as usize) < BUFFER_ROWS
+                    || self.board.get_checked(p.x, p.y) != Some(Cell::Empty)
+                {
+                    continue;
+                }
+                let y = p.y + shake as i16;
+                if y as usize >= self.board.height() {
+                    continue;
+                }
+
+                let trail = Styled::new(
+                    self.plain("¦ "),
+                    self.palette.piece(self.trail_shape).faint(),
+                );
+                self.put_cell(p.x, y, &trail);
+            }
+            self.trail = trail;
+        }
+
+        self.shake_frames = self.shake_frames.saturating_sub(1);
+    }
+
+    // Draw the score popups. They stay inside the board, so the next draw()
+    // paints over whatever they covered.
+    fn draw_popups(&mut self) {
+        let popups = std::mem::take(&mut self.popups);
+        let cols = self.layout.inner.0 as usize;
+
+        for popup in popups.iter() {
+            let row = match popup.row().and_then(|row| row.checked_sub(BUFFER_ROWS)) {
+                Some(row) => row,
+                None => continue,
+            };
+            let text: String = popup.text.chars().take(cols).collect();
+            let x = (cols - text.chars().count()) / 2;
+
+            let (left, top) = self.board_to_screen(0, row as u16);
+            let mut style = self.fg(Color::YELLOW).bold();
+            if popup.age * 3 >= POPUP_DURATION * 2 {
+                style = style.faint();
+            }
+            self.put_text(left + x as u16, top, &text, style);
+        }
+
+        self.popups = popups;
+    }
+
+    // The next few pieces, stacked under a "Next" header. Each gets the same
+    // number of rows whatever its shape, wiped before it's drawn.
+    fn draw_preview(&mut self) {
+        if self.layout.preview_shown == 0 {
+            return;
+        }
+
+        let (x, y) = self.layout.preview;
+        self.put_text(x, y, "Next", Style::default().bold());
+
+        let queue: Vec<Tetromino> = self
+            .queue
+            .iter()
+            .take(self.layout.preview_shown)
+            .cloned()
+            .collect();
+        for (i, t) in queue.iter().enumerate() {
+            let top = y + 1 + i as u16 * PREVIEW_ROWS;
+            for row in 0..PREVIEW_ROWS - 1 {
+                self.put_text(x, top + row, &" ".repeat(8), Style::default());
+            }
+
+            let cell = self.palette.cell(t.shape).to_string();
+            for block in t.cells() {
+                let (bx, by) = (x + block.x as u16 * 2, top + block.y as u16);
+                self.put_text(bx, by, &cell, self.palette.piece(t.shape));
+            }
+        }
+    }
+
+    // Screen reader mode, on or off.
+    pub fn set_screen_reader(&mut self, on: bool) {
+        self.narrator = match on {
+            true => Narrator::on(),
+            false => Narrator::default(),
+        };
+        if on && matches!(self.state, GameState::Title) {
+            self.narrator.say(format_args!(
+                "tetris. enter to play, k reads the stack heights, q quits"
+            ));
+        }
+    }
+
+    // Screen reader: a move or rotation, if it went anywhere. Gravity is
+    // left out, `before` only counts what the key did.
+    fn say_move(&mut self, action: Option<Action>, before: Option<(i16, [Point; 4])>) {
+        let t = match self.falling.as_ref() {
+            Some(t) if self.narrator.is_on() => t,
+            _ => return,
+        };
+        let (column, orientation) = match before {
+            Some(before) => before,
+            None => return,
+        };
+        let now = Self::column(t);
+        let what = match action {
+            Some(Action::Left | Action::Right) if now != column => match now < column {
+                true => "left",
+                false => "right",
+            },
+            Some(Action::RotateLeft | Action::RotateRight)
+                if Self::orientation(t) != orientation =>
+            {
+                "rotated"
+            }
+            _ => return,
+        };
+        self.narrator
+            .moved(format_args!("{}, column {}", what, now + 1));
+    }
+
+    // Leftmost column `t` takes up.
+    fn column(t: &Tetromino) -> i16 {
+        t.cells().iter().map(|b| b.x).min().unwrap()
+    }
+
+    // `t`'s blocks relative to its top-left corner, the same wherever it
+    // is as long as it's the same way round.
+    fn orientation(t: &Tetromino) -> [Point; 4] {
+        let x = Self::column(t);
+        let y = t.cells().iter().map(|b| b.y).min().unwrap();
+        let mut blocks = t.cells().map(|b| Point {
+            x: b.x - x,
+            y: b.y - y,
+        });
+        blocks.sort_by_key(|b| (b.y, b.x));
+        blocks
+    }
+
+    // Swap the falling piece for the held one, or the next one if nothing's
+    // held yet. Once a piece, does nothing after that until it locks.
+    fn hold(&mut self) {
+        let shape = match self.falling.as_ref() {
+            Some(t) if !self.hold_used => t.shape,
+            _ => return,
+        };
+        let (t, dealt) = match self.held.replace(shape) {
+            Some(held) => (Tetromino::of(held), false),
+            None => {
+                let t = self.queue.pop_front().unwrap();
+                self.refill_queue();
+                (t, true)
+            }
+        };
+        self.hold_used = true;
+        self.fall_timer = Duration::ZERO;
+        self.narrator
+            .say(format_args!("holding {}", shape.letter()));
+        if !self.spawn_piece(t) {
+            self.top_out();
+            return;
+        }
+        // The held one was counted when it was dealt.
+        if !dealt {
+            self.piece_counts[self.falling.as_ref().unwrap().shape as usize] -= 1;
+        }
+    }
+
+    // The held piece under the preview, faint once it's been swapped for
+    // this piece.
+    fn draw_hold(&mut self) {
+        let (x, y) = match self.layout.hold {
+            Some(at) => at,
+            None => return,
+        };
+        self.put_text(x, y, "Hold", Style::default().bold());
+        for row in 0..PREVIEW_ROWS - 1 {
+            self.put_text(x, y + 1 + row, &" ".repeat(8), Style::default());
+        }
+
+        let t = match self.held {
+            Some(shape) => Tetromino::of(shape),
+            None => return,
+        };
+        let style = match self.hold_used {
+            true => self.palette.piece(t.shape).faint(),
+            false => self.palette.piece(t.shape),
+        };
+        let cell = self.palette.cell(t.shape).to_string();
+        for block in t.cells() {
+            let (bx, by) = (x + block.x as u16 * 2, y + 1 + block.y as u16);
+            self.put_text(bx, by, &cell, style);
+        }
+    }
+
+    // Top the queue up to MAX_PREVIEW pieces.
+    fn refill_queue(&mut self) {
+        while self.queue.len() < MAX_PREVIEW {
+            self.queue
+                .push_back(Tetromino::of(self.dealer.next_shape()));
+        }
+    }
+
+    // Start dealing over with the current randomizer and seed.
+    fn redeal(&mut self) {
+        self.dealer = Dealer::new(self.randomizer, self.seed);
+        self.garbage_rng = StdRng::seed_from_u64(self.seed);
+        self.queue.clear();
+        self.refill_queue();
+    }
+
+    // Where the falling piece would land.
+    fn ghost(&self) -> Option<Tetromino> {
+        let mut ghost = self.falling.clone()?;
+        while self.board.try_move(&mut ghost, Point::DOWN) {}
+        Some(ghost)
+    }
+
+    // draw the falling piece, and its ghost under it.
+    fn draw_falling(&mut self) {
+        if let Some(ghost) = self.ghost().filter(|_| self.ghost_on) {
+            let cell = Styled::new(
+                self.palette.ghost_cell(ghost.shape),
+                self.palette.ghost(ghost.shape).faint(),
+            );
+            for block in ghost.cells() {
+                self.put_cell(block.x, block.y, &cell);
+            }
+        }
+
+        // The piece goes on top, so where the two overlap the piece wins.
+        // Faint with the rest of the board while paused.
+        if let Some(t) = self.falling.clone() {
+            let style = match self.state {
+                GameState::Pause => self.palette.piece(t.shape).faint(),
+                _ => self.palette.piece(t.shape),
+            };
+            let cell = Styled::new(self.palette.cell(t.shape), style);
+            for block in t.cells() {
+                self.put_cell(block.x, block.y, &cell);
+            }
+        }
+    }
+
+    // The number the countdown is on, over the middle of the board.
+    fn draw_countdown(&mut self) {
+        if self.countdown.is_zero() || !matches!(self.state, GameState::Play) {
+            return;
+        }
+
+        let n = self
+            .countdown
+            .as_millis()
+            .div_ceil(COUNTDOWN_STEP.as_millis());
+        let (cols, rows) = self.layout.inner;
+        let x = self.layout.left + cols / 2 - 1;
+        let y = self.layout.top + 1 + rows / 2 - 1;
+        self.put_text(x, y, &format!(" {} ", n), Style::default().bold());
+    }
+
+    // Draw the pause menu over the middle of the board. The next frame's
+    // draw() paints over it once the game is back on.
+    fn draw_pause_menu(&mut self) {
+        if !matches!(self.state, GameState::Pause) {
+            return;
+        }
+
+        let (cols, rows) = self.layout.inner;
+        let x = self.layout.left + cols / 2 - 3;
+        let y = self.layout.top + 1 + rows / 2 - 2;
+
+        self.put_text(x, y, "  PAUSED  ", Style::default().bold());
+
+        for (i, &item) in PAUSE_MENU.iter().enumerate() {
+            // Modifiers say whether they're on.
+            let item = match item {
+                "Mirror" if self.mirror => "Mirror on".to_string(),
+                "Mirror" => "Mirror off".to_string(),
+                _ => item.to_string(),
+            };
+            let y = y + 2 + i as u16;
+            if i == self.menu_selection {
+                let line = format!("> {:<10}", item);
+                self.put_text(x, y, &line, Style::default().invert());
+            } else {
+                self.put_text(x, y, &format!("  {:<10}", item), Style::default());
+            }
+        }
+    }
+
+    // Blank out the board's area and put the size it needs in the middle.
+    // The game is drawn from scratch once it fits.
+    fn draw_too_small(&mut self) {
+        self.needs_redraw = true;
+        let (cols, rows) = self.layout.screen;
+        if self.too_small_shown == Some((cols, rows)) {
+            return;
+        }
+        self.too_small_shown = Some((cols, rows));
+
+        let blank = " ".repeat(cols as usize);
+        for y in 1..=rows {
+            self.put_text(self.origin + 1, y, &blank, Style::default());
+        }
+
+        // One line if it fits, a line a part if not.
+        let (need_cols, need_rows) = self.layout.need;
+        let parts = [
+            "Terminal too small:".to_string(),
+            format!("need at least {}x{},", need_cols, need_rows),
+            format!("have {}x{}", cols, rows),
+        ];
+        let text = parts.join(" ");
+        let lines = match text.len() <= cols as usize {
+            true => vec![text],
+            false => parts.to_vec(),
+        };
+
+        let y = rows.saturating_sub(lines.len() as u16) / 2;
+        for (i, line) in lines.iter().enumerate() {
+            let x = cols.saturating_sub(line.len() as u16) / 2;
+            self.put_text(
+                self.origin + x + 1,
+                y + i as u16 + 1,
+                line,
+                Style::default(),
+            );
+        }
+    }
+
+    // The title screen. The HUD is off while it's up, so the full high score
+    // table gets the room right of the board too.
+    fn draw_title(&mut self) {
+        if !matches!(self.state, GameState::Title) {
+            return;
+        }
+
+        let x = self.layout.left + 1;
+        let y = self.layout.top + 2;
+        let cols = self.layout.inner.0 as usize;
+
+        let name = format!("{:^cols$}", "T E T R I S");
+        self.put_text(x, y, &name, self.fg(Color::CYAN).bold());
+
+        if let Some(date) = self.daily.as_ref() {
+            let label = format!("{:^cols$}", format!("Daily {}", date));
+            self.put_text(x, y + 1, &label, Style::default());
+        }
+
+        if !self.title_scores {
+            let keys = ["enter play", "h high scores", "q quit"];
+            for (i, line) in keys.iter().enumerate() {
+                let line = format!("{:^cols$}", line);
+                self.put_text(x, y + 2 + i as u16, &line, Style::default());
+            }
+            return;
+        }
+
+        let row = |rank: &str, name: &str, score: &str, date: &str, mode: &str| {
+            format!(
+                "{:>2} {:<name_width$} {:>7}  {:<10} {}",
+                rank,
+                name,
+                score,
+                date,
+                mode,
+                name_width = MAX_NAME_LEN
+            )
+        };
+
+        let header = row("#", "Name", "Score", "Date", "Mode");
+        self.put_text(x, y + 2, &header, Style::default().bold());
+
+        let lines: Vec<String> = self
+            .high_scores
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                row(
+                    &(i + 1).to_string(),
+                    &entry.name,
+                    &entry.score.to_string(),
+                    &entry.date,
+                    &entry.mode,
+                )
+            })
+            .collect();
+        if lines.is_empty() {
+            self.put_text(x, y + 3, "No scores yet", Style::default());
+        }
+        for (i, line) in lines.iter().enumerate() {
+            self.put_text(x, y + 3 + i as u16, line, Style::default());
+        }
+
+        let keys = self.plain("h back · enter play");
+        let y = y + 4 + highscore::MAX_ENTRIES as u16;
+        self.put_text(x, y, &keys, Style::default());
+    }
+
+    // Draw the name prompt for the high score table.
+    fn draw_name_entry(&mut self) {
+        if !matches!(self.state, GameState::NameEntry) {
+            return;
+        }
+
+        let x = self.layout.left + 2;
+        let y = self.layout.top + 1 + self.layout.inner.1 / 2 - 1;
+
+        self.put_text(x, y, "NEW HIGH SCORE!", self.fg(Color::YELLOW).bold());
+
+        // Pad so a backspaced char doesn't linger.
+        let name = format!(
+            "Name: {:<width$}",
+            format!("{}_", self.name),
+            width = MAX_NAME_LEN + 1
+        );
+        self.put_text(x, y + 1, &name, Style::default());
+    }
+
+    // Frame time and FPS, bottom right corner of the screen where nothing
+    // else goes.
+    fn draw_debug(&mut self) {
+        if !self.debug {
+            return;
+        }
+
+        let (cols, rows) = self
+            .screen_size
+            .or_else(|| termion::terminal_size().ok())
+            .unwrap_or((80, 24));
+        let ms = |d: Duration| format!("{:.2}ms", d.as_secs_f64() * 1000.0);
+        let stats = &self.frame_stats;
+        let lines = [
+            ("fps", format!("{:.1}", stats.fps())),
+            ("frame avg", ms(stats.avg_work())),
+            ("frame max", ms(stats.max_work())),
+            ("update", ms(stats.avg_update())),
+            ("render", ms(stats.avg_render())),
+            ("bytes/frame", stats.avg_bytes().to_string()),
+            ("gravity", format!("{}ms", self.fall_rate_ms())),
+        ];
+
+        // Bottom right corner, clear of the board.
+        let top = (rows + 1).saturating_sub(lines.len() as u16).max(1);
+        let x = cols.saturating_sub(DEBUG_WIDTH) + 1;
+        for (i, (label, value)) in lines.iter().enumerate() {
+            let line = format!(" {:<11}{:>7}", label, value);
+            self.put_text(x, top + i as u16, &line, Style::default().faint());
+        }
+    }
+
+    // "tetris — Marathon — 12,400 pts — lvl 7", sent only when it changes.
+    // One board of a versus match doesn't get to name the window.
+    fn update_title(&mut self) {
+        if !self.title || self.versus {
+            return;
+        }
+
+        let title = match self.state {
+            GameState::Title => "tetris".to_string(),
+            _ => self.plain(&format!(
+                "tetris — {} — {} pts — lvl {}",
+                self.mode_name(),
+                thousands(self.score),
+                self.level()
+            )),
+        };
+        if self.title_shown.as_ref() != Some(&title) {
+            self.renderer.set_title(&title).unwrap();
+            self.title_shown = Some(title);
+        }
+    }
+
+    // Top-left of a `width` by `rows` panel centered on the board, across
+    // and down. Narrow boards get the panel hanging over both sides rather
+    // than cut short, but never off the left of the screen.
+    fn panel_pos(&self, width: u16, rows: u16) -> (u16, u16) {
+        let (cols, board_rows) = self.layout.inner;
+        let x = if width <= cols {
+            self.layout.left + 1 + (cols - width) / 2
+        } else {
+            (self.layout.left + 1)
+                .saturating_sub((width - cols) / 2)
+                .max(1)
+        };
+        let y = self.layout.top + 1 + board_rows.saturating_sub(rows) / 2;
+        (x, y)
+    }
+
+    // Draw game over
+    fn draw_game_over(&mut self) {
+        if !matches!(self.state, GameState::Lose) {
+            return;
+        }
+
+        let stats = [
+            ("Score", self.score.to_string()),
+            ("Lines", self.lines_cleared.to_string()),
+            ("Level", self.level().to_string()),
+            (
+                match self.mode {
+                    GameMode::Survival => "Lasted",
+                    _ => "Time",
+                },
+                format_time(self.play_time),
+            ),
+            ("Pieces", self.pieces_placed.to_string()),
+            ("PPS", format!("{:.2}", self.pps())),
+            ("Combo", self.max_combo.to_string()),
+        ];
+        // Title, stats, a gap and the keys.
+        let panel_rows = stats.len() as u16 + 3;
+
+        // The high score table goes under the panel if the board is tall
+        // enough for a few of it, as much as fits.
+        let table_rows = (self.layout.inner.1.saturating_sub(panel_rows + 1))
+            .min(1 + highscore::MAX_ENTRIES as u16);
+        // Not in versus, nobody's on it.
+        let show_table = !self.versus && table_rows > MIN_TABLE_ENTRIES;
+        let rows = match show_table {
+            true => panel_rows + 1 + table_rows,
+            false => panel_rows,
+        };
+
+        let (x, y) = self.panel_pos(GAME_OVER_WIDTH, rows);
+        let width = GAME_OVER_WIDTH as usize;
+
+        // Padded by hand around the title, and the cursor put back after
+        // it, so a terminal drawing the emoji one column wide doesn't drag
+        // the rest of the line over.
+        let title = match self.emoji {
+            true => self.plain("GAME OVER ☹️"),
+            false => "GAME OVER".to_string(),
+        };
+        let title_width = glyphs::display_width(&title).unwrap().min(width);
+        let pad = (width - title_width) / 2;
+        // Every other blink dim while it flashes.
+        let flashed = GAME_OVER_FLASH.saturating_sub(self.game_over_timer);
+        let dim = !self.game_over_timer.is_zero()
+            && !(flashed.as_millis() / GAME_OVER_BLINK.as_millis()).is_multiple_of(2);
+        let look = match dim {
+            true => Style::default().faint(),
+            false => self.fg(Color::RED).bold(),
+        };
+        self.put_text(x, y, &" ".repeat(pad), Style::default());
+        self.put_text(x + pad as u16, y, &title, look);
+        let rest = " ".repeat(width - pad - title_width);
+        self.put_text(x + (pad + title_width) as u16, y, &rest, Style::default());
+
+        // Same label/value split as the HUD.
+        let text = self.palette.text();
+        for (i, (label, value)) in stats.iter().enumerate() {
+            let label = format!("{:<7}", label);
+            let value = format!("{:>1$}", value, width - 7 - 2);
+            let spans = [
+                (" ", Style::default()),
+                (label.as_str(), text.bold()),
+                (value.as_str(), text),
+                (" ", Style::default()),
+            ];
+            self.put_spans(x, y + 1 + i as u16, &spans);
+        }
+
+        let blank = " ".repeat(width);
+        self.put_text(x, y + 1 + stats.len() as u16, &blank, Style::default());
+        let keys = format!("{:^width$}", self.plain("r retry · q quit"));
+        self.put_text(x, y + 2 + stats.len() as u16, &keys, Style::default());
+
+        if show_table {
+            self.draw_leaderboard(y + panel_rows + 1, table_rows - 1);
+        }
+    }
+
+    // The high score table, starting at row `y` inside the board, `entries`
+    // of it. Short of the whole table it's the top, or down as far as it
+    // takes to show the score just set. Each line is padded to the board
+    // width so the stack doesn't show through.
+    fn draw_leaderboard(&mut self, y: u16, entries: u16) {
+        let x = self.layout.left + 1;
+        let row_width = self.layout.inner.0 as usize;
+        // rank, space, name, space, score
+        let name_width = row_width.saturating_sub(2 + 1 + 1 + 7);
+
+        let title = format!("{:^row_width$}", "HIGH SCORES");
+        self.put_text(x, y, &title, Style::default().bold());
+
+        // An empty table is still a table.
+        let mut lines: Vec<(bool, String)> = self
+            .high_scores
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let name: String = entry.name.chars().take(name_width).collect();
+                let line = format!("{:>2} {:<name_width$} {:>7}", i + 1, name, entry.score);
+                (self.rank == Some(i), line)
+            })
+            .collect();
+        lines.resize(highscore::MAX_ENTRIES, (false, String::new()));
+        let entries = entries as usize;
+        let skip = self
+            .rank
+            .map_or(0, |rank| (rank + 1).saturating_sub(entries));
+
+        for (i, (current, line)) in lines.iter().skip(skip).take(entries).enumerate() {
+            let style = match current {
+                true => self.fg(Color::YELLOW).bold(),
+                false => Style::default(),
+            };
+            let line = format!("{:<row_width$}", line);
+            self.put_text(x, y + 1 + i as u16, &line, style);
+        }
+    }
+
+    // Validate if done falling.
+    fn done_falling(&self) -> bool {
+        // If it can't go down one, it sits on another block/ground, and it's
+        // done falling.
+        self.falling
+            .as_ref()
+            .is_some_and(|t| !self.board.try_move(&mut t.clone(), Point::DOWN))
+    }
+
+    // Advance the game by one tick.
+    // Everything that changes the game lives here and nothing here touches
+    // the terminal, so the same key sequence with the same time deltas always
+    // ends up in the same place. The AI (and hopefully tests one day) lean on
+    // that.
+    pub fn step(&mut self, key: Option<Key>, dt: Duration) -> StepResult {
+        let placed = self.pieces_placed;
+        let lines = self.lines_cleared;
+        let over = self.is_over();
+
+        self.advance(key, dt);
+
+        let locked = self.pieces_placed > placed;
+        StepResult {
+            locked,
+            lines_cleared: self.lines_cleared.saturating_sub(lines) as u32,
+            t_spin: locked
+                && self
+                    .events
+                    .iter()
+                    .any(|event| matches!(event, Event::TSpin | Event::Clear { t_spin: true, .. })),
+            game_over: !over && self.is_over(),
+        }
+    }
+
+    // Topped out, whether or not the name is in yet.
+    fn is_over(&self) -> bool {
+        matches!(self.state, GameState::Lose | GameState::NameEntry)
+    }
+
+    // The step itself.
+    fn advance(&mut self, key: Option<Key>, dt: Duration) {
+        self.narrator.tick(dt);
+
+        // Typing a name takes every key, q and friends included.
+        if matches!(self.state, GameState::NameEntry) {
+            self.enter_name(key);
+            return;
+        }
+
+        // The AI plays the board as it is, mirrored or not.
+        let mirrored = self.mirror && self.ai.is_none();
+        let action = key.and_then(keys::action).map(|action| match mirrored {
+            true => action.mirrored(),
+            false => action,
+        });
+
+        // Quit works even when there is no falling block now.
+        if action == Some(Action::Quit) {
+            self.state = GameState::Quit;
+            return;
+        }
+
+        if matches!(self.state, GameState::Title) {
+            self.title_screen(key);
+            return;
+        }
+
+        // Counting down. Any key cuts it short, nothing else happens yet.
+        if !self.countdown.is_zero() {
+            self.countdown = match key {
+                Some(_) => Duration::ZERO,
+                None => self.countdown.saturating_sub(dt),
+            };
+            return;
+        }
+
+        // Start over.
+        if action == Some(Action::Restart) {
+            self.reset();
+            return;
+        }
+
+        // Pause/resume.
+        if action == Some(Action::Pause) {
+            self.state = match self.state {
+                GameState::Play => GameState::Pause,
+                GameState::Pause => GameState::Play,
+                _ => return,
+            };
+            self.menu_selection = 0;
+            let paused = matches!(self.state, GameState::Pause);
+            self.narrator.say(format_args!(
+                "{}",
+                if paused { "paused" } else { "playing" }
+            ));
+            return;
+        }
+
+        // The pause menu takes the keys while paused.
+        if matches!(self.state, GameState::Pause) {
+            self.pause_menu(key);
+            return;
+        }
+
+        // Nothing moves while paused or once the game is over. Bar GAME
+        // OVER flashing, and only once it's up, not under the name entry.
+        if !matches!(self.state, GameState::Play) {
+            if matches!(self.state, GameState::Lose) {
+                self.game_over_timer = self.game_over_timer.saturating_sub(dt);
+            }
+            return;
+        }
+
+        self.new_best_timer = self.new_best_timer.saturating_sub(dt);
+        self.level_up_timer = self.level_up_timer.saturating_sub(dt);
+        self.announce_timer = self.announce_timer.saturating_sub(dt);
+
+        for popup in self.popups.iter_mut() {
+            popup.age += dt;
+        }
+        self.popups
+            .retain(|popup| popup.age < POPUP_DURATION && popup.row().is_some());
+
+        for (_, left) in self.fading.iter_mut() {
+            *left = left.saturating_sub(dt);
+        }
+        self.fading.retain(|(_, left)| !left.is_zero());
+        self.reveal_timer = self.reveal_timer.saturating_sub(dt);
+
+        // Invisible: a look at the stack, while they last.
+        if self.mode == GameMode::Invisible && action == Some(Action::Reveal) {
+            if self.reveals > 0 && self.reveal_timer.is_zero() {
+                self.reveals -= 1;
+                self.reveal_timer = REVEAL_DURATION;
+            }
+            return;
+        }
+
+        if self.mode == GameMode::Practice {
+            match action {
+                Some(Action::Undo) => {
+                    self.undo();
+                    return;
+                }
+                // Pick the piece: 1 to 7, in Shape::ALL order. It takes the
+                // falling piece's place, and its count.
+                Some(Action::Pick(i)) if self.clearing.is_empty() => {
+                    let replaced = self.falling.as_ref().map(|t| t.shape);
+                    if self.spawn(Shape::ALL[i]) {
+                        if let Some(shape) = replaced {
+                            self.piece_counts[shape as usize] -= 1;
+                        }
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+        // Mid line clear. Everything waits, input and gravity included, until
+        // the flash is done.
+        if !self.clearing.is_empty() {
+            self.clear_timer = self.clear_timer.saturating_sub(dt);
+            if self.clear_timer.is_zero() {
+                self.clear_completed_lines();
+                self.clearing.clear();
+            }
+            return;
+        }
+
+        self.fall_timer += dt;
+        if self.clock_started {
+            self.play_time += dt;
+        }
+
+        // Survival: up comes the garbage. It can top the game out by itself.
+        if self.mode == GameMode::Survival && self.clock_started {
+            self.garbage_timer += dt;
+            let interval = self.garbage_interval();
+            if self.garbage_timer >= interval {
+                self.garbage_timer -= interval;
+                let hole = self.garbage_rng.gen_range(0..self.width);
+                self.add_garbage(1, hole).unwrap();
+                if !matches!(self.state, GameState::Play) {
+                    return;
+                }
+            }
+        }
+
+        if action == Some(Action::Hold) && self.hold_on {
+            self.hold();
+            return;
+        }
+
+        // Screen reader: the stack's height, column by column.
+        if self.narrator.is_on() && action == Some(Action::Heights) {
+            let heights: Vec<String> = analysis::column_heights(&self.board)
+                .iter()
+                .map(|h| h.to_string())
+                .collect();
+            self.narrator
+                .say(format_args!("heights {}", heights.join(" ")));
+            return;
+        }
+
+        // Where the piece was, to tell whether a move went anywhere.
+        let before = self
+            .falling
+            .as_ref()
+            .map(|t| (Self::column(t), Self::orientation(t)));
+
+        let fall_rate_ms = self.fall_rate_ms();
+        if let Some(t) = self.falling.as_mut() {
+            // This block handles the tetrominos falling. This works independent of the current frame rate.
+            // Maybe there are better ways of handling this but hey, this works.
+            if self.fall_timer.as_millis() >= fall_rate_ms {
+                // fall.
+                self.rotated_last &= !self.board.try_move(t, Point::DOWN);
+
+                // Reset clock.
+                self.fall_timer = Duration::ZERO;
+            }
+
+            // Next move.
+            match action {
+                Some(Action::Left) => {
+                    self.rotated_last &= !self.board.try_move(t, Point::LEFT);
+                }
+                // Points for the row it went down, if it did. Pressing down
+                // on a piece that's already landed gets nothing.
+                Some(Action::SoftDrop) => {
+                    let moved = self.board.try_move(t, Point::DOWN);
+                    self.rotated_last &= !moved;
+                    if moved {
+                        self.score += self.soft_drop_points as i64;
+                    }
+                }
+                Some(Action::Right) => {
+                    self.rotated_last &= !self.board.try_move(t, Point::RIGHT);
+                }
+                Some(Action::RotateLeft) => {
+                    self.rotated_last |= self.board.try_rotate(t, false);
+                }
+                Some(Action::RotateRight) => {
+                    self.rotated_last |= self.board.try_rotate(t, true);
+                }
+                // Hard drop. All the way down, 2 pts a row, and it locks below.
+                Some(Action::HardDrop) => {
+                    let start = t.clone();
+                    while self.board.try_move(t, Point::DOWN) {
+                        self.score += 2;
+                        self.rotated_last = false;
+                    }
+
+                    // Leave a trail behind and thud. Looks only, the board
+                    // doesn't know about either.
+                    if self.effects {
+                        self.trail = start
+                            .cells()
+                            .iter()
+                            .zip(t.cells().iter())
+                            .flat_map(|(from, to)| {
+                                (from.y..to.y).map(move |y| Point { x: from.x, y })
+                            })
+                            .collect();
+                        self.trail_shape = t.shape;
+                        self.trail_frames = TRAIL_FRAMES;
+                        self.shake_frames = SHAKE_FRAMES;
+                    }
+                }
+                _ => (),
+            };
+        } else if !self.entry_timer.is_zero() {
+            // Entry delay. The next piece waits, and so does gravity, or the
+            // piece would come in a row down.
+            self.entry_timer = self.entry_timer.saturating_sub(dt);
+            self.fall_timer = Duration::ZERO;
+        } else {
+            // Create a new falling piece if there isn't one currently.
+            // If it doesn't fit, the stack has reached the spawn and the game
+            // is over. Garbage is the only other way out.
+            let t = self.queue.pop_front().unwrap();
+            self.refill_queue();
+            if !self.spawn_piece(t) {
+                self.top_out();
+            }
+        }
+        self.say_move(action, before);
+
+        // All the game checks here.
+        // Check if done falling, i.e., touches the ground or another block.
+        if self.done_falling() {
+            let t_spin = self.is_t_spin();
+            if self.mode == GameMode::Practice {
+                self.save_undo();
+            }
+
+            // Locked without a single block in view, that's as good as not
+            // fitting at all. Unless it finished a line up there.
+            let out_of_sight = self
+                .falling
+                .as_ref()
+                .is_some_and(|t| t.cells().iter().all(|b| (b.y as usize) < BUFFER_ROWS));
+
+            self.insert_falling();
+            self.entry_timer = self.entry_delay;
+            self.score_lock(t_spin);
+            if out_of_sight && self.completed_lines().is_empty() {
+                self.top_out();
+                return;
+            }
+        }
+
+        // Completed lines flash for a moment before they are cleared. No
+        // delay, no flash, they go right away.
+        if self.clearing.is_empty() {
+            self.clearing = self.completed_lines();
+            self.clear_timer = self.line_clear_delay;
+            if self.line_clear_delay.is_zero() && !self.clearing.is_empty() {
+                self.clear_completed_lines();
+                self.clearing.clear();
+            }
+        }
+
+        // Keep up the session best. Going past a previous game's best gets
+        // called out, once per game.
+        if self.score > self.session_best {
+            if self.session_best > 0 && !self.beat_best {
+                self.new_best_timer = NEW_BEST_DURATION;
+            }
+            self.beat_best = true;
+            self.session_best = self.score;
+        }
+    }
+
+    // Title screen keys: enter or space to play, h to flip between the keys
+    // and the high score table.
+    fn title_screen(&mut self, key: Option<Key>) {
+        match key {
+            Some(Key::Char('\n' | ' ')) => {
+                self.state = GameState::Play;
+                self.needs_redraw = true;
+                self.start_countdown();
+            }
+            Some(Key::Char('h')) => {
+                self.title_scores = !self.title_scores;
+                self.needs_redraw = true;
+            }
+            _ => (),
+        }
+    }
+
+    // Start counting down, if that's on.
+    fn start_countdown(&mut self) {
+        if self.countdown_on {
+            self.countdown = COUNTDOWN_STEP * COUNTDOWN_FROM;
+        }
+    }
+
+    fn mode_name(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    // Survival: time between garbage rows. Starts at SURVIVAL_START and
+    // takes a second off every SURVIVAL_SPEEDUP survived, down to
+    // SURVIVAL_FASTEST.
+    fn garbage_interval(&self) -> Duration {
+        let speedups = (self.play_time.as_secs() / SURVIVAL_SPEEDUP.as_secs()) as u32;
+        SURVIVAL_START
+            .saturating_sub(Duration::from_secs(1) * speedups)
+            .max(SURVIVAL_FASTEST)
+    }
+
+    // The stack hit the top. Off to the high score table if it made it,
+    // otherwise game over. Zen just starts the stack over.
+    fn top_out(&mut self) {
+        if self.mode == GameMode::Zen {
+            self.sweep();
+            return;
+        }
+        self.log.event(
+            self.play_time,
+            format_args!("over score {} lines {}", self.score, self.lines_cleared),
+        );
+        self.narrator.say(format_args!(
+            "game over, score {}, lines {}. r to play again",
+            self.score, self.lines_cleared
+        ));
+        if let Some(csv) = self.stats_csv.as_ref() {
+            let game = GameRecord {
+                ended: stats_csv::now(),
+                mode: self.mode_name(),
+                seed: self.seed,
+                score: self.score,
+                lines: self.lines_cleared,
+                level: self.level(),
+                pieces: self.pieces_placed,
+                duration: self.play_time,
+            };
+            // Same as the high scores, a file that can't be written to
+            // doesn't end anything but the row.
+            if let Err(e) = csv.append(&game) {
+                self.log
+                    .event(self.play_time, format_args!("stats-csv failed: {}", e));
+            }
+        }
+        #[cfg(feature = "snapshot")]
+        if let Err(e) = self.save_snapshot() {
+            self.log
+                .event(self.play_time, format_args!("snapshot failed: {}", e));
+        }
+
+        // Practice isn't a fair game, it stays off the table.
+        let ranked = !self.versus && self.mode != GameMode::Practice;
+        self.game_over_timer = GAME_OVER_FLASH;
+        if ranked && self.ai.is_none() && self.high_scores.qualifies(self.score) {
+            // Made the table. The AI's scores aren't the player's though.
+            self.state = GameState::NameEntry;
+        } else {
+            self.state = GameState::Lose;
+        }
+    }
+
+    // Zen: the stack's gone, along with the piece that didn't fit, and the
+    // next one comes in on an empty board. Score, lines and level stay.
+    fn sweep(&mut self) {
+        self.sweeps += 1;
+        self.log.event(
+            self.play_time,
+            format_args!("sweep {} score {}", self.sweeps, self.score),
+        );
+        self.narrator
+            .say(format_args!("stack swept, score {}", self.score));
+        self.board.clear();
+        self.falling = None;
+        self.clearing.clear();
+        self.fading.clear();
+        self.trail.clear();
+        self.combo = 0;
+        self.back_to_back = false;
+        self.entry_timer = self.entry_delay;
+        self.needs_redraw = true;
+    }
+
+    // The board as it ended up to the --snapshot file, if there is one.
+    #[cfg(feature = "snapshot")]
+    fn save_snapshot(&self) -> io::Result<()> {
+        let path = match self.snapshot.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let footer = [
+            format!("SCORE {}", self.score),
+            format!("LINES {}", self.lines_cleared),
+            format!("TIME {}", format_time(self.play_time)),
+        ];
+        let rows: Vec<&[Cell]> = self.board.rows().skip(BUFFER_ROWS).collect();
+        let image = snapshot::board(&rows, &self.theme, &footer);
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        image.write_png(&mut file)?;
+        file.flush()
+    }
+
+    // Push the stack up `rows` rows and fill in the bottom with garbage,
+    // full but for a hole at `hole_col`. Anything pushed out the top, the
+    // falling piece included, tops out.
+    pub fn add_garbage(&mut self, rows: usize, hole_col: usize) -> Result<(), Error> {
+        if hole_col >= self.width {
+            return Err(Error::GarbageHole {
+                hole_col,
+                width: self.width,
+            });
+        }
+
+        let rows = rows.min(self.board.height());
+        let pushed_out = self
+            .board
+            .rows()
+            .take(rows)
+            .flatten()
+            .any(|&cell| cell != Cell::Empty);
+
+        let mut garbage = vec![Cell::Garbage; self.width];
+        garbage[hole_col] = Cell::Empty;
+        self.board.push_up(rows, &garbage);
+        for (block, _) in self.fading.iter_mut() {
+            block.y -= rows as i16;
+        }
+        self.fading.retain(|(block, _)| block.y >= 0);
+
+        // The falling piece gets out of the way if it can.
+        let mut stuck = false;
+        if let Some(t) = self.falling.as_mut() {
+            if !self.board.fits(t) {
+                let up = Point::new(0, -(rows as i16));
+                stuck = !self.board.try_move(t, up);
+            }
+        }
+
+        if pushed_out || stuck {
+            self.falling = None;
+            self.top_out();
+        }
+
+        Ok(())
+    }
+
+    // Move through the pause menu and pick an entry.
+    fn pause_menu(&mut self, key: Option<Key>) {
+        match key {
+            Some(Key::Char('w')) | Some(Key::Up) => {
+                self.menu_selection =
+                    (self.menu_selection + PAUSE_MENU.len() - 1) % PAUSE_MENU.len();
+            }
+            Some(Key::Char('s')) | Some(Key::Down) => {
+                self.menu_selection = (self.menu_selection + 1) % PAUSE_MENU.len();
+            }
+            // Same as the p, r and q keys. No "are you sure?" on quit,
+            // picking it from a menu is sure enough.
+            Some(Key::Char('\n')) => match PAUSE_MENU[self.menu_selection] {
+                "Resume" => self.state = GameState::Play,
+                "Restart" => self.reset(),
+                "Mirror" => self.set_mirror(!self.mirror),
+                _ => self.state = GameState::Quit,
+            },
+            _ => (),
+        }
+    }
+
+    // Type in a name for the high score table.
+    fn enter_name(&mut self, key: Option<Key>) {
+        match key {
+            Some(Key::Char('\n')) if !self.name.is_empty() => {
+                self.rank = self
+                    .high_scores
+                    .insert(&self.name, self.score, self.mode_name());
+                // Not being able to save shouldn't take the game down with it.
+                let _ = self.high_scores.save();
+                self.state = GameState::Lose;
+            }
+            Some(Key::Backspace) => {
+                self.name.pop();
+            }
+            Some(Key::Char(c))
+                if (c.is_ascii_graphic() || c == ' ') && self.name.len() < MAX_NAME_LEN =>
+            {
+                self.name.push(c);
+            }
+            _ => (),
+        }
+    }
+
+    // Where a fresh tetromino starts: centered, with its bottom row on the
+    // top row of the board and the rest up in the buffer. Pieces are
+    // different widths, so this is per piece, and for odd leftovers the extra
+    // column goes on the right.
+    fn spawn_offset(t: &Tetromino, width: usize) -> Point {
+        let x = (width as i16 - t.width()) / 2;
+        let y = BUFFER_ROWS as i16 + 1 - t.height();
+        Point {
+            x: x.max(0),
+            y: y.max(0),
+        }
+    }
+
+    // Move a fresh `t` to where it spawns. If the stack is in the way it
+    // goes further up into the buffer, and only if it doesn't fit anywhere
+    // up there is it false.
+    fn move_to_spawn(&self, t: &mut Tetromino) -> bool {
+        let mut offset = Self::spawn_offset(t, self.width);
+        loop {
+            let mut moved = t.clone();
+            if self.board.try_move(&mut moved, offset) {
+                *t = moved;
+                return true;
+            }
+            if offset.y == 0 {
+                return false;
+            }
+            offset.y -= 1;
+        }
+    }
+
+    // Put the game back the way it was at the start. Anything that is
+    // tracked per game gets cleared here, and only here.
+    pub fn reset(&mut self) {
+        self.board.clear();
+        self.score = 0;
+        self.beat_best = false;
+        self.new_best_timer = Duration::ZERO;
+        self.level_up_timer = Duration::ZERO;
+        self.game_over_timer = Duration::ZERO;
+        self.lines_cleared = 0;
+        self.piece_counts = [0; 7];
+        self.pieces_placed = 0;
+        self.garbage_timer = Duration::ZERO;
+        self.fading.clear();
+        self.reveals = REVEALS;
+        self.reveal_timer = Duration::ZERO;
+        self.sweeps = 0;
+        self.undo.clear();
+        self.held = None;
+        self.hold_used = false;
+        // A new deal either way, no half-used bag carried over from the last
+        // game. Same pieces again if the seed was picked.
+        if !self.seeded {
+            self.seed = rand::random();
+        }
+        self.redeal();
+        self.play_time = Duration::ZERO;
+        self.clock_started = false;
+        self.start_countdown();
+        self.fall_timer = Duration::ZERO;
+        self.entry_timer = Duration::ZERO;
+        self.clearing.clear();
+        self.trail.clear();
+        self.trail_frames = 0;
+        self.shake_frames = 0;
+        self.popups.clear();
+        self.events.clear();
+        self.announcement = None;
+        self.announce_timer = Duration::ZERO;
+        self.rotated_last = false;
+        self.back_to_back = false;
+        self.combo = 0;
+        self.max_combo = 0;
+        self.falling = None;
+        self.state = GameState::Play;
+        self.name.clear();
+        self.rank = None;
+        self.needs_redraw = true;
+
+        // Whatever the AI was planning was for the old board.
+        if let Some(ai) = self.ai.as_mut() {
+            *ai = Ai::default();
+        }
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    // The board in plain text, a line per row from the top and a char per
+    // cell: `.` empty, `G` garbage, and the shape's letter for the rest,
+    // falling piece included. Same as Game::from_matrix() takes, so a
+    // picture of one game sets up another. The buffer is left out.
+    pub fn render_to_string(&self) -> String {
+        let mut rows: Vec<Vec<char>> = self
+            .board
+            .rows()
+            .skip(BUFFER_ROWS)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Cell::Empty => '.',
+                        Cell::Garbage => 'G',
+                        Cell::Filled(shape) => shape.letter(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        if let Some(t) = self.falling.as_ref() {
+            for block in t.cells() {
+                if let Some(y) = (block.y as usize).checked_sub(BUFFER_ROWS) {
+                    rows[y][block.x as usize] = t.shape.letter();
+                }
+            }
+        }
+
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    // How many of each shape were dealt this game.
+    pub fn piece_counts(&self) -> [(char, usize); 7] {
+        Shape::ALL.map(|shape| (shape.letter(), self.piece_counts[shape as usize]))
+    }
+
+    pub fn high_scores(&self) -> &HighScores {
+        &self.high_scores
+    }
+
+    // What the last piece to lock set off, if anything.
+    pub fn last_events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn lines_cleared(&self) -> usize {
+        self.lines_cleared
+    }
+
+    // The locked stack sized up, see analysis.rs. Columns are measured from
+    // the floor, so a piece poking into the buffer counts too.
+    pub fn aggregate_height(&self) -> usize {
+        analysis::aggregate_height(&self.board)
+    }
+
+    pub fn max_height(&self) -> usize {
+        analysis::max_height(&self.board)
+    }
+
+    pub fn holes(&self) -> usize {
+        analysis::holes(&self.board)
+    }
+
+    pub fn bumpiness(&self) -> usize {
+        analysis::bumpiness(&self.board)
+    }
+
+    // Pieces locked onto the stack this game.
+    pub fn pieces_placed(&self) -> u64 {
+        self.pieces_placed
+    }
+
+    // Pieces per second of play so far. Paused time doesn't count.
+    pub fn pps(&self) -> f64 {
+        match self.play_time.is_zero() {
+            true => 0.0,
+            false => self.pieces_placed as f64 / self.play_time.as_secs_f64(),
+        }
+    }
+
+    // Levels start at the start level, 1 unless set otherwise, and go up
+    // every LINES_PER_LEVEL lines.
+    pub fn level(&self) -> usize {
+        self.start_level + self.lines_cleared / LINES_PER_LEVEL
+    }
+
+    // Lines into this level, out of how many it takes. Off the same count
+    // as level(), and so the fall rate.
+    fn level_progress(&self) -> (usize, usize) {
+        (self.lines_cleared % LINES_PER_LEVEL, LINES_PER_LEVEL)
+    }
+
+    // How long the piece hangs before falling a row at the current level.
+    fn fall_rate_ms(&self) -> u128 {
+        let rate = FALL_RATE_MS as f64 * FALL_RATE_SPEEDUP.powi(self.level() as i32 - 1);
+        (rate as u128).max(1000 / FRAME_RATE as u128)
+    }
+
+    // Let the AI play instead of the keyboard.
+    pub fn set_autoplay(&mut self, on: bool) {
+        self.ai = if on { Some(Ai::default()) } else { None };
+    }
+
+    // Some terminals render faint poorly (or not at all). Turn it off there.
+    pub fn set_dim_locked(&mut self, on: bool) {
+        self.dim_locked = on;
+    }
+
+    // The dots in empty cells. Only the look changes, empty is still empty.
+    pub fn set_grid(&mut self, on: bool) {
+        self.grid = on;
+        self.refresh_palette();
+    }
+
+    // What empty cells look like, whatever the glyphs are. Two columns wide,
+    // like every cell. The grid being off still blanks them.
+    pub fn set_empty_glyph(&mut self, glyph: &str) -> Result<(), Error> {
+        glyphs::check(glyph)?;
+        self.empty_glyph = Some(glyph.to_string());
+        self.refresh_palette();
+        Ok(())
+    }
+
+    // A pause after every lock before the next piece, like the old games
+    // had. Zero (the default) is straight on to the next one.
+    pub fn set_entry_delay(&mut self, delay: Duration) {
+        self.entry_delay = delay;
+    }
+
+    // Points for each row soft dropped, 0 for none.
+    pub fn set_soft_drop_points(&mut self, points: u32) {
+        self.soft_drop_points = points;
+    }
+
+    // How long completed lines hang about before they collapse. Zero clears
+    // them on the spot.
+    pub fn set_line_clear_delay(&mut self, delay: Duration) {
+        self.line_clear_delay = delay;
+    }
+
+    // Flip the board left to right, L for J, S for Z. Left still goes left
+    // on screen.
+    pub fn set_mirror(&mut self, on: bool) {
+        self.mirror = on;
+        self.needs_redraw = true;
+    }
+
+    // Shade every other column. Only the look changes, like the grid.
+    pub fn set_stripes(&mut self, on: bool) {
+        self.stripes = on;
+        self.needs_redraw = true;
+    }
+
+    // The ghost piece. On by default.
+    pub fn set_ghost(&mut self, on: bool) {
+        self.ghost_on = on;
+    }
+
+    // Hold, on by default. Off, whatever was held is gone.
+    pub fn set_hold(&mut self, on: bool) {
+        self.hold_on = on;
+        if !on {
+            self.held = None;
+        }
+        self.needs_redraw = true;
+    }
+
+    // Hard drop trail and thud, and score popups, for those who'd rather not.
+    pub fn set_effects(&mut self, on: bool) {
+        self.effects = on;
+    }
+
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.mode = mode;
+    }
+
+    // How the next piece gets picked. Starts the deal over.
+    pub fn set_randomizer(&mut self, randomizer: Randomizer) {
+        self.randomizer = randomizer;
+        self.redeal();
+    }
+
+    // Naive or cascade, see gravity.rs.
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.gravity = gravity;
+    }
+
+    // Same seed and randomizer, same pieces. Starts the deal over.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.seeded = true;
+        self.redeal();
+    }
+
+    // What filled cells, empty cells and the ghost look like.
+    pub fn set_glyphs(&mut self, glyphs: Glyphs) {
+        self.glyphs = glyphs;
+        self.refresh_palette();
+    }
+
+    // What color everything is drawn in.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.refresh_palette();
+    }
+
+    // Colors on or off. NO_COLOR and --no-color turn them off.
+    pub fn set_color(&mut self, on: bool) {
+        self.color = on;
+        self.refresh_palette();
+    }
+
+    // A glyph per shape, as well as the colors.
+    pub fn set_colorblind(&mut self, on: bool) {
+        self.colorblind = on;
+        self.refresh_palette();
+    }
+
+    // Score, level and mode up in the terminal's title bar or tab.
+    pub fn set_title(&mut self, on: bool) {
+        self.title = on;
+    }
+
+    // Write what happens to `out`, see log.rs.
+    pub fn set_log(&mut self, out: Box<dyn Write>) {
+        self.log = Logger::to(out);
+    }
+
+    // Record the game to `out` as an asciicast, see asciicast.rs.
+    pub fn set_record(&mut self, out: Box<dyn Write>) -> io::Result<()> {
+        let (cols, rows) = self.layout.screen;
+        self.renderer
+            .record(Asciicast::new(out, cols, rows, "tetris")?);
+        Ok(())
+    }
+
+    // Save the board as a PNG at `path` whenever a game ends, see
+    // snapshot.rs.
+    #[cfg(feature = "snapshot")]
+    pub fn set_snapshot(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.snapshot = Some(path.into());
+    }
+
+    // Add a row for every game that ends to the CSV file at `path`, see
+    // stats_csv.rs.
+    pub fn set_stats_csv(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.stats_csv = Some(StatsCsv::new(path));
+    }
+
+    // 3-2-1 before each game. On by default.
+    pub fn set_countdown(&mut self, on: bool) {
+        self.countdown_on = on;
+        if !on {
+            self.countdown = Duration::ZERO;
+        }
+    }
+
+    // The frowny after GAME OVER. On by default.
+    pub fn set_emoji(&mut self, on: bool) {
+        self.emoji = on;
+    }
+
+    // ASCII only: the cells, the border, the keys help and the rest all
+    // drawn without so much as a middle dot. The border style is kept for
+    // when it's off again.
+    pub fn set_ascii(&mut self, on: bool) {
+        self.ascii = on;
+        self.refresh_palette();
+    }
+
+    // `text` as it goes on screen: as is, or made ASCII.
+    fn plain(&self, text: &str) -> String {
+        match self.ascii {
+            true => glyphs::to_ascii(text),
+            false => text.to_string(),
+        }
+    }
+
+    // How many colors the terminal does. Game::new() guesses.
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.color_depth = depth;
+        self.refresh_palette();
+    }
+
+    // Start on `level`, 1 to MAX_START_LEVEL, as fast as if the levels
+    // before it had been played. Restarts start there too.
+    pub fn set_start_level(&mut self, level: usize) -> Result<(), Error> {
+        if !(1..=MAX_START_LEVEL).contains(&level) {
+            return Err(Error::StartLevel(level));
+        }
+
+        self.start_level = level;
+        Ok(())
+    }
+
+    // How many upcoming pieces to show, MIN_PREVIEW to MAX_PREVIEW. Fewer
+    // are drawn if the terminal doesn't have the rows for them.
+    pub fn set_preview_count(&mut self, count: usize) -> Result<(), Error> {
+        if !(MIN_PREVIEW..=MAX_PREVIEW).contains(&count) {
+            return Err(Error::PreviewCount(count));
+        }
+
+        self.preview_count = count;
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    // Cell size: 1 is two columns by one row, 2 twice that each way. None
+    // goes big on a terminal with room for it.
+    pub fn set_scale(&mut self, scale: Option<u16>) -> Result<(), Error> {
+        if let Some(n) = scale.filter(|n| !(1..=MAX_SCALE).contains(n)) {
+            return Err(Error::UnknownScale(n.to_string()));
+        }
+
+        self.scale = scale;
+        self.relayout();
+        self.needs_redraw = true;
+        Ok(())
+    }
+
+    // Row and column numbers around the board, handy for talking placements.
+    pub fn set_labels(&mut self, on: bool) {
+        self.labels = on;
+        self.relayout();
+    }
+
+    // The keys help under the board, for those who know them already.
+    pub fn set_hint(&mut self, on: bool) {
+        self.hint = on;
+        self.relayout();
+    }
+
+    // Frame style around the board, None for no frame at all.
+    pub fn set_border(&mut self, border: Option<Border>) {
+        self.border = border;
+        self.relayout();
+        self.needs_redraw = true;
+    }
+
+    // Work out where everything goes, for the screen as it is now.
+    fn relayout(&mut self) {
+        let screen = self
+            .screen_size
+            .or_else(|| termion::terminal_size().ok())
+            .unwrap_or((80, 24));
+        let room = |scale| Room {
+            width: self.width,
+            height: self.height,
+            labels: self.labels,
+            frame: self.border.is_some(),
+            preview_count: self.preview_count,
+            hold: self.hold_on,
+            // Versus has its own keys, see the README.
+            hint: self.hint && !self.versus,
+            origin: self.origin,
+            screen,
+            scale,
+        };
+
+        // Big cells on a screen twice the size it needs, as long as the rest
+        // still fits around them.
+        let small = Layout::new(&room(1));
+        self.layout = match self.scale {
+            Some(scale) => Layout::new(&room(scale)),
+            None if small.screen.0 >= small.need.0 * 2 && small.screen.1 >= small.need.1 * 2 => {
+                let big = Layout::new(&room(MAX_SCALE));
+                match big.fits {
+                    true => big,
+                    false => small,
+                }
+            }
+            None => small,
+        };
+    }
+
+    // Lay out in `cols` x `rows` starting at column `x` rather than the
+    // whole screen, next to another board.
+    fn share_screen(&mut self, x: u16, cols: u16, rows: u16) {
+        self.versus = true;
+        self.origin = x;
+        self.screen_size = Some((x + cols, rows));
+        self.relayout();
+        self.needs_redraw = true;
+    }
+
+    // One frame worth of game: read a key from the input (or the AI) and
+    // step with it.
+    pub fn update(&mut self, dt: Duration) -> StepResult {
+        // Read the keyboard. In autoplay, the keyboard is only good for
+        // quitting, pausing and such, and the AI picks the moves.
+        let mut key = self.input.next_key();
+        let playing = matches!(self.state, GameState::Play);
+        let action = key.and_then(keys::action);
+        if playing
+            && !matches!(
+                action,
+                Some(
+                    Action::Quit | Action::Pause | Action::Restart | Action::Labels | Action::Debug
+                )
+            )
+        {
+            if let Some(ai) = self.ai.as_mut() {
+                key = ai.next_key(self.falling.as_ref(), &self.board);
+            }
+        }
+
+        // Labels are a display thing, the game never sees the key.
+        // Unless it's part of a name.
+        let naming = matches!(self.state, GameState::NameEntry);
+        let action = key.and_then(keys::action);
+        if !naming && action == Some(Action::Labels) {
+            self.toggle_labels();
+            key = None;
+        }
+
+        // Same for the debug overlay.
+        if !naming && action == Some(Action::Debug) {
+            self.debug = !self.debug;
+            self.needs_redraw = true;
+            key = None;
+        }
+
+        // Nothing moves while the game can't be seen, bar quitting.
+        if !self.layout.fits {
+            if key.and_then(keys::action) == Some(Action::Quit) {
+                self.state = GameState::Quit;
+            }
+            return StepResult::default();
+        }
+
+        self.step(key, dt)
+    }
+
+    // Start the game.
+    pub fn run(&mut self) {
+        self.init_screen();
+
+        let mut old_time = Instant::now();
+        while !matches!(self.state, GameState::Quit) {
+            let now = Instant::now();
+            self.frame(now - old_time);
+            old_time = now;
 
             // Maintain frame rate.
             thread::sleep(Duration::from_millis(1000 / (FRAME_RATE as u64)));
         }
 
-        // Move cursor out of the board and show cursor.
-        // If not, the terminal clears the board.
-        self.goto(0, (self.height as u16) + 3);
-        write!(self.stdout, "{}", cursor::Show).unwrap();
+        // The cursor and the alternate screen are restored by the guard when
+        // the game is dropped.
+        self.renderer.flush().unwrap();
+        self.log.flush();
+    }
+
+    // Update and render, timed for the debug overlay.
+    fn frame(&mut self, dt: Duration) {
+        let start = Instant::now();
+        self.update(dt);
+        let updated = Instant::now();
+        let written = self.renderer.written();
+        self.render();
+        let bytes = self.renderer.written() - written;
+        self.frame_stats
+            .record(dt, updated - start, updated.elapsed(), bytes);
+    }
+
+    // Draw one frame.
+    fn render(&mut self) {
+        self.renderer.begin_frame().unwrap();
+
+        // Screen reader mode draws nothing, what happened goes out as lines.
+        if self.narrator.is_on() {
+            for line in self.narrator.take() {
+                self.renderer.write_line(&line).unwrap();
+            }
+            self.renderer.end_frame().unwrap();
+            return;
+        }
+
+        // The game doesn't fit, say so instead. Keep checking, the terminal
+        // can be made bigger.
+        if self.needs_redraw || !self.layout.fits {
+            self.relayout();
+        }
+        if !self.layout.fits {
+            self.draw_too_small();
+            self.renderer.end_frame().unwrap();
+            return;
+        }
+        self.too_small_shown = None;
+
+        // e.g. restarted, wipe whatever the last game left on screen.
+        if self.needs_redraw {
+            self.init_screen();
+        }
+
+        // Draw board.
+        self.draw();
+        self.draw_danger();
+
+        // Draw HUD, or the title screen in its place.
+        if matches!(self.state, GameState::Title) {
+            self.draw_title();
+        } else {
+            self.draw_hud();
+            self.draw_streaks();
+            self.draw_announcement();
+            self.draw_piece_stats();
+            self.draw_preview();
+            self.draw_hold();
+        }
+
+        // Draw falling.
+        self.draw_falling();
+
+        // Draw hard drop effects.
+        self.draw_effects();
+
+        // Draw score popups.
+        self.draw_popups();
+
+        // Draw pause menu.
+        self.draw_pause_menu();
+
+        // 3, 2, 1.
+        self.draw_countdown();
+
+        // Draw high score name prompt.
+        self.draw_name_entry();
+
+        // Game Over :(
+        // Stays up until r restarts or q quits.
+        self.draw_game_over();
+
+        // Frame time, on top of everything.
+        self.draw_debug();
+
+        // Not on the screen at all, up on the tab.
+        self.update_title();
+
+        // Out it goes.
+        self.renderer.end_frame().unwrap();
+    }
+
+    // One line summary of the game, meant for the normal screen after exit.
+    pub fn summary(&self) -> String {
+        format!(
+            "Score: {}  Lines: {}  Time: {}",
+            self.score,
+            self.lines_cleared,
+            format_time(self.play_time)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use config::Config;
+
+    // Rows on the board, the buffer over it included.
+    const ROWS: usize = BOARD_HEIGHT + BUFFER_ROWS;
+
+    fn game() -> Game {
+        Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(io::sink())).unwrap()
+    }
+
+    fn game_with_mode(mode: GameMode) -> Game {
+        let mut game = game();
+        game.set_mode(mode);
+        game
+    }
+
+    // A `shape` turned `rotation` with its pivot anywhere, board or not.
+    fn tetromino(shape: Shape, rotation: u8, x: i16, y: i16) -> Tetromino {
+        Tetromino {
+            shape,
+            rotation,
+            pos: Point { x, y },
+        }
+    }
+
+    fn coords(t: &Tetromino) -> [(i16, i16); 4] {
+        t.cells().map(|b| (b.x, b.y))
+    }
+
+    #[test]
+    fn translate_rejects_i16_overflow() {
+        let board = Board::new(4, 4);
+        let mut t = tetromino(Shape::I, 0, i16::MAX, 1);
+        let blocks = [(i16::MAX, 0), (i16::MAX, 1), (i16::MAX, 2), (i16::MAX, 3)];
+        assert_eq!(coords(&t), blocks);
+
+        assert!(!board.try_move(&mut t, Point::RIGHT));
+        assert_eq!(coords(&t), blocks);
+
+        let mut t = tetromino(Shape::I, 1, 1, i16::MIN);
+        let blocks = [(0, i16::MIN), (1, i16::MIN), (2, i16::MIN), (3, i16::MIN)];
+        assert_eq!(coords(&t), blocks);
+
+        assert!(!board.try_move(&mut t, Point { x: 0, y: -1 }));
+        assert_eq!(coords(&t), blocks);
+    }
+
+    #[test]
+    fn rotate_rejects_i16_overflow() {
+        let board = Board::new(4, 4);
+        // Up against the end of an i16, where lying down would go past it.
+        let mut t = tetromino(Shape::I, 0, i16::MAX, 1);
+        let start = t.clone();
+
+        assert!(!board.try_rotate(&mut t, false));
+        assert!(!board.try_rotate(&mut t, true));
+        assert_eq!(t, start);
+    }
+
+    #[test]
+    fn rotation_goes_round_the_pivot() {
+        let board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        let mut t = tetromino(Shape::T, 0, 4, 4);
+        assert_eq!(coords(&t), [(4, 3), (4, 4), (4, 5), (5, 4)]);
+
+        assert!(board.try_rotate(&mut t, true));
+        assert_eq!(t.rotation, 1);
+        assert_eq!(coords(&t), [(3, 4), (4, 4), (5, 4), (4, 3)]);
+
+        // Four turns and it's back where it started.
+        for _ in 0..3 {
+            assert!(board.try_rotate(&mut t, true));
+        }
+        assert_eq!(t, tetromino(Shape::T, 0, 4, 4));
+        assert!(board.try_rotate(&mut t, false));
+        assert_eq!(t.rotation, 3);
+
+        // Moving is the pivot and nothing else.
+        assert!(board.try_move(&mut t, Point::RIGHT));
+        assert_eq!((t.pos, t.rotation), (Point::new(5, 4), 3));
+    }
+
+    #[test]
+    fn clock_skips_time_before_spawn_and_while_paused() {
+        let mut game = game();
+        let tick = Duration::from_millis(10);
+
+        // The first tick spawns the piece and starts the clock.
+        game.step(None, tick);
+        assert_eq!(game.play_time, Duration::ZERO);
+
+        game.step(None, tick);
+        game.step(None, tick);
+        assert_eq!(game.play_time, 2 * tick);
+
+        // Paused ticks don't count, however long they are.
+        game.step(Some(Key::Char('p')), tick);
+        game.step(None, Duration::from_secs(60));
+        game.step(None, Duration::from_secs(60));
+        assert_eq!(game.play_time, 2 * tick);
+
+        // Back to counting after resume.
+        game.step(Some(Key::Char('p')), tick);
+        game.step(None, tick);
+        assert_eq!(game.play_time, 3 * tick);
+        assert_eq!(format_time(game.play_time), "00:00.03");
+    }
+
+    #[test]
+    fn level_bar_fills_by_eighths() {
+        assert_eq!(progress_bar(0, 10, 6), "░░░░░░");
+        assert_eq!(progress_bar(5, 10, 6), "███░░░");
+        assert_eq!(progress_bar(7, 10, 6), "████▏░");
+        assert_eq!(progress_bar(10, 10, 6), "██████");
+
+        // Same counter as the level, starting over on a level up.
+        let mut game = game();
+        game.lines_cleared = 17;
+        assert_eq!((game.level(), game.level_progress()), (2, (7, 10)));
+        for j in ROWS - 3..ROWS {
+            game.board.fill_row(j, Cell::Garbage);
+        }
+        game.clear_completed_lines();
+        assert_eq!((game.level(), game.level_progress()), (3, (0, 10)));
+        assert_eq!(game.level_up_timer, LEVEL_UP_FLASH);
+    }
+
+    #[test]
+    fn thousands_get_commas() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(12400), "12,400");
+        assert_eq!(thousands(-1234567), "-1,234,567");
+    }
+
+    #[test]
+    fn format_time_is_minutes_seconds_centis() {
+        assert_eq!(format_time(Duration::ZERO), "00:00.00");
+        assert_eq!(format_time(Duration::from_millis(61_239)), "01:01.23");
+        assert_eq!(format_time(Duration::from_secs(600)), "10:00.00");
+    }
+
+    #[test]
+    fn spawn_is_centered_on_odd_widths() {
+        for width in [7, 11] {
+            let board = Board::new(width, BOARD_HEIGHT);
+            for shape in Shape::ALL {
+                let mut t = Tetromino::of(shape);
+                let offset = Game::spawn_offset(&t, width);
+                assert!(board.try_move(&mut t, offset));
+
+                let left = t.cells().iter().map(|b| b.x).min().unwrap();
+                let right = width as i16 - 1 - t.cells().iter().map(|b| b.x).max().unwrap();
+                assert!(left >= 0 && right >= 0);
+                assert!((left - right).abs() <= 1, "{} vs {}", left, right);
+            }
+        }
+    }
+
+    #[test]
+    fn pause_menu_freezes_gravity_and_picks_entries() {
+        let mut game = game();
+        let tick = Duration::from_millis(10);
+
+        game.step(None, tick);
+        let y = game.falling.as_ref().unwrap().cells()[0].y;
+
+        // Way past the fall rate, but paused.
+        game.step(Some(Key::Char('p')), tick);
+        game.step(None, Duration::from_secs(10));
+        assert_eq!(game.falling.as_ref().unwrap().cells()[0].y, y);
+
+        // Down wraps around: Resume -> Restart -> Mirror -> Quit -> Resume.
+        game.step(Some(Key::Down), tick);
+        assert_eq!(game.menu_selection, 1);
+        game.step(Some(Key::Up), tick);
+        game.step(Some(Key::Up), tick);
+        assert_eq!(game.menu_selection, 3);
+
+        // Mirror flips, and stays on the menu.
+        game.step(Some(Key::Up), tick);
+        game.step(Some(Key::Char('\n')), tick);
+        assert!(game.mirror);
+        assert!(matches!(game.state, GameState::Pause));
+        game.step(Some(Key::Down), tick);
+
+        // Resume.
+        game.step(Some(Key::Down), tick);
+        game.step(Some(Key::Char('\n')), tick);
+        assert!(matches!(game.state, GameState::Play));
+
+        // Quit.
+        game.step(Some(Key::Char('p')), tick);
+        game.step(Some(Key::Up), tick);
+        game.step(Some(Key::Char('\n')), tick);
+        assert!(matches!(game.state, GameState::Quit));
+    }
+
+    #[test]
+    fn session_best_survives_restart() {
+        let mut game = game();
+        let tick = Duration::from_millis(10);
+
+        game.step(None, tick);
+        game.step(Some(Key::Down), tick);
+        game.step(Some(Key::Down), tick);
+        assert_eq!(game.session_best, 2);
+        assert!(game.new_best_timer.is_zero());
+
+        game.step(Some(Key::Char('r')), tick);
+        assert_eq!(game.session_best, 2);
+
+        // Past the countdown.
+        game.step(Some(Key::Char('x')), tick);
+        game.step(None, tick);
+        game.step(Some(Key::Down), tick);
+        game.step(Some(Key::Down), tick);
+        assert!(game.new_best_timer.is_zero());
+
+        // Past the old best.
+        game.step(Some(Key::Down), tick);
+        assert_eq!(game.session_best, 3);
+        assert_eq!(game.new_best_timer, NEW_BEST_DURATION);
+
+        // Only once per game.
+        game.step(Some(Key::Down), Duration::from_secs(3));
+        assert!(game.new_best_timer.is_zero());
+    }
+
+    #[test]
+    fn game_over_flashes_then_stays() {
+        let screen = testing::Capture::default();
+        let mut game =
+            Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(screen.clone())).unwrap();
+        // Practice, so there's no name to enter first.
+        game.set_mode(GameMode::Practice);
+        game.top_out();
+        assert!(matches!(game.state, GameState::Lose));
+        let dim = |game: &mut Game| {
+            screen.0.take();
+            game.render();
+            let drawn = String::from_utf8(screen.0.take()).unwrap();
+            assert!(drawn.contains("GAME OVER"));
+            drawn.contains(&format!("{}GAME OVER", termion::style::Faint))
+        };
+
+        assert!(!dim(&mut game));
+        game.step(None, GAME_OVER_BLINK);
+        assert!(dim(&mut game));
+        game.step(None, GAME_OVER_BLINK);
+        assert!(!dim(&mut game));
+
+        // Done flashing, it stays lit.
+        game.step(None, GAME_OVER_FLASH);
+        assert!(game.game_over_timer.is_zero());
+        assert!(!dim(&mut game));
+        game.step(None, GAME_OVER_BLINK);
+        assert!(!dim(&mut game));
+    }
+
+    #[test]
+    fn name_entry_takes_every_key() {
+        let mut game = game();
+        let tick = Duration::from_millis(10);
+
+        game.score = 500;
+        game.state = GameState::NameEntry;
+        for key in [
+            Key::Char('q'),
+            Key::Char('r'),
+            Key::Char('x'),
+            Key::Backspace,
+            Key::Char('p'),
+        ] {
+            game.step(Some(key), tick);
+        }
+        assert_eq!(game.name, "qrp");
+
+        game.step(Some(Key::Char('\n')), tick);
+        assert!(matches!(game.state, GameState::Lose));
+        assert_eq!(game.high_scores.entries()[0].name, "qrp");
+        assert_eq!(game.high_scores.entries()[0].score, 500);
+    }
+
+    #[test]
+    fn completed_lines_flash_before_collapsing() {
+        let mut game = game();
+        let tick = Duration::from_millis(16);
+
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+        game.board.set(0, ROWS - 2, Cell::Filled(Shape::O));
+        game.step(None, tick);
+        assert_eq!(game.clearing, vec![ROWS - 1]);
+
+        // Nothing moves while the row flashes.
+        let y = game.falling.as_ref().unwrap().cells()[0].y;
+        for _ in 1..LINE_CLEAR_DELAY.as_millis() / tick.as_millis() {
+            game.step(Some(Key::Down), tick);
+            assert_eq!(game.falling.as_ref().unwrap().cells()[0].y, y);
+            assert_eq!(game.lines_cleared(), 0);
+        }
+
+        // Gone, and the row above came down.
+        game.step(None, tick);
+        assert!(game.clearing.is_empty());
+        assert_eq!(game.lines_cleared(), 1);
+        assert_eq!(game.board.get(0, ROWS - 1), Cell::Filled(Shape::O));
+
+        // Held as long as it's told, or not at all.
+        game.set_line_clear_delay(Duration::from_secs(1));
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+        game.step(None, tick);
+        game.step(None, Duration::from_millis(900));
+        assert_eq!(game.lines_cleared(), 1);
+        game.step(None, Duration::from_millis(100));
+        assert_eq!(game.lines_cleared(), 2);
+
+        game.set_line_clear_delay(Duration::ZERO);
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+        game.step(None, tick);
+        assert_eq!(game.lines_cleared(), 3);
+        assert!(game.clearing.is_empty());
+    }
+
+    #[test]
+    fn garbage_and_the_flash_keep_the_cell_glyphs() {
+        let screen = testing::Capture::default();
+        let mut game =
+            Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(screen.clone())).unwrap();
+        game.set_glyphs(Glyphs::preset("solid").unwrap());
+        game.set_ascii(true);
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::T));
+        game.board.set(0, ROWS - 1, Cell::Garbage);
+        game.board.set(0, ROWS - 2, Cell::Garbage);
+        game.step(None, Duration::from_millis(16));
+        assert_eq!(game.clearing, vec![ROWS - 1]);
+        let drawn = |game: &mut Game| {
+            screen.0.take();
+            game.render();
+            testing::strip_ansi(&String::from_utf8(screen.0.take()).unwrap())
+        };
+
+        // No block glyph anywhere, garbage and flash included.
+        let ascii = drawn(&mut game);
+        assert!(!ascii.contains('█'), "{:?}", ascii);
+        assert!(ascii.contains(&"[]".repeat(BOARD_WIDTH)), "{:?}", ascii);
+
+        // Each shape keeps its own glyph through the flash.
+        game.set_ascii(false);
+        game.set_colorblind(true);
+        let colorblind = drawn(&mut game);
+        let row = format!("██{}", "()".repeat(BOARD_WIDTH - 1));
+        assert!(colorblind.contains(&row), "{:?}", colorblind);
+    }
+
+    #[test]
+    fn scripted_input_drives_the_game() {
+        let mut game = game();
+        let tick = Duration::from_millis(16);
+        game.set_input(Box::new(ScriptedInput::new([
+            None,
+            Some(Key::Left),
+            Some(Key::Left),
+            Some(Key::Char(' ')),
+        ])));
+
+        // Spawn.
+        game.update(tick);
+        let spawned = game.falling.clone().unwrap();
+
+        // Left, left, hard drop.
+        for _ in 0..3 {
+            game.update(tick);
+        }
+        assert!(game.falling.is_none());
+
+        // Two columns left, resting on the floor.
+        let bottom = spawned.cells().iter().map(|b| b.y).max().unwrap();
+        let drop = ROWS as i16 - 1 - bottom;
+        for block in spawned.cells() {
+            let cell = game
+                .board
+                .get((block.x - 2) as usize, (block.y + drop) as usize);
+            assert_eq!(cell, Cell::Filled(spawned.shape));
+        }
+        let filled = game
+            .board
+            .rows()
+            .flatten()
+            .filter(|&&cell| cell != Cell::Empty);
+        assert_eq!(filled.count(), 4);
+    }
+
+    #[test]
+    fn hud_leaves_nothing_behind() {
+        let capture = testing::Capture::default();
+        let mut game = Game::with_output(10, 20, Box::new(capture.clone())).unwrap();
+        // Everything drawn so far, as it'd be on screen.
+        let drawn = || testing::screen(&String::from_utf8_lossy(&capture.0.borrow()), 80, 24);
+        let field = |screen: &[String], label: &str| {
+            let row = screen.iter().find(|row| row.contains(label)).unwrap();
+            let from = &row[row.find(label).unwrap()..];
+            let field: String = from.chars().take(HUD_WIDTH as usize).collect();
+            field.trim_end().to_string()
+        };
+
+        game.score = 12400;
+        game.lines_cleared = 137;
+        game.play_time = Duration::from_secs(754);
+        game.announcement = Some(Event::Clear {
+            lines: 4,
+            t_spin: false,
+            back_to_back: true,
+        });
+        game.announce_timer = Duration::from_secs(1);
+        game.render();
+        let screen = drawn();
+        assert_eq!(field(&screen, "Score"), "Score  12400");
+        assert!(screen
+            .iter()
+            .any(|row| row.contains("Back-to-Back Tetris!")));
+
+        // Smaller numbers and a shorter message cover the old ones.
+        game.score = 7;
+        game.lines_cleared = 9;
+        game.announcement = Some(Event::Combo(1));
+        game.render();
+        let screen = drawn();
+        assert_eq!(field(&screen, "Score"), "Score  7");
+        assert_eq!(field(&screen, "Lines"), "Lines  9");
+        assert!(!screen.iter().any(|row| row.contains("Tetris!")));
+
+        // And a restart starts from nothing.
+        game.reset();
+        game.render();
+        let screen = drawn();
+        assert_eq!(field(&screen, "Score"), "Score  0");
+        assert_eq!(field(&screen, "Level"), "Level  1");
+        assert_eq!(field(&screen, "Time"), "Time   00:00.00");
+    }
+
+    #[test]
+    fn streaks_show_and_go() {
+        let capture = testing::Capture::default();
+        let mut game = Game::with_output(10, 20, Box::new(capture.clone())).unwrap();
+        let drawn = || testing::strip_ansi(&String::from_utf8(capture.0.take()).unwrap());
+
+        game.render();
+        assert!(!drawn().contains("COMBO"));
+
+        // Three clears in a row, the last two hard ones.
+        game.combo = 3;
+        game.back_to_back = true;
+        game.render();
+        let out = drawn();
+        assert!(out.contains("COMBO x2"), "{:?}", out);
+        assert!(out.contains("B2B"));
+
+        game.combo = 0;
+        game.back_to_back = false;
+        game.render();
+        let out = drawn();
+        assert!(!out.contains("COMBO") && !out.contains("B2B"));
+    }
+
+    #[test]
+    fn no_grid_is_only_the_look() {
+        let mut game = game();
+        game.set_grid(false);
+        assert_eq!(game.palette.empty_cell(), "  ");
+
+        // Still collides, still clears.
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+        game.step(None, Duration::from_millis(16));
+        game.step(None, LINE_CLEAR_DELAY);
+        assert_eq!(game.lines_cleared(), 1);
+        game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+        assert_eq!(game.pieces_placed(), 1);
+        assert!(game.board[ROWS - 1].contains(&Cell::Empty));
+
+        game.set_grid(true);
+        assert_eq!(game.palette.empty_cell(), "· ");
+    }
+
+    #[test]
+    fn pieces_per_second() {
+        let mut game = game();
+        assert_eq!(game.pps(), 0.0);
+
+        // Spawned isn't placed, locked is.
+        let tick = Duration::from_millis(500);
+        game.step(None, tick);
+        assert!(game.falling.is_some());
+        assert_eq!(game.pieces_placed(), 0);
+        game.step(Some(Key::Char(' ')), tick);
+        game.step(None, tick);
+        game.step(Some(Key::Char(' ')), tick);
+        assert_eq!(game.pieces_placed(), 2);
+
+        // Two pieces in the second and a half since the first spawned.
+        assert_eq!(game.play_time, Duration::from_millis(1500));
+        assert!((game.pps() - 2.0 / 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn entry_delay_holds_the_next_piece() {
+        let mut game = game();
+        game.set_entry_delay(Duration::from_millis(100));
+        let tick = Duration::from_millis(40);
+        game.step(None, tick);
+        game.step(Some(Key::Char(' ')), tick);
+        assert!(game.falling.is_none());
+
+        // Nothing falling for the delay, but p and q still work.
+        game.step(None, tick);
+        game.step(None, tick);
+        assert!(game.falling.is_none());
+        game.step(Some(Key::Char('p')), tick);
+        assert!(matches!(game.state, GameState::Pause));
+        game.step(Some(Key::Char('p')), tick);
+        game.step(None, tick);
+        assert!(game.falling.is_none());
+        game.step(None, tick);
+        assert!(game.falling.is_some());
+
+        // Straight on with no delay.
+        game.set_entry_delay(Duration::ZERO);
+        game.step(Some(Key::Char(' ')), tick);
+        game.step(None, tick);
+        assert!(game.falling.is_some());
+
+        game.step(Some(Key::Char('q')), tick);
+        assert!(matches!(game.state, GameState::Quit));
+    }
+
+    #[test]
+    fn soft_drop_scores_rows_not_presses() {
+        let mut game = game();
+        let tick = Duration::from_millis(16);
+        game.step(None, tick);
+
+        game.step(Some(Key::Down), tick);
+        assert_eq!(game.score(), 1);
+        game.set_soft_drop_points(3);
+        game.step(Some(Key::Down), tick);
+        assert_eq!(game.score(), 4);
+
+        // Landed, down goes nowhere and gets nothing.
+        let t = game.falling.as_mut().unwrap();
+        while game.board.try_move(t, Point::DOWN) {}
+        game.step(Some(Key::Down), tick);
+        assert_eq!(game.pieces_placed, 1);
+        assert_eq!(game.score(), 4);
+    }
+
+    #[test]
+    fn reset_clears_every_counter() {
+        let mut game = game();
+        game.set_seed(1);
+
+        // Fill the bottom row so it clears once it's done flashing.
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+        game.step(None, Duration::from_millis(16));
+        game.step(None, LINE_CLEAR_DELAY);
+        assert_eq!(game.lines_cleared(), 1);
+        assert!(game.score() > 0);
+        game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+        assert_eq!(game.pieces_placed, 1);
+
+        game.step(Some(Key::Char('r')), Duration::from_millis(16));
+
+        assert_eq!(game.score(), 0);
+        assert_eq!(game.lines_cleared(), 0);
+        assert_eq!(game.play_time, Duration::ZERO);
+        assert!(!game.clock_started);
+        assert!(game.piece_counts().iter().all(|&(_, count)| count == 0));
+        assert_eq!(game.pieces_placed, 0);
+        assert_eq!(game.garbage_timer, Duration::ZERO);
+        assert!(game.undo.is_empty());
+        assert!(game.queue.len() >= MAX_PREVIEW);
+        assert_eq!(game.fall_timer, Duration::ZERO);
+        assert!(game.falling.is_none());
+        assert!(matches!(game.state, GameState::Play));
+        assert!(game.board.rows().flatten().all(|&cell| cell == Cell::Empty));
+        assert_eq!(game.combo, 0);
+        assert!(!game.back_to_back);
+        assert!(game.announcement.is_none());
+        assert!(game.last_events().is_empty());
+
+        // The last game's bag went with it, the first seven are a whole one.
+        let mut first: Vec<Shape> = game.queue.iter().map(|t| t.shape).collect();
+        while first.len() < Shape::ALL.len() {
+            first.push(game.dealer.next_shape());
+        }
+        first.truncate(Shape::ALL.len());
+        first.sort_by_key(|&shape| shape as usize);
+        assert_eq!(first, Shape::ALL);
+    }
+
+    #[test]
+    fn hard_drop_leaves_trail_over_empty_cells_only() {
+        let mut game = game();
+        game.step(None, Duration::from_millis(16));
+        let start = coords(game.falling.as_ref().unwrap());
+
+        game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+        assert_eq!(game.trail_frames, TRAIL_FRAMES);
+        assert_eq!(game.shake_frames, SHAKE_FRAMES);
+        assert!(!game.trail.is_empty());
+        // Trail runs from where the piece was down to where it landed.
+        assert!(game
+            .trail
+            .iter()
+            .all(|p| start.iter().any(|&(x, y)| x == p.x && p.y >= y)));
+
+        game.set_effects(false);
+        game.reset();
+        assert!(game.trail.is_empty());
+        assert_eq!(game.trail_frames, 0);
+        assert_eq!(game.shake_frames, 0);
+        game.step(None, Duration::from_millis(16));
+        game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+        assert!(game.trail.is_empty());
+    }
+
+    #[test]
+    fn clear_popup_drifts_up_and_expires() {
+        let mut game = game();
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+        game.step(None, Duration::from_millis(16));
+        game.step(None, LINE_CLEAR_DELAY);
+        assert_eq!(game.popups.len(), 1);
+        assert_eq!(game.popups[0].text, "+100");
+        assert_eq!(game.popups[0].row(), Some(ROWS - 1));
+
+        game.step(None, POPUP_DRIFT);
+        assert_eq!(game.popups[0].row(), Some(ROWS - 2));
+
+        game.step(None, POPUP_DURATION);
+        assert!(game.popups.is_empty());
+    }
+
+    #[test]
+    fn locks_announce_back_to_back_and_combos() {
+        let mut game = game();
+        let full = vec![Cell::Filled(Shape::I); BOARD_WIDTH];
+        let fill = |game: &mut Game, rows: usize| {
+            for j in ROWS - rows..ROWS {
+                game.board.set_row(j, &full);
+            }
+        };
+
+        fill(&mut game, 4);
+        game.score_lock(false);
+        let tetris = Event::Clear {
+            lines: 4,
+            t_spin: false,
+            back_to_back: false,
+        };
+        assert_eq!(game.last_events(), &[tetris]);
+        assert_eq!(game.announcement.unwrap().text(), "Tetris!");
+        game.clear_completed_lines();
+
+        // Another one straight after: back to back, and a combo going. The
+        // back to back wins the HUD.
+        fill(&mut game, 4);
+        game.score_lock(false);
+        let b2b = Event::Clear {
+            lines: 4,
+            t_spin: false,
+            back_to_back: true,
+        };
+        assert_eq!(game.last_events(), &[b2b, Event::Combo(1)]);
+        assert_eq!(game.announcement, Some(b2b));
+        assert_eq!(b2b.text(), "Back-to-Back Tetris!");
+        game.clear_completed_lines();
+
+        // A single keeps the combo going but ends the back to back.
+        fill(&mut game, 1);
+        game.score_lock(false);
+        assert_eq!(game.announcement, Some(Event::Combo(2)));
+        assert!(!game.back_to_back);
+        game.clear_completed_lines();
+
+        // Nothing cleared, the combo's over.
+        game.score_lock(false);
+        assert!(game.last_events().is_empty());
+        assert_eq!(game.combo, 0);
+        // But not forgotten for the game over panel.
+        assert_eq!(game.max_combo, 2);
+    }
+
+    #[test]
+    fn t_spin_needs_a_rotation_and_three_corners() {
+        let mut game = game();
+        let h = ROWS as i16;
+
+        // T pointing right, middle at (1, h - 2), three corners filled.
+        game.falling = Some(tetromino(Shape::T, 0, 1, h - 2));
+        for (x, y) in [(0, h - 1), (2, h - 1), (0, h - 3)] {
+            game.board
+                .set(x as usize, y as usize, Cell::Filled(Shape::O));
+        }
+
+        assert!(!game.is_t_spin());
+        game.rotated_last = true;
+        assert!(game.is_t_spin());
+
+        game.board.set(0, ROWS - 3, Cell::Empty);
+        assert!(!game.is_t_spin());
+
+        // Corner back, and it's sitting on the stack, so the next step locks
+        // it as one.
+        game.board.set(0, ROWS - 3, Cell::Filled(Shape::O));
+        let result = game.step(None, Duration::from_millis(16));
+        assert!(result.locked && result.t_spin);
+
+        let single = Event::Clear {
+            lines: 1,
+            t_spin: true,
+            back_to_back: false,
+        };
+        assert_eq!(single.text(), "T-Spin Single!");
+    }
+
+    #[test]
+    fn pieces_spawn_in_the_buffer() {
+        let mut game = game();
+        let rows = |game: &Game| {
+            let t = game.falling.as_ref().unwrap();
+            let ys = t.cells().map(|b| b.y as usize);
+            (*ys.iter().min().unwrap(), *ys.iter().max().unwrap())
+        };
+
+        // Bottom row in view, the top one not yet.
+        assert!(game.spawn(Shape::O));
+        assert_eq!(rows(&game), (BUFFER_ROWS - 1, BUFFER_ROWS));
+
+        // Stack up to the top row: it comes in higher, out of sight.
+        game.board.fill_row(BUFFER_ROWS, Cell::Garbage);
+        game.board.set(0, BUFFER_ROWS, Cell::Empty);
+        assert!(game.spawn(Shape::O));
+        assert_eq!(rows(&game), (BUFFER_ROWS - 2, BUFFER_ROWS - 1));
+        assert!(matches!(game.state, GameState::Play));
+
+        // Locking up there, with nothing in view, is the end.
+        game.step(None, Duration::from_millis(16));
+        assert!(matches!(game.state, GameState::Lose | GameState::NameEntry));
+    }
+
+    #[test]
+    fn step_says_what_happened() {
+        let mut game = game();
+        let tick = Duration::from_millis(16);
+        game.board.fill_row(ROWS - 1, Cell::Filled(Shape::I));
+
+        // The spawn, and the row flashing, is nothing much.
+        assert_eq!(game.step(None, tick), StepResult::default());
+        let result = game.step(None, LINE_CLEAR_DELAY);
+        assert_eq!(result.lines_cleared, 1);
+        assert!(!result.locked);
+
+        let result = game.step(Some(Key::Char(' ')), tick);
+        assert!(result.locked && !result.game_over);
+
+        // Locked out of sight.
+        game.board.fill_row(BUFFER_ROWS, Cell::Garbage);
+        game.board.set(0, BUFFER_ROWS, Cell::Empty);
+        game.spawn(Shape::O);
+        let result = game.step(None, tick);
+        assert!(result.locked && result.game_over);
+        assert!(!game.step(None, tick).game_over);
+    }
+
+    #[test]
+    fn border_goes_red_near_the_top() {
+        let mut game = game();
+        assert!(!game.in_danger());
+
+        game.board
+            .set(0, BUFFER_ROWS + DANGER_ROWS, Cell::Filled(Shape::I));
+        assert!(!game.in_danger());
+
+        game.board
+            .set(0, BUFFER_ROWS + DANGER_ROWS - 1, Cell::Filled(Shape::I));
+        assert!(game.in_danger());
+        game.draw_danger();
+        assert!(game.danger_shown);
+
+        game.board
+            .set(0, BUFFER_ROWS + DANGER_ROWS - 1, Cell::Empty);
+        game.draw_danger();
+        assert!(!game.danger_shown);
+    }
+
+    // Any piece, anywhere it fits on a random board: clockwise then back
+    // counter-clockwise, when both go through, is where it started. Seeded so
+    // a failure comes back the same every run.
+    #[test]
+    fn clockwise_then_counter_clockwise_is_identity() {
+        let mut rng = StdRng::seed_from_u64(0x7e7);
+        let (w, h) = (BOARD_WIDTH, BOARD_HEIGHT);
+        let mut checked = 0;
+
+        for _ in 0..2000 {
+            let mut board = Board::new(w, h);
+            for j in 0..h {
+                for i in 0..w {
+                    if rng.gen_bool(0.2) {
+                        board.set(i, j, Cell::Filled(Shape::O));
+                    }
+                }
+            }
+
+            let mut t = Tetromino::random();
+            for _ in 0..rng.gen_range(0..4) {
+                board.try_rotate(&mut t, false);
+            }
+            let offset = Point {
+                x: rng.gen_range(-2..w as i16),
+                y: rng.gen_range(-2..h as i16),
+            };
+            if !board.try_move(&mut t, offset) {
+                continue;
+            }
+
+            let start = t.clone();
+            if !board.try_rotate(&mut t, true) {
+                assert_eq!(t, start, "blocked rotation moved the piece");
+                continue;
+            }
+            if !board.try_rotate(&mut t, false) {
+                continue;
+            }
+
+            assert!(
+                t == start,
+                "{:?} came back as {:?}",
+                coords(&start),
+                coords(&t)
+            );
+            checked += 1;
+        }
+
+        // Make sure the seed actually exercises something.
+        assert!(checked > 100, "only {} cases checked", checked);
+    }
+
+    #[test]
+    fn title_screen_waits_for_enter() {
+        let mut game = game();
+        game.show_title();
+
+        game.step(Some(Key::Char('h')), Duration::from_millis(16));
+        assert!(game.title_scores);
+        game.step(None, Duration::from_secs(1));
+        assert!(game.falling.is_none());
+        assert_eq!(game.play_time, Duration::ZERO);
+
+        game.step(Some(Key::Char('\n')), Duration::from_millis(16));
+        assert!(matches!(game.state, GameState::Play));
+
+        // 3, 2, 1, then the first piece.
+        game.step(None, COUNTDOWN_STEP * 2);
+        assert!(game.falling.is_none());
+        assert_eq!(game.countdown, COUNTDOWN_STEP);
+        game.step(None, COUNTDOWN_STEP);
+        game.step(None, Duration::from_millis(16));
+        assert!(game.falling.is_some());
+        // The clock starts with the piece, not the countdown.
+        assert_eq!(game.play_time, Duration::ZERO);
+    }
+
+    #[test]
+    fn countdown_skips_or_goes() {
+        let mut game = game();
+        game.reset();
+        // Any key, even one that does nothing.
+        game.step(Some(Key::Char('x')), Duration::from_millis(16));
+        assert!(game.countdown.is_zero());
+        game.step(None, Duration::from_millis(16));
+        assert!(game.falling.is_some());
+
+        // Off, straight in.
+        game.set_countdown(false);
+        game.reset();
+        game.step(None, Duration::from_millis(16));
+        assert!(game.falling.is_some());
+    }
+
+    #[test]
+    fn pieces_come_in_bags_of_seven() {
+        let mut game = game();
+        let mut dealt = Vec::new();
+        for _ in 0..7 {
+            game.falling = None;
+            game.board.clear();
+            let next = game.queue[0].shape;
+            game.step(None, Duration::from_millis(16));
+            assert_eq!(game.falling.as_ref().unwrap().shape, next);
+            assert!(game.queue.len() >= MAX_PREVIEW);
+            dealt.push(next as usize);
+        }
+
+        dealt.sort();
+        assert_eq!(dealt, [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn start_level_is_the_first_and_as_fast() {
+        let mut level_5 = game();
+        level_5.lines_cleared = 4 * LINES_PER_LEVEL;
+        let mut game = game();
+        assert_eq!(game.set_start_level(0), Err(Error::StartLevel(0)));
+        assert_eq!(
+            game.set_start_level(MAX_START_LEVEL + 1),
+            Err(Error::StartLevel(MAX_START_LEVEL + 1))
+        );
+        let slow = game.fall_rate_ms();
+
+        game.set_start_level(5).unwrap();
+        assert_eq!(game.level(), 5);
+        assert!(game.fall_rate_ms() < slow);
+        assert_eq!(game.fall_rate_ms(), level_5.fall_rate_ms());
+
+        // Up from there, and back to it on a restart.
+        game.lines_cleared = LINES_PER_LEVEL;
+        assert_eq!(game.level(), 6);
+        game.reset();
+        assert_eq!(game.level(), 5);
+    }
+
+    #[test]
+    fn lines_score_by_the_level() {
+        let mut game = game();
+        game.set_start_level(15).unwrap();
+        for y in ROWS - 2..ROWS {
+            game.board.fill_row(y, Cell::Garbage);
+        }
+        assert_eq!(testing::clear_lines(&mut game), 2);
+        assert_eq!(game.score, 2 * 100 * 15);
+    }
+
+    #[test]
+    fn hold_is_once_a_piece() {
+        let mut game = game();
+        let tick = Duration::from_millis(16);
+        game.step(None, tick);
+        let first = game.falling.as_ref().unwrap().shape;
+        let next = game.queue[0].shape;
+
+        // Nothing held yet, the next one comes in.
+        game.step(Some(Key::Char('c')), tick);
+        assert_eq!(game.held, Some(first));
+        assert_eq!(game.falling.as_ref().unwrap().shape, next);
+
+        // Not again until it locks.
+        let queue: Vec<Shape> = game.queue.iter().map(|t| t.shape).collect();
+        game.step(Some(Key::Char('c')), tick);
+        assert_eq!(game.held, Some(first));
+        assert_eq!(game.falling.as_ref().unwrap().shape, next);
+        assert!(game.queue.iter().map(|t| t.shape).eq(queue));
+
+        // Locked, so the held one can come back out, counted the once.
+        game.step(Some(Key::Char(' ')), tick);
+        game.step(None, tick);
+        game.step(Some(Key::Char('c')), tick);
+        assert_eq!(game.falling.as_ref().unwrap().shape, first);
+        assert!(game.hold_used);
+        let dealt: usize = game.piece_counts.iter().sum();
+        assert_eq!(dealt, 3);
+    }
+
+    #[test]
+    fn hold_stays_up_through_pause_and_goes_on_restart() {
+        let screen = testing::Capture::default();
+        let mut game =
+            Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(screen.clone())).unwrap();
+        let tick = Duration::from_millis(16);
+        game.step(None, tick);
+        game.step(Some(Key::Char('c')), tick);
+        let held = game.held.unwrap();
+
+        // Whether the held piece's first block is up in the hold panel.
+        let (x, y) = game.layout.hold.unwrap();
+        let block = Tetromino::of(held).cells()[0];
+        let (col, row) = (x - 1 + block.x as u16 * 2, y + block.y as u16);
+        let drawn_held = |game: &mut Game| {
+            screen.0.take();
+            game.render();
+            let drawn = testing::screen(&String::from_utf8_lossy(&screen.0.take()), 80, 24);
+            drawn[row as usize]
+                .chars()
+                .nth(col as usize)
+                .is_some_and(|c| c != ' ')
+        };
+
+        // Still there to plan with while paused, next pieces and all.
+        game.step(Some(Key::Char('p')), tick);
+        assert!(matches!(game.state, GameState::Pause));
+        assert!(drawn_held(&mut game));
+        screen.0.take();
+        game.render();
+        assert!(String::from_utf8(screen.0.take()).unwrap().contains("Next"));
+        game.step(Some(Key::Char('p')), tick);
+        assert_eq!(game.held, Some(held));
+
+        // A new game starts with nothing held.
+        game.step(Some(Key::Char('r')), tick);
+        assert_eq!(game.held, None);
+        assert!(!game.hold_used);
+        assert!(!drawn_held(&mut game));
+    }
+
+    #[test]
+    fn purist_has_no_ghost_or_hold() {
+        let screen = testing::Capture::default();
+        let mut game =
+            Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(screen.clone())).unwrap();
+        let tick = Duration::from_millis(16);
+        game.spawn(Shape::T);
+        let ghost = game.palette.ghost_cell(Shape::T).to_string();
+        game.render();
+        assert!(String::from_utf8(screen.0.take()).unwrap().contains(&ghost));
+
+        game.set_ghost(false);
+        game.set_hold(false);
+        game.render();
+        let drawn = String::from_utf8(screen.0.take()).unwrap();
+        assert!(!drawn.contains(&ghost));
+        assert!(!drawn.contains("c hold"));
+        assert_eq!(game.layout.hold, None);
+
+        game.step(Some(Key::Char('c')), tick);
+        assert_eq!(game.held, None);
+        assert_eq!(game.falling.as_ref().unwrap().shape, Shape::T);
+    }
+
+    #[test]
+    fn preview_count_is_validated() {
+        let mut game = game();
+        assert_eq!(game.set_preview_count(0), Err(Error::PreviewCount(0)));
+        assert_eq!(game.set_preview_count(6), Err(Error::PreviewCount(6)));
+        assert_eq!(game.set_preview_count(5), Ok(()));
+
+        // 80x24 has room for three under the header, over the keys help,
+        // and four without it. The held piece takes one.
+        game.relayout();
+        assert_eq!(game.layout.preview_shown, 2);
+        game.set_hint(false);
+        assert_eq!(game.layout.preview_shown, 3);
+    }
+
+    #[test]
+    fn ascii_keeps_the_width() {
+        for text in ["GAME OVER ☹️", "←→ move · ↓ drop", "████▏░", "a — b ¦"]
+        {
+            let ascii = glyphs::to_ascii(text);
+            assert!(ascii.is_ascii(), "{:?}", ascii);
+            assert_eq!(Some(ascii.len()), glyphs::display_width(text), "{:?}", text);
+        }
+        assert_eq!(glyphs::to_ascii("GAME OVER ☹️"), "GAME OVER :(");
+        assert_eq!(glyphs::to_ascii("███▏░░"), "###---");
+
+        // Glyphs that are ASCII already stay.
+        let solid = Glyphs::preset("solid").unwrap().ascii();
+        assert_eq!(
+            (solid.filled(), solid.empty(), solid.ghost()),
+            ("[]", ". ", "::")
+        );
+        let custom = Glyphs::new("##", "  ", "░░").unwrap().ascii();
+        assert_eq!(
+            (custom.filled(), custom.empty(), custom.ghost()),
+            ("##", "  ", "::")
+        );
+    }
+
+    #[test]
+    fn glyphs_must_be_two_columns() {
+        assert!(Glyphs::new("##", "  ", "..").is_ok());
+        assert!(Glyphs::new("█", "· ", "░░").is_err());
+        assert!(Glyphs::new("[]]", "· ", "░░").is_err());
+        assert_eq!(
+            Glyphs::new("[]", "· ", "\t "),
+            Err(Error::GlyphWidth("\t ".to_string()))
+        );
+        // One wide char is two columns on its own.
+        assert!(Glyphs::new("口", "· ", "::").is_ok());
+        // So is one asked to be drawn as an emoji.
+        assert_eq!(glyphs::display_width("☹"), Some(1));
+        assert_eq!(glyphs::display_width("GAME OVER ☹️"), Some(12));
+
+        for name in glyphs::PRESETS {
+            assert!(Glyphs::preset(name).is_ok(), "{} is broken", name);
+        }
+        assert_eq!(
+            Glyphs::preset("fancy"),
+            Err(Error::UnknownGlyphs("fancy".to_string()))
+        );
+    }
+
+    #[test]
+    fn empty_glyph_is_only_the_look() {
+        let mut game = game();
+        game.board.fill_row(ROWS - 1, Cell::Garbage);
+        assert_eq!(
+            game.set_empty_glyph("."),
+            Err(Error::GlyphWidth(".".into()))
+        );
+        assert_eq!(game.set_empty_glyph(". "), Ok(()));
+        assert!(game.palette.empty_cell().contains(". "));
+        // Whatever it looks like, the full row still goes.
+        assert_eq!(testing::clear_lines(&mut game), 1);
+
+        // A preset picked after keeps it, no grid still blanks it.
+        game.set_glyphs(Glyphs::preset("solid").unwrap());
+        assert!(game.palette.empty_cell().contains(". "));
+        game.set_grid(false);
+        assert!(!game.palette.empty_cell().contains(". "));
+
+        let config = Config::parse("empty_glyph = \". \"").unwrap();
+        assert_eq!(config.empty_glyph.as_deref(), Some(". "));
+        assert!(Config::parse("empty_glyph = .").is_err());
+    }
+
+    #[test]
+    fn ghost_sits_on_the_stack() {
+        let mut game = game();
+        game.step(None, Duration::from_millis(16));
+        let t = game.falling.clone().unwrap();
+
+        let ghost = game.ghost().unwrap();
+        let lowest = ghost.cells().iter().map(|b| b.y).max().unwrap();
+        assert_eq!(lowest, ROWS as i16 - 1);
+        for (a, b) in t.cells().iter().zip(ghost.cells().iter()) {
+            assert_eq!(a.x, b.x);
+        }
+    }
+
+    #[test]
+    fn seeded_randomizers_deal_the_same_pieces() {
+        let deal = |kind: Randomizer, seed: u64| -> Vec<Shape> {
+            let mut dealer = Dealer::new(kind, seed);
+            (0..70).map(|_| dealer.next_shape()).collect()
+        };
+
+        for kind in [
+            Randomizer::PureRandom,
+            Randomizer::SevenBag,
+            Randomizer::HistoryTgm,
+        ] {
+            assert_eq!(deal(kind, 42), deal(kind, 42));
+            assert_ne!(deal(kind, 42), deal(kind, 43));
+        }
+
+        // Every bag of seven has one of each.
+        for bag in deal(Randomizer::SevenBag, 7).chunks(7) {
+            let mut bag = bag.to_vec();
+            bag.sort_by_key(|&shape| shape as usize);
+            assert_eq!(bag, Shape::ALL);
+        }
+
+        // TGM never opens with an S or Z, and repeats less than pure random.
+        let repeats = |shapes: &[Shape]| shapes.windows(2).filter(|w| w[0] == w[1]).count();
+        let tgm = deal(Randomizer::HistoryTgm, 7);
+        assert!(![Shape::S, Shape::Z].contains(&tgm[0]));
+        assert!(repeats(&tgm) < repeats(&deal(Randomizer::PureRandom, 7)));
+
+        let mut game = game();
+        game.set_randomizer(Randomizer::HistoryTgm);
+        game.set_seed(42);
+        let shapes: Vec<Shape> = game.queue.iter().map(|t| t.shape).collect();
+        assert_eq!(shapes, deal(Randomizer::HistoryTgm, 42)[..MAX_PREVIEW]);
+    }
+
+    #[test]
+    fn cascade_drops_lumps_into_holes_and_chains() {
+        // A full bottom row, one under it missing its first cell, and a
+        // block hanging over that hole.
+        let setup = |gravity| {
+            let mut game = game();
+            game.set_gravity(gravity);
+            game.board.fill_row(ROWS - 1, Cell::Garbage);
+            game.board.fill_row(ROWS - 2, Cell::Garbage);
+            game.board.set(0, ROWS - 2, Cell::Empty);
+            game.board.set(0, ROWS - 5, Cell::Filled(Shape::O));
+            game.clear_completed_lines();
+            game
+        };
+
+        // Naive: the block comes down a row and stays hanging.
+        let game = setup(Gravity::Naive);
+        assert_eq!(game.lines_cleared(), 1);
+        assert_eq!(game.score(), 100);
+        assert_eq!(game.board.get(0, ROWS - 4), Cell::Filled(Shape::O));
+
+        // Cascade: it falls into the hole, that line goes too, at double.
+        let game = setup(Gravity::Cascade);
+        assert_eq!(game.lines_cleared(), 2);
+        assert_eq!(game.score(), 100 + 200);
+        assert!(game.board.rows().flatten().all(|&cell| cell == Cell::Empty));
+
+        assert_eq!(Gravity::by_name("cascade"), Ok(Gravity::Cascade));
+        assert!(Gravity::by_name("sticky").is_err());
+    }
+
+    #[test]
+    fn stack_metrics_read_the_locked_board() {
+        let mut game = game();
+        assert_eq!(game.max_height(), 0);
+
+        // Garbage two high with a hole in column 3, and a block on top of
+        // the first column.
+        game.add_garbage(2, 3).unwrap();
+        game.board.set(0, ROWS - 3, Cell::Filled(Shape::I));
+        assert_eq!(game.max_height(), 3);
+        assert_eq!(game.aggregate_height(), 3 + 2 * (BOARD_WIDTH - 2));
+        assert_eq!(game.holes(), 0);
+        assert_eq!(game.bumpiness(), 1 + 2 + 2);
+
+        // Cover the hole over.
+        game.board.set(3, ROWS - 3, Cell::Filled(Shape::I));
+        assert_eq!(game.holes(), 2);
+    }
+
+    #[test]
+    fn log_says_what_happened() {
+        let capture = testing::Capture::default();
+        let mut game = game();
+        // Off unless asked for.
+        assert!(!game.log.is_on());
+        game.set_log(Box::new(capture.clone()));
+        game.set_line_clear_delay(Duration::ZERO);
+        game.board.fill_row(ROWS - 1, Cell::Garbage);
+        game.board.set(4, ROWS - 1, Cell::Empty);
+
+        game.spawn(Shape::I);
+        game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+        game.top_out();
+        game.log.flush();
+
+        let log = String::from_utf8(capture.0.take()).unwrap();
+        assert_eq!(
+            log.lines().collect::<Vec<_>>(),
+            [
+                "0.000 spawn I",
+                // Straight down 18 rows, 2 a row.
+                "0.016 lock I 4,16 4,17 4,18 4,19 +36",
+                "0.016 clear 1 +100",
+                "0.016 over score 136 lines 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn screen_reader_says_what_happens() {
+        let screen = testing::Capture::default();
+        let mut game =
+            Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(screen.clone())).unwrap();
+        game.set_screen_reader(true);
+        let tick = Duration::from_millis(16);
+
+        game.spawn(Shape::T);
+        game.step(Some(Key::Left), tick);
+        game.step(Some(Key::Left), tick);
+        // Still moving, only the spawn is said.
+        game.render();
+        game.step(None, Duration::from_millis(250));
+        game.step(Some(Key::Char('k')), tick);
+        game.step(Some(Key::Char(' ')), tick);
+        game.render();
+
+        let said = String::from_utf8(screen.0.take()).unwrap();
+        assert!(!said.contains('\x1b'), "{:?}", said);
+        assert_eq!(
+            said.split("\r\n").collect::<Vec<_>>(),
+            [
+                "T, column 5",
+                "left, column 3",
+                "heights 0 0 0 0 0 0 0 0 0 0",
+                "T locked",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn game_over_adds_a_stats_row() {
+        let path = std::env::temp_dir().join(format!("tetris-game-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut game = game();
+        game.set_seed(42);
+        game.set_stats_csv(&path);
+        game.score = 1200;
+        game.lines_cleared = 12;
+        game.top_out();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let row = text.lines().nth(1).unwrap();
+        assert!(row.ends_with(",Marathon,42,1200,12,2,0,0.000"), "{}", row);
+    }
+
+    #[test]
+    fn recording_plays_back_the_screen() {
+        let screen = testing::Capture::default();
+        let cast = testing::Capture::default();
+        let mut game =
+            Game::with_output(BOARD_WIDTH, BOARD_HEIGHT, Box::new(screen.clone())).unwrap();
+        game.set_record(Box::new(cast.clone())).unwrap();
+        game.init_screen();
+        for _ in 0..3 {
+            game.frame(Duration::from_millis(16));
+        }
+        drop(game);
+
+        // The header, then a line per frame.
+        let cast = String::from_utf8(cast.0.take()).unwrap();
+        let mut lines = cast.lines();
+        let header = lines.next().unwrap();
+        assert!(header.contains("\"width\": 80, \"height\": 24"));
+        let frames: Vec<&str> = lines.collect();
+        assert!(frames.len() >= 3);
+        assert!(frames.iter().all(|line| line.contains(", \"o\", \"")));
+        assert!(!screen.0.borrow().is_empty());
+    }
+
+    #[cfg(feature = "snapshot")]
+    #[test]
+    fn game_over_saves_a_snapshot() {
+        let path = std::env::temp_dir().join(format!("tetris-{}.png", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut game = game();
+        game.set_snapshot(&path);
+        game.top_out();
+        let png = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // 10 by 20 cells of 12 pixels, and three lines under them.
+        assert_eq!(png[16..20], 120u32.to_be_bytes());
+        assert_eq!(png[20..24], (240u32 + 4 + 3 * 14).to_be_bytes());
+    }
+
+    #[test]
+    fn garbage_rises_from_the_bottom_with_a_hole() {
+        let mut game = game();
+        game.board.set(0, ROWS - 1, Cell::Filled(Shape::T));
+
+        assert_eq!(
+            game.add_garbage(1, BOARD_WIDTH),
+            Err(Error::GarbageHole {
+                hole_col: BOARD_WIDTH,
+                width: BOARD_WIDTH
+            })
+        );
+
+        game.add_garbage(2, 3).unwrap();
+        for row in game.board.rows().skip(ROWS - 2) {
+            for (x, &cell) in row.iter().enumerate() {
+                let expected = if x == 3 { Cell::Empty } else { Cell::Garbage };
+                assert_eq!(cell, expected);
+            }
+        }
+        // The stack went up with it.
+        assert_eq!(game.board.get(0, ROWS - 3), Cell::Filled(Shape::T));
+        assert!(matches!(game.state, GameState::Play));
+
+        // Push it out the top.
+        game.add_garbage(ROWS - 3, 0).unwrap();
+        assert!(matches!(game.state, GameState::Play));
+        game.add_garbage(1, 0).unwrap();
+        assert!(matches!(game.state, GameState::Lose | GameState::NameEntry));
+    }
+
+    #[test]
+    fn row_masks_keep_up_with_the_board() {
+        let mut game = game();
+        let h = ROWS as i16;
+        let full = (1 << BOARD_WIDTH) - 1;
+        let masks = |game: &Game| [game.board.mask(ROWS - 2), game.board.mask(ROWS - 1)];
+
+        // Bottom row but for the first four, and one up on the right.
+        for x in 4..BOARD_WIDTH {
+            game.board.set(x, ROWS - 1, Cell::Garbage);
+        }
+        game.board.set(BOARD_WIDTH - 1, ROWS - 2, Cell::Garbage);
+        assert_eq!(masks(&game), [1 << 9, full & !0b1111]);
+
+        // An I locks in the gap.
+        game.falling = Some(tetromino(Shape::I, 1, 1, h - 1));
+        game.insert_falling();
+        assert_eq!(masks(&game), [1 << 9, full]);
+        assert_eq!(game.completed_lines(), vec![ROWS - 1]);
+
+        // The line goes, the one over it comes down.
+        game.clear_completed_lines();
+        assert_eq!(masks(&game), [0, 1 << 9]);
+
+        // Garbage pushes it back up.
+        game.add_garbage(1, 3).unwrap();
+        assert_eq!(masks(&game), [1 << 9, full & !(1 << 3)]);
+    }
+
+    #[test]
+    fn no_border_keeps_cells_where_the_frame_was() {
+        // No room to center in, so it all hugs the top-left corner.
+        let mut game = game();
+        game.screen_size = Some((1, 1));
+        game.relayout();
+        let corner = |game: &Game| (game.layout.left, game.layout.top, game.layout.bottom);
+        assert_eq!(corner(&game), (1, 1, 22));
+
+        game.set_border(None);
+        assert_eq!(corner(&game), (0, 0, 20));
+
+        // Two digits of row numbers and a space, then straight into cells.
+        game.set_labels(true);
+        assert_eq!(corner(&game), (3, 0, 20));
+
+        game.set_border(Some(border::DOUBLE));
+        assert_eq!(corner(&game), (4, 1, 22));
+    }
+
+    #[test]
+    fn layout_is_centered() {
+        // 80x24: the board's 22 columns, 2 to the sidebar's 27, 2 to the
+        // preview's 8 is 61, leaving 9 left and 10 right. 22 rows out of 24,
+        // the keys help takes the other 2.
+        let mut game = game();
+        assert_eq!((game.layout.left, game.layout.top), (10, 1));
+        assert_eq!(game.layout.hud, (10 + 24, 2));
+        assert_eq!(game.layout.preview, (10 + 24 + 29, 2));
+        assert_eq!(game.layout.preview.0 + 7, 80 - 10);
+        assert_eq!(game.layout.hint, Some(23));
+
+        // Without it, the board moves down into the middle.
+        game.set_hint(false);
+        assert_eq!(game.layout.top, 2);
+        assert_eq!(game.layout.hint, None);
+
+        // Too narrow for the sidebar, the HUD goes under and the board is
+        // centered over it.
+        let mut game = game_with_mode(GameMode::Marathon);
+        game.screen_size = Some((40, 40));
+        game.relayout();
+        assert!(game.layout.hud_below);
+        assert_eq!(game.layout.left, 10);
+        assert_eq!(game.layout.hud, (11, game.layout.bottom + 1));
+        assert_eq!(game.layout.preview_shown, 0);
+    }
+
+    #[test]
+    fn waits_for_a_big_enough_terminal() {
+        // 10x20 with a border is 22x22, plus 2 and the 18 column HUD beside
+        // it. Under it, 22 + 5 + 1 rows.
+        let mut game = game();
+        game.screen_size = Some((41, 21));
+        game.relayout();
+        assert!(!game.layout.fits);
+        assert_eq!(game.layout.need, (42, 22));
+
+        // Nothing moves until it fits.
+        let before = game.board.clone();
+        for _ in 0..100 {
+            game.update(Duration::from_secs(1));
+        }
+        assert_eq!(game.board, before);
+        assert!(game.falling.is_none());
+
+        // Tall enough for the HUD to go under the board.
+        game.screen_size = Some((30, 29));
+        game.render();
+        assert!(game.layout.fits && game.layout.hud_below);
+        game.update(Duration::from_millis(16));
+        assert!(game.falling.is_some());
+    }
+
+    #[test]
+    fn game_over_panel_is_centered_on_the_board() {
+        let mut game = game();
+        game.screen_size = Some((80, 24));
+        game.relayout();
+        let (left, top) = (game.layout.left, game.layout.top);
+
+        // 20 columns and 20 rows inside the border: mid-board both ways, not
+        // up by the width.
+        assert_eq!(game.panel_pos(18, 8), (left + 2, top + 1 + 6));
+        assert_eq!(game.panel_pos(20, 20), (left + 1, top + 1));
+
+        // A narrow board, the panel hangs over evenly on both sides.
+        let mut game = Game::with_output(4, 16, Box::new(std::io::sink())).unwrap();
+        game.screen_size = Some((80, 24));
+        game.relayout();
+        let left = game.layout.left;
+        assert_eq!(game.panel_pos(18, 8).0, left + 1 - 5);
+    }
+
+    #[test]
+    fn game_over_lands_mid_board_whatever_its_size() {
+        for (w, h) in [(6, 12), (10, 20), (16, 30)] {
+            let screen = testing::Capture::default();
+            let mut game = Game::with_output(w, h, Box::new(screen.clone())).unwrap();
+            // Practice, so there's no name to enter first.
+            game.set_mode(GameMode::Practice);
+            game.set_emoji(false);
+            game.set_scale(Some(1)).unwrap();
+            game.screen_size = Some((80, 40));
+            game.relayout();
+            game.top_out();
+            game.render();
+            let lines: Vec<Vec<char>> =
+                testing::screen(&String::from_utf8(screen.0.take()).unwrap(), 80, 40)
+                    .iter()
+                    .map(|line| line.chars().collect())
+                    .collect();
+            let find = |text: &str| {
+                let text: Vec<char> = text.chars().collect();
+                lines.iter().enumerate().find_map(|(y, line)| {
+                    let x = line
+                        .windows(text.len())
+                        .position(|s| s == text.as_slice())?;
+                    Some((x, y))
+                })
+            };
+
+            // The board inside its border, in screen rows and columns.
+            let (corner, top) = find("┌").unwrap();
+            let (left, top) = (corner + 1, top + 1);
+            let (title, title_row) = find("GAME OVER").unwrap();
+            let (_, keys_row) = find("r retry").unwrap();
+
+            // Across: the title's middle is the board's.
+            let size = format!("{}x{}", w, h);
+            assert!((2 * title + 9).abs_diff(2 * left + 2 * w) <= 1, "{}", size);
+
+            // Down: the panel, and the high score table under it if there's
+            // room, as far from the bottom as from the top, so height/2 is
+            // in the middle of it.
+            let mut bottom = keys_row;
+            if find("HIGH SCORES").is_some() {
+                bottom += 1 + (h - (keys_row - title_row + 2)).min(1 + highscore::MAX_ENTRIES);
+            }
+            let (above, below) = (title_row - top, top + h - 1 - bottom);
+            assert!(
+                above.abs_diff(below) <= 1,
+                "{}: {} above, {} below",
+                size,
+                above,
+                below
+            );
+            assert!((title_row..=bottom).contains(&(top + h / 2)), "{}", size);
+        }
+    }
+
+    #[test]
+    fn board_corners_on_screen() {
+        let mut game = game();
+        game.screen_size = Some((80, 24));
+        game.relayout();
+        let (left, top) = (game.layout.left, game.layout.top);
+        let (w, h) = (BOARD_WIDTH as u16, BOARD_HEIGHT as u16);
+
+        // Just inside the border, two columns to a cell.
+        assert_eq!(game.board_to_screen(0, 0), (left + 1, top + 1));
+        assert_eq!(game.board_to_screen(w - 1, 0), (left + 2 * w - 1, top + 1));
+        assert_eq!(game.board_to_screen(0, h - 1), (left + 1, top + h));
+        assert_eq!(
+            game.board_to_screen(w - 1, h - 1),
+            (left + 2 * w - 1, top + h)
+        );
+        // The last cell ends on the right border, the bottom row sits on top
+        // of the bottom one.
+        assert_eq!(left + 2 * w + 1, left + 1 + game.layout.inner.0);
+        assert_eq!(top + h + 1, game.layout.bottom);
+
+        // Cells count from the top of the buffer.
+        let bottom = (BUFFER_ROWS + BOARD_HEIGHT - 1) as i16;
+        assert_eq!(game.cell_pos(0, bottom), Some((left + 1, top + h)));
+    }
+
+    #[test]
+    fn big_cells_on_a_big_terminal() {
+        // Twice the 42x22 it needs and then some: cells go 4x2.
+        let mut game = game();
+        game.screen_size = Some((100, 50));
+        game.relayout();
+        assert_eq!(game.layout.cell, (4, 2));
+        assert_eq!(game.layout.inner, (40, 40));
+        let (left, top) = (game.layout.left, game.layout.top);
+        assert_eq!(
+            game.cell_pos(3, BUFFER_ROWS as i16 + 2),
+            Some((left + 1 + 12, top + 1 + 4))
+        );
+        assert_eq!(game.cell_pos(3, BUFFER_ROWS as i16 - 1), None);
+
+        // Unless it's set to stay small.
+        game.set_scale(Some(1)).unwrap();
+        assert_eq!(game.layout.cell, (2, 1));
+        assert_eq!(
+            game.set_scale(Some(3)),
+            Err(Error::UnknownScale("3".to_string()))
+        );
+
+        // Not quite twice the size.
+        game.set_scale(None).unwrap();
+        game.screen_size = Some((100, 43));
+        game.relayout();
+        assert_eq!(game.layout.cell, (2, 1));
+
+        assert_eq!(Config::parse("scale = auto").unwrap().scale, Some(None));
+        assert_eq!(Config::parse("scale = 2").unwrap().scale, Some(Some(2)));
+        assert!(Config::parse("scale = 3").is_err());
+    }
+
+    #[test]
+    fn survival_garbage_speeds_up() {
+        let mut game = game();
+        game.set_mode(GameMode::Survival);
+        assert_eq!(game.garbage_interval(), SURVIVAL_START);
+
+        // Nothing until the first piece is out.
+        game.step(None, SURVIVAL_START);
+        assert!(game.board[ROWS - 1].iter().all(|&c| c == Cell::Empty));
+
+        game.step(None, SURVIVAL_START);
+        let row = &game.board[ROWS - 1];
+        assert_eq!(
+            row.iter().filter(|&&c| c == Cell::Garbage).count(),
+            BOARD_WIDTH - 1
+        );
+
+        game.play_time = SURVIVAL_SPEEDUP * 3;
+        assert_eq!(
+            game.garbage_interval(),
+            SURVIVAL_START - Duration::from_secs(3)
+        );
+        game.play_time = SURVIVAL_SPEEDUP * 100;
+        assert_eq!(game.garbage_interval(), SURVIVAL_FASTEST);
+
+        // Marathon never gets any.
+        let mut game = game_with_mode(GameMode::Marathon);
+        game.step(None, Duration::from_millis(16));
+        game.step(None, SURVIVAL_START * 2);
+        assert!(!game.board.rows().flatten().any(|&c| c == Cell::Garbage));
+    }
+
+    #[test]
+    fn themes_and_config() {
+        for name in theme::PRESETS {
+            assert!(Theme::preset(name).is_ok(), "{} is broken", name);
+        }
+        assert_eq!(
+            Theme::preset("neon"),
+            Err(Error::UnknownTheme("neon".to_string()))
+        );
+
+        assert_eq!(Color::parse("light_blue"), Ok(Color::Ansi(12)));
+        assert_eq!(Color::parse(" 255, 165,0 "), Ok(Color::Rgb(255, 165, 0)));
+        assert_eq!(Color::parse("default"), Ok(Color::Default));
+        for bad in ["orange", "1, 2", "256, 0, 0", ""] {
+            assert!(Color::parse(bad).is_err(), "{:?} parsed", bad);
+        }
+
+        // Colors change the theme whichever line it's on.
+        let config = Config::parse(
+            "# mine\n\ncolor.L = 1, 2, 3\ncolor.ghost = red  # loud\ntheme = monochrome\n",
+        )
+        .unwrap();
+        let mut theme = Theme::preset("monochrome").unwrap();
+        theme.set("L", "1, 2, 3").unwrap();
+        theme.set("ghost", "red").unwrap();
+        assert_eq!(config.theme, Some(theme.clone()));
+        assert_eq!(theme.ghost(Shape::T), Color::RED);
+        theme.set("ghost", "piece").unwrap();
+        assert_eq!(theme.ghost(Shape::T), theme.piece(Shape::T));
+
+        assert_eq!(Config::parse(""), Ok(Config::default()));
+        assert_eq!(
+            Config::parse("snapshot = /tmp/x.png").unwrap().snapshot,
+            Some("/tmp/x.png".into())
+        );
+        assert_eq!(
+            Config::parse("countdown = off").unwrap().countdown,
+            Some(false)
+        );
+        assert_eq!(Config::parse("soft_drop = 0").unwrap().soft_drop, Some(0));
+        assert_eq!(
+            Config::parse("soft_drop = lots"),
+            Err(Error::Config {
+                line: 1,
+                error: Box::new(Error::NotANumber("lots".to_string()))
+            })
+        );
+        let config = Config::parse("ghost = off\nhold = off").unwrap();
+        assert_eq!((config.ghost, config.hold), (Some(false), Some(false)));
+        assert_eq!(
+            Config::parse("colors = basic").unwrap().colors,
+            Some(ColorDepth::Basic)
+        );
+        assert_eq!(
+            Config::parse("theme = classic\ncolor.X = red"),
+            Err(Error::Config {
+                line: 2,
+                error: Box::new(Error::UnknownSetting("color.X".to_string()))
+            })
+        );
+        assert_eq!(
+            Config::parse("volume = 11"),
+            Err(Error::Config {
+                line: 1,
+                error: Box::new(Error::UnknownSetting("volume".to_string()))
+            })
+        );
+        assert!(Config::parse("colour").is_err());
+
+        assert_eq!(Config::parse("emoji = off").unwrap().emoji, Some(false));
+        assert_eq!(Config::parse("hint = off").unwrap().hint, Some(false));
+        assert_eq!(Config::parse("ascii = on").unwrap().ascii, Some(true));
+        assert_eq!(Config::parse("title = on").unwrap().title, Some(true));
+        assert_eq!(config::scale_by_name("auto"), Ok(None));
+        assert_eq!(config::scale_by_name("2"), Ok(Some(2)));
+        assert!(config::scale_by_name("3").is_err());
+        assert_eq!(
+            Config::parse("emoji = maybe"),
+            Err(Error::Config {
+                line: 1,
+                error: Box::new(Error::NotOnOff("maybe".to_string()))
+            })
+        );
+    }
+
+    #[test]
+    fn colors_fall_back_to_what_the_terminal_has() {
+        use theme::{nearest_256, nearest_basic};
+
+        // No orange in the 16, the L goes yellow. The escapes themselves are
+        // the renderer's, see its tests.
+        assert_eq!(nearest_basic(255, 165, 0), 3);
+        assert_eq!(nearest_basic(250, 10, 10), 9);
+
+        // The 256 palette has it, near enough.
+        assert_eq!(nearest_256(255, 165, 0), 214);
+        assert_eq!(nearest_256(128, 128, 128), 244);
+
+        // Not much to work with, so high contrast unless asked otherwise.
+        assert_eq!(
+            Theme::default_for(ColorDepth::Basic),
+            Theme::preset("high-contrast").unwrap()
+        );
+        assert_eq!(Theme::default_for(ColorDepth::TrueColor), Theme::default());
+    }
+
+    #[test]
+    fn practice_undo_takes_back_the_last_piece() {
+        let mut game = game_with_mode(GameMode::Practice);
+        let frame = Duration::from_millis(16);
+        game.step(None, frame);
+        let first = game.falling.clone().unwrap();
+        let next = game.queue[0].shape;
+
+        game.step(Some(Key::Char(' ')), frame);
+        game.step(None, frame);
+        assert_eq!(game.pieces_placed, 1);
+        assert!(game.score > 0);
+
+        game.step(Some(Key::Char('u')), frame);
+        assert!(game.board.rows().flatten().all(|&c| c == Cell::Empty));
+        assert_eq!((game.score, game.pieces_placed), (0, 0));
+        assert_eq!(game.falling, Some(first));
+        assert_eq!(game.queue[0].shape, next);
+
+        // Nothing left to undo.
+        game.step(Some(Key::Char('u')), frame);
+        assert_eq!(game.pieces_placed, 0);
+
+        // Only so far back.
+        for _ in 0..UNDO_DEPTH + 5 {
+            game.save_undo();
+        }
+        assert_eq!(game.undo.len(), UNDO_DEPTH);
+
+        // Not outside practice.
+        let mut game = game_with_mode(GameMode::Marathon);
+        game.step(None, frame);
+        game.step(Some(Key::Char(' ')), frame);
+        game.step(Some(Key::Char('u')), frame);
+        assert_eq!(game.pieces_placed, 1);
+    }
+
+    #[test]
+    fn zen_sweeps_the_stack_instead_of_ending() {
+        let tick = Duration::from_millis(16);
+        // Full to the top bar the first column, nothing fits at the spawn.
+        let stack_up = |game: &mut Game| {
+            for y in 0..ROWS {
+                game.board.fill_row(y, Cell::Garbage);
+                game.board.set(0, y, Cell::Empty);
+            }
+        };
+
+        let mut game = game_with_mode(GameMode::Zen);
+        game.score = 1200;
+        game.lines_cleared = 12;
+        stack_up(&mut game);
+        game.step(None, tick);
+        assert!(matches!(game.state, GameState::Play));
+        assert_eq!(game.sweeps, 1);
+        assert!(game.board.rows().flatten().all(|&cell| cell == Cell::Empty));
+        assert_eq!((game.score, game.lines_cleared), (1200, 12));
+
+        // And on it goes.
+        for _ in 0..100 {
+            game.step(None, tick);
+        }
+        assert!(game.falling.is_some());
+        assert!(matches!(game.state, GameState::Play));
+
+        // Anywhere else it's over.
+        let mut game = game_with_mode(GameMode::Practice);
+        stack_up(&mut game);
+        game.step(None, tick);
+        assert!(matches!(game.state, GameState::Lose));
+    }
+
+    #[test]
+    fn invisible_hides_the_stack_but_not_from_the_piece() {
+        let mut game = game_with_mode(GameMode::Invisible);
+        let tick = Duration::from_millis(16);
+        game.step(None, tick);
+        game.step(Some(Key::Char(' ')), tick);
+        let block = game.board[ROWS - 1]
+            .iter()
+            .position(|&cell| cell != Cell::Empty)
+            .unwrap();
+
+        // In sight for a moment, then gone from view but still there.
+        assert_ne!(game.shown(block, ROWS - 1), Cell::Empty);
+        game.step(None, INVISIBLE_DELAY);
+        assert_eq!(game.shown(block, ROWS - 1), Cell::Empty);
+        assert_ne!(game.board.get(block, ROWS - 1), Cell::Empty);
+
+        // v brings it back for a while, REVEALS times.
+        game.step(Some(Key::Char('v')), tick);
+        assert_ne!(game.shown(block, ROWS - 1), Cell::Empty);
+        game.step(None, REVEAL_DURATION);
+        assert_eq!(game.shown(block, ROWS - 1), Cell::Empty);
+        for _ in 0..REVEALS {
+            game.step(Some(Key::Char('v')), tick);
+            game.step(None, REVEAL_DURATION);
+        }
+        game.step(Some(Key::Char('v')), tick);
+        assert_eq!(game.reveals, 0);
+        assert_eq!(game.shown(block, ROWS - 1), Cell::Empty);
+
+        // What's still in sight moves down with a clear.
+        game.fading = vec![(Point::new(0, ROWS as i16 - 2), INVISIBLE_DELAY)];
+        game.clearing.clear();
+        game.board.fill_row(ROWS - 1, Cell::Garbage);
+        game.clear_completed_lines();
+        assert_eq!(game.fading[0].0, Point::new(0, ROWS as i16 - 1));
+    }
+
+    #[test]
+    fn daily_is_seeded_by_the_date_and_replays_on_restart() {
+        let mut game = game();
+        game.start_daily("2026-10-15");
+        assert_eq!(game.seed, 20261015);
+        let first: Vec<Shape> = game.queue.iter().map(|t| t.shape).collect();
+
+        game.step(None, Duration::from_millis(16));
+        game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+        game.step(Some(Key::Char('r')), Duration::from_millis(16));
+        let again: Vec<Shape> = game.queue.iter().map(|t| t.shape).collect();
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    fn mirror_flips_the_drawing_and_the_keys() {
+        let mut game = game();
+        let y = BUFFER_ROWS as i16;
+        let right = game.cell_pos(BOARD_WIDTH as i16 - 1, y);
+        game.set_mirror(true);
+        assert_eq!(game.cell_pos(0, y), right);
+
+        // Left on screen is right on the board.
+        game.spawn(Shape::O);
+        let x = game.falling.as_ref().unwrap().cells()[0].x;
+        game.step(Some(Key::Left), Duration::from_millis(16));
+        assert_eq!(game.falling.as_ref().unwrap().cells()[0].x, x + 1);
+    }
+
+    #[test]
+    fn practice_picks_pieces_and_stays_off_the_table() {
+        let mut game = game_with_mode(GameMode::Practice);
+        let frame = Duration::from_millis(16);
+        game.step(None, frame);
+
+        game.step(Some(Key::Char('3')), frame);
+        assert_eq!(game.falling.as_ref().unwrap().shape, Shape::T);
+        game.step(Some(Key::Char('1')), frame);
+        assert_eq!(game.falling.as_ref().unwrap().shape, Shape::I);
+        // Only the one that's falling counts, not every piece it replaced.
+        assert_eq!(game.piece_counts.iter().sum::<usize>(), 1);
+        assert_eq!(game.piece_counts[Shape::I as usize], 1);
+
+        // Digits are nothing special outside practice.
+        let mut marathon = game_with_mode(GameMode::Marathon);
+        marathon.step(None, frame);
+        let falling = marathon.falling.clone();
+        marathon.step(Some(Key::Char('3')), frame);
+        assert_eq!(marathon.falling, falling);
+
+        // Any score makes the empty table, but not from practice.
+        game.score = 1000;
+        game.top_out();
+        assert!(matches!(game.state, GameState::Lose));
     }
 }
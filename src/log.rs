@@ -0,0 +1,54 @@
+// Event log.
+// With --log, a line per thing that happened (a piece dealt, a lock, a
+// clear, the end) goes to a file, for when something odd happens and needs
+// playing back. Each line starts with the play time it happened at, in
+// seconds, then what it was:
+//
+//     12.480 spawn T
+//     13.216 lock T 3,17 4,17 5,17 4,18 +6
+//     13.216 clear 1 +100
+//     95.002 over score 8400 lines 36
+//
+// Off, it's a None and costs a branch. Nothing is written while drawing.
+use std::fmt;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct Logger {
+    out: Option<BufWriter<Box<dyn Write>>>,
+}
+
+impl Logger {
+    pub fn to(out: Box<dyn Write>) -> Self {
+        Self {
+            out: Some(BufWriter::new(out)),
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.out.is_some()
+    }
+
+    // One line, at `time` into the game. A log that can't be written to is
+    // given up on rather than taking the game down with it.
+    pub fn event(&mut self, time: Duration, what: fmt::Arguments) {
+        if let Some(out) = self.out.as_mut() {
+            if writeln!(out, "{:.3} {}", time.as_secs_f64(), what).is_err() {
+                self.out = None;
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(out) = self.out.as_mut() {
+            let _ = out.flush();
+        }
+    }
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
@@ -1,6 +1,357 @@
-use tetris::Game;
+use std::env;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::process;
+use std::time::Duration;
+
+use tetris::config::Config;
+use tetris::glyphs::Glyphs;
+use tetris::gravity::Gravity;
+use tetris::randomizer::Randomizer;
+use tetris::theme::Theme;
+use tetris::versus::Versus;
+use tetris::{Game, GameMode};
 
 fn main() {
-    let mut game = Game::default();
+    let args: Vec<String> = env::args().collect();
+
+    // --versus: two players, one keyboard.
+    if args.iter().any(|arg| arg == "--versus") {
+        let mut versus = Versus::default();
+        versus.run();
+
+        let summary = versus.summary();
+        drop(versus);
+        if !args.iter().any(|arg| arg == "--no-summary") {
+            println!("{}", summary);
+        }
+        return;
+    }
+    // --daily: today's pieces, the same for everyone.
+    let mut game = match args.iter().any(|arg| arg == "--daily") {
+        true => Game::daily(),
+        false => Game::default(),
+    };
+    game.show_title();
+
+    // ~/.config/tetris/config, see config.rs.
+    match Config::load() {
+        Ok(config) => {
+            if let Some(depth) = config.colors {
+                game.set_color_depth(depth);
+                game.set_theme(Theme::default_for(depth));
+            }
+            if let Some(theme) = config.theme {
+                game.set_theme(theme);
+            }
+            if let Some(on) = config.emoji {
+                game.set_emoji(on);
+            }
+            if let Some(on) = config.hint {
+                game.set_hint(on);
+            }
+            if let Some(on) = config.ascii {
+                game.set_ascii(on);
+            }
+            if let Some(on) = config.title {
+                game.set_title(on);
+            }
+            if let Some(on) = config.countdown {
+                game.set_countdown(on);
+            }
+            if let Some(on) = config.ghost {
+                game.set_ghost(on);
+            }
+            if let Some(on) = config.hold {
+                game.set_hold(on);
+            }
+            if let Some(scale) = config.scale {
+                if let Err(e) = game.set_scale(scale) {
+                    fail(game, format!("config: {}", e));
+                }
+            }
+            #[cfg(feature = "snapshot")]
+            if let Some(path) = config.snapshot {
+                game.set_snapshot(path);
+            }
+            #[cfg(not(feature = "snapshot"))]
+            if config.snapshot.is_some() {
+                fail(game, "config: snapshot needs the snapshot feature");
+            }
+            if let Some(glyph) = config.empty_glyph {
+                if let Err(e) = game.set_empty_glyph(&glyph) {
+                    fail(game, format!("config: {}", e));
+                }
+            }
+            if let Some(points) = config.soft_drop {
+                game.set_soft_drop_points(points);
+            }
+        }
+        Err(e) => fail(game, e),
+    }
+
+    // --no-color, or NO_COLOR set to anything: no colors at all.
+    // Ref: https://no-color.org
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || args.iter().any(|arg| arg == "--no-color") {
+        game.set_color(false);
+    }
+
+    // --colorblind: every piece gets its own glyph.
+    if args.iter().any(|arg| arg == "--colorblind") {
+        game.set_colorblind(true);
+    }
+
+    // --ai: sit back and watch.
+    if args.iter().any(|arg| arg == "--ai") {
+        game.set_autoplay(true);
+    }
+
+    // --no-dim: draw the stack at full brightness.
+    if args.iter().any(|arg| arg == "--no-dim") {
+        game.set_dim_locked(false);
+    }
+
+    // --ascii: nothing but ASCII on screen.
+    if args.iter().any(|arg| arg == "--ascii") {
+        game.set_ascii(true);
+    }
+
+    // --no-grid: blank empty cells, no dots.
+    if args.iter().any(|arg| arg == "--no-grid") {
+        game.set_grid(false);
+    }
+
+    // --mirror: the board flipped left to right. Also in the pause menu.
+    if args.iter().any(|arg| arg == "--mirror") {
+        game.set_mirror(true);
+    }
+
+    // --stripes: shade every other column.
+    if args.iter().any(|arg| arg == "--stripes") {
+        game.set_stripes(true);
+    }
+
+    // --no-countdown: straight into the game, no 3-2-1.
+    if args.iter().any(|arg| arg == "--no-countdown") {
+        game.set_countdown(false);
+    }
+
+    // --no-ghost, --no-hold: no landing spot drawn, no putting a piece aside.
+    if args.iter().any(|arg| arg == "--no-ghost") {
+        game.set_ghost(false);
+    }
+    if args.iter().any(|arg| arg == "--no-hold") {
+        game.set_hold(false);
+    }
+
+    // --screen-reader: plain lines saying what happens, no board.
+    if args.iter().any(|arg| arg == "--screen-reader") {
+        game.set_screen_reader(true);
+    }
+
+    // --no-effects: no hard drop trail and thud, no score popups.
+    if args.iter().any(|arg| arg == "--no-effects") {
+        game.set_effects(false);
+    }
+
+    // --labels: number the rows and columns. `l` toggles them in game too.
+    if args.iter().any(|arg| arg == "--labels") {
+        game.set_labels(true);
+    }
+
+    // --glyphs NAME: brackets, solid or shaded cells.
+    if let Some(i) = args.iter().position(|arg| arg == "--glyphs") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match Glyphs::preset(name) {
+            Ok(glyphs) => game.set_glyphs(glyphs),
+            Err(e) => fail(game, format!("--glyphs: {}", e)),
+        }
+    }
+
+    // --empty-glyph GLYPH: empty cells drawn as GLYPH, two columns wide.
+    if let Some(i) = args.iter().position(|arg| arg == "--empty-glyph") {
+        let glyph = args.get(i + 1).map_or("", |glyph| glyph.as_str());
+        if let Err(e) = game.set_empty_glyph(glyph) {
+            fail(game, format!("--empty-glyph: {}", e));
+        }
+    }
+
+    // --theme NAME: classic, pastel, monochrome or high-contrast. Beats the
+    // config file.
+    if let Some(i) = args.iter().position(|arg| arg == "--theme") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match Theme::preset(name) {
+            Ok(theme) => game.set_theme(theme),
+            Err(e) => fail(game, format!("--theme: {}", e)),
+        }
+    }
+
+    // --mode NAME: marathon, survival, practice, invisible or zen.
+    if let Some(i) = args.iter().position(|arg| arg == "--mode") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match GameMode::by_name(name) {
+            Ok(mode) => game.set_mode(mode),
+            Err(e) => fail(game, format!("--mode: {}", e)),
+        }
+    }
+
+    // --border NAME: single, double, heavy, rounded, ascii or none.
+    if let Some(i) = args.iter().position(|arg| arg == "--border") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match tetris::border::by_name(name) {
+            Ok(border) => game.set_border(border),
+            Err(e) => fail(game, format!("--border: {}", e)),
+        }
+    }
+
+    // --randomizer NAME: random, bag or tgm.
+    if let Some(i) = args.iter().position(|arg| arg == "--randomizer") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match Randomizer::by_name(name) {
+            Ok(randomizer) => game.set_randomizer(randomizer),
+            Err(e) => fail(game, format!("--randomizer: {}", e)),
+        }
+    }
+
+    // --gravity NAME: naive or cascade, for after a line clear.
+    if let Some(i) = args.iter().position(|arg| arg == "--gravity") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match Gravity::by_name(name) {
+            Ok(gravity) => game.set_gravity(gravity),
+            Err(e) => fail(game, format!("--gravity: {}", e)),
+        }
+    }
+
+    // --log PATH: append what happens to PATH, for bug reports.
+    if let Some(i) = args.iter().position(|arg| arg == "--log") {
+        let opened = match args.get(i + 1) {
+            Some(path) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| e.to_string()),
+            None => Err("needs a file to write to".to_string()),
+        };
+        match opened {
+            Ok(file) => game.set_log(Box::new(file)),
+            Err(e) => fail(game, format!("--log: {}", e)),
+        }
+    }
+
+    // --record PATH: an asciicast of the game, for `asciinema play PATH`.
+    if let Some(i) = args.iter().position(|arg| arg == "--record") {
+        let recording = match args.get(i + 1) {
+            Some(path) => File::create(path)
+                .and_then(|file| game.set_record(Box::new(file)))
+                .map_err(|e| e.to_string()),
+            None => Err("needs a file to write to".to_string()),
+        };
+        if let Err(e) = recording {
+            fail(game, format!("--record: {}", e));
+        }
+    }
+
+    // --snapshot PATH: the board as a PNG at the end of each game. Only with
+    // the snapshot feature, on unless built with --no-default-features.
+    #[cfg(feature = "snapshot")]
+    if let Some(i) = args.iter().position(|arg| arg == "--snapshot") {
+        match args.get(i + 1) {
+            Some(path) => game.set_snapshot(path),
+            None => fail(game, "--snapshot: needs a file to write to"),
+        }
+    }
+    #[cfg(not(feature = "snapshot"))]
+    if args.iter().any(|arg| arg == "--snapshot") {
+        fail(game, "--snapshot: needs the snapshot feature");
+    }
+
+    // --stats-csv PATH: a row per finished game, appended to PATH.
+    if let Some(i) = args.iter().position(|arg| arg == "--stats-csv") {
+        match args.get(i + 1) {
+            Some(path) => game.set_stats_csv(path),
+            None => fail(game, "--stats-csv: needs a file to write to"),
+        }
+    }
+
+    // --seed N: the same pieces every time.
+    if let Some(i) = args.iter().position(|arg| arg == "--seed") {
+        match args.get(i + 1).and_then(|n| n.parse().ok()) {
+            Some(seed) => game.set_seed(seed),
+            None => fail(game, "--seed: needs a number"),
+        }
+    }
+
+    // --are MS: wait MS milliseconds after each lock before the next piece.
+    if let Some(i) = args.iter().position(|arg| arg == "--are") {
+        match args.get(i + 1).and_then(|n| n.parse().ok()) {
+            Some(ms) => game.set_entry_delay(Duration::from_millis(ms)),
+            None => fail(game, "--are: needs a number of milliseconds"),
+        }
+    }
+
+    // --line-clear MS: hold completed lines for MS milliseconds before they
+    // collapse.
+    if let Some(i) = args.iter().position(|arg| arg == "--line-clear") {
+        match args.get(i + 1).and_then(|n| n.parse().ok()) {
+            Some(ms) => game.set_line_clear_delay(Duration::from_millis(ms)),
+            None => fail(game, "--line-clear: needs a number of milliseconds"),
+        }
+    }
+
+    // --soft-drop N: N points for each row a soft drop goes down.
+    if let Some(i) = args.iter().position(|arg| arg == "--soft-drop") {
+        match args.get(i + 1).and_then(|n| n.parse().ok()) {
+            Some(points) => game.set_soft_drop_points(points),
+            None => fail(game, "--soft-drop: needs a number of points"),
+        }
+    }
+
+    // --scale N: big cells, 2 for every cell at 4x2, 1 for the usual, auto
+    // for 2 when there's room. Beats the config file.
+    if let Some(i) = args.iter().position(|arg| arg == "--scale") {
+        let name = args.get(i + 1).map_or("", |name| name.as_str());
+        match tetris::config::scale_by_name(name) {
+            Ok(scale) => {
+                if let Err(e) = game.set_scale(scale) {
+                    fail(game, format!("--scale: {}", e));
+                }
+            }
+            Err(e) => fail(game, format!("--scale: {}", e)),
+        }
+    }
+
+    // --level N: start on level N, 1 to 20.
+    if let Some(i) = args.iter().position(|arg| arg == "--level") {
+        let level = args.get(i + 1).and_then(|n| n.parse().ok()).unwrap_or(0);
+        if let Err(e) = game.set_start_level(level) {
+            fail(game, format!("--level: {}", e));
+        }
+    }
+
+    // --preview N: show the next N pieces, 1 to 5.
+    if let Some(i) = args.iter().position(|arg| arg == "--preview") {
+        let count = args.get(i + 1).and_then(|n| n.parse().ok()).unwrap_or(0);
+        if let Err(e) = game.set_preview_count(count) {
+            fail(game, format!("--preview: {}", e));
+        }
+    }
+
     game.run();
+
+    // Drop the game first so the terminal is back to normal before printing.
+    let summary = game.summary();
+    drop(game);
+
+    // --no-summary: leave the terminal exactly as it was.
+    if !args.iter().any(|arg| arg == "--no-summary") {
+        println!("{}", summary);
+    }
+}
+
+/// Puts the terminal back, or the message is lost, then exits with `msg`.
+fn fail(game: Game, msg: impl Display) -> ! {
+    drop(game);
+    eprintln!("{}", msg);
+    process::exit(2);
 }
@@ -0,0 +1,108 @@
+// Screen reader mode.
+// With --screen-reader the board isn't drawn at all. What happens is said
+// instead, a plain line at a time, no cursor moves and no colors, for a
+// screen reader to read out:
+//
+//     T, column 4
+//     left, column 3
+//     T locked
+//     Double! 2 lines, score 200
+//
+// Gravity says nothing. Moves and rotations are said once things settle,
+// the last one only, so holding a key down doesn't bury everything else.
+use std::fmt;
+use std::time::Duration;
+
+// How long moves have to stop for before the last one is said.
+const SETTLE: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+pub(crate) struct Narrator {
+    // Off, nothing is said, or even formatted.
+    on: bool,
+    // Ready to go out.
+    lines: Vec<String>,
+    // The latest move, and how long since it.
+    pending: Option<String>,
+    since: Duration,
+}
+
+impl Narrator {
+    pub fn on() -> Self {
+        Self {
+            on: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    // Something that happened, out on the next frame. A move waiting goes
+    // first, it happened first.
+    pub fn say(&mut self, text: fmt::Arguments) {
+        if self.on {
+            self.lines.extend(self.pending.take());
+            self.lines.push(text.to_string());
+        }
+    }
+
+    // Where the piece is now, in place of wherever it was a moment ago.
+    pub fn moved(&mut self, text: fmt::Arguments) {
+        if self.on {
+            self.pending = Some(text.to_string());
+            self.since = Duration::ZERO;
+        }
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
+        self.since += dt;
+        if self.since >= SETTLE {
+            self.lines.extend(self.pending.take());
+        }
+    }
+
+    // Everything said since the last time.
+    pub fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_wait_until_they_stop() {
+        let mut narrator = Narrator::on();
+        let tick = Duration::from_millis(50);
+        for column in 1..=5 {
+            narrator.moved(format_args!("right, column {}", column));
+            narrator.tick(tick);
+        }
+        assert!(narrator.take().is_empty());
+
+        narrator.tick(SETTLE);
+        assert_eq!(narrator.take(), ["right, column 5"]);
+        narrator.tick(SETTLE);
+        assert!(narrator.take().is_empty());
+    }
+
+    #[test]
+    fn events_go_out_at_once_after_the_move() {
+        let mut narrator = Narrator::on();
+        narrator.moved(format_args!("left, column 3"));
+        narrator.say(format_args!("T locked"));
+        assert_eq!(narrator.take(), ["left, column 3", "T locked"]);
+    }
+
+    #[test]
+    fn off_says_nothing() {
+        let mut narrator = Narrator::default();
+        narrator.moved(format_args!("left, column 3"));
+        narrator.say(format_args!("T locked"));
+        narrator.tick(SETTLE);
+        assert!(narrator.take().is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+// Randomizers.
+// Which piece comes next. Pure random is the old way, any piece any time.
+// The 7-bag deals all seven in a shuffled order before starting over. The
+// TGM one remembers the last four pieces and rerolls a few times to dodge
+// them.
+// Ref: https://tetris.wiki/TGM_randomizer
+use std::collections::VecDeque;
+
+use rand::prelude::*;
+
+use crate::{Error, Shape};
+
+// Rerolls the TGM randomizer gets before it settles for a repeat.
+const TGM_ROLLS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Randomizer {
+    PureRandom,
+    #[default]
+    SevenBag,
+    HistoryTgm,
+}
+
+impl Randomizer {
+    pub const NAMES: [&'static str; 3] = ["random", "bag", "tgm"];
+
+    pub fn by_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "random" => Ok(Randomizer::PureRandom),
+            "bag" => Ok(Randomizer::SevenBag),
+            "tgm" => Ok(Randomizer::HistoryTgm),
+            _ => Err(Error::UnknownRandomizer(name.to_string())),
+        }
+    }
+}
+
+// Deals shapes with a randomizer. Same kind and seed, same pieces.
+pub struct Dealer {
+    kind: Randomizer,
+    rng: StdRng,
+    // 7-bag: what's left of the current bag.
+    bag: Vec<Shape>,
+    // TGM: the last four pieces, oldest first. Starts full of S and Z so the
+    // first piece is hardly ever one of those.
+    history: VecDeque<Shape>,
+}
+
+impl Dealer {
+    pub fn new(kind: Randomizer, seed: u64) -> Self {
+        Self {
+            kind,
+            rng: StdRng::seed_from_u64(seed),
+            bag: Vec::new(),
+            history: VecDeque::from([Shape::Z, Shape::Z, Shape::S, Shape::S]),
+        }
+    }
+
+    pub fn next_shape(&mut self) -> Shape {
+        match self.kind {
+            Randomizer::PureRandom => self.roll(),
+            Randomizer::SevenBag => {
+                if self.bag.is_empty() {
+                    self.bag = Shape::ALL.to_vec();
+                    self.bag.shuffle(&mut self.rng);
+                }
+                self.bag.pop().unwrap()
+            }
+            Randomizer::HistoryTgm => {
+                let mut shape = self.roll();
+                for _ in 1..TGM_ROLLS {
+                    if !self.history.contains(&shape) {
+                        break;
+                    }
+                    shape = self.roll();
+                }
+
+                self.history.pop_front();
+                self.history.push_back(shape);
+                shape
+            }
+        }
+    }
+
+    fn roll(&mut self) -> Shape {
+        *Shape::ALL.choose(&mut self.rng).unwrap()
+    }
+}
@@ -0,0 +1,395 @@
+// Renderer.
+// What the game draws through. The game works out where everything goes,
+// what it says and how it should look, the renderer gets it onto a screen.
+// How it looks is a Style, plain colors and bold and such, so a renderer
+// that isn't a terminal has nothing to pick apart. TermionRenderer is the
+// terminal, and the only thing that knows about escape codes.
+//
+// A frame is begin_frame(), then any number of draws, then end_frame().
+use std::io::{self, Write};
+
+use termion::{clear, color, cursor, style};
+
+use crate::asciicast::Asciicast;
+use crate::glyphs;
+use crate::terminal::TerminalGuard;
+use crate::theme::{nearest_256, nearest_basic, Color, ColorDepth};
+
+// How text is drawn. No colors is the terminal's own, and the default is
+// plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub faint: bool,
+    pub invert: bool,
+}
+
+impl Style {
+    // Text in `color`.
+    pub fn color(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            ..Self::default()
+        }
+    }
+
+    pub fn background(self, color: Color) -> Self {
+        Self {
+            bg: Some(color),
+            ..self
+        }
+    }
+
+    pub fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    pub fn faint(self) -> Self {
+        Self {
+            faint: true,
+            ..self
+        }
+    }
+
+    pub fn invert(self) -> Self {
+        Self {
+            invert: true,
+            ..self
+        }
+    }
+}
+
+// Some text and how it looks, e.g. a board cell: its glyph and colors.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Styled {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Styled {
+    pub fn new(text: impl Into<String>, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+pub trait Renderer {
+    fn begin_frame(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // The frame is done, out it goes.
+    fn end_frame(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    // Anything held back goes out now.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Wipe the whole screen.
+    fn clear(&mut self) -> io::Result<()>;
+
+    fn hide_cursor(&mut self) -> io::Result<()>;
+
+    // Park the cursor at screen position (x, y), 1-based.
+    fn move_to(&mut self, x: u16, y: u16) -> io::Result<()>;
+
+    // `text` in `style` from (x, y) on.
+    fn draw_text(&mut self, x: u16, y: u16, text: &str, style: Style) -> io::Result<()>;
+
+    // One row of a board cell from (x, y) on. The text is as wide as the
+    // cell.
+    fn draw_cell(&mut self, x: u16, y: u16, cell: &Styled) -> io::Result<()> {
+        self.draw_text(x, y, &cell.text, cell.style)
+    }
+
+    // Cells side by side from (x, y) on, e.g. a row of the board.
+    fn draw_row(&mut self, x: u16, y: u16, cells: &[Styled]) -> io::Result<()> {
+        let mut x = x;
+        for cell in cells {
+            self.draw_cell(x, y, cell)?;
+            x += width(&cell.text);
+        }
+        Ok(())
+    }
+
+    // A line of its own, for screen reader mode where nothing is drawn.
+    fn write_line(&mut self, line: &str) -> io::Result<()>;
+
+    // How many colors there are to draw in. Anything else gets the nearest.
+    fn set_color_depth(&mut self, _depth: ColorDepth) {}
+
+    // Window or tab title, if there is such a thing.
+    fn set_title(&mut self, _title: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Bytes sent so far, for the debug overlay.
+    fn written(&self) -> u64 {
+        0
+    }
+
+    // Send what's drawn from now on to `cast` too. Renderers that don't draw
+    // in escape codes have nothing to give it.
+    fn record(&mut self, _cast: Asciicast) {}
+}
+
+// Columns `text` takes up.
+fn width(text: &str) -> u16 {
+    glyphs::display_width(text).unwrap_or(text.chars().count()) as u16
+}
+
+// The terminal, or anything standing in for one, by escape codes.
+pub struct TermionRenderer {
+    out: TerminalGuard,
+    depth: ColorDepth,
+}
+
+impl TermionRenderer {
+    // The real terminal: raw mode on the alternate screen.
+    pub fn stdout() -> Self {
+        Self {
+            out: TerminalGuard::stdout(),
+            depth: ColorDepth::default(),
+        }
+    }
+
+    // Escapes and all to `out`.
+    pub fn new(out: Box<dyn Write>) -> Self {
+        Self {
+            out: TerminalGuard::new(out),
+            depth: ColorDepth::default(),
+        }
+    }
+
+    // `text` at the cursor, in `style` and back to plain after. Plain text
+    // goes out as is.
+    fn styled(&mut self, text: &str, style: Style) -> io::Result<()> {
+        if style == Style::default() {
+            return self.out.write_all(text.as_bytes());
+        }
+        let mut escapes = String::new();
+        if style.bold {
+            escapes += style::Bold.as_ref();
+        }
+        if style.faint {
+            escapes += style::Faint.as_ref();
+        }
+        if style.invert {
+            escapes += style::Invert.as_ref();
+        }
+        if let Some(color) = style.fg {
+            escapes += &fg(color, self.depth);
+        }
+        if let Some(color) = style.bg {
+            escapes += &bg(color, self.depth);
+        }
+        write!(self.out, "{}{}{}", escapes, text, style::Reset)
+    }
+}
+
+impl Renderer for TermionRenderer {
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        write!(self.out, "{}", clear::All)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        write!(self.out, "{}", cursor::Hide)
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> io::Result<()> {
+        write!(self.out, "{}", cursor::Goto(x, y))
+    }
+
+    fn draw_text(&mut self, x: u16, y: u16, text: &str, style: Style) -> io::Result<()> {
+        self.move_to(x, y)?;
+        self.styled(text, style)
+    }
+
+    // The cursor carries on across the row, no need to move it per cell.
+    fn draw_row(&mut self, x: u16, y: u16, cells: &[Styled]) -> io::Result<()> {
+        self.move_to(x, y)?;
+        for cell in cells {
+            self.styled(&cell.text, cell.style)?;
+        }
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        write!(self.out, "{}\r\n", line)
+    }
+
+    fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.depth = depth;
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.out.set_title(title)
+    }
+
+    fn written(&self) -> u64 {
+        self.out.written()
+    }
+
+    fn record(&mut self, cast: Asciicast) {
+        self.out.record(cast);
+    }
+}
+
+// The escape code to draw in `color`, on a terminal that can do `depth`.
+fn fg(color: Color, depth: ColorDepth) -> String {
+    match (color, depth) {
+        (Color::Default, _) => color::Fg(color::Reset).to_string(),
+        (Color::Ansi(n), ColorDepth::Basic) => basic(n, 30),
+        (Color::Ansi(n), _) => color::Fg(color::AnsiValue(n)).to_string(),
+        (Color::Rgb(r, g, b), ColorDepth::Basic) => basic(nearest_basic(r, g, b), 30),
+        (Color::Rgb(r, g, b), ColorDepth::Palette256) => {
+            color::Fg(color::AnsiValue(nearest_256(r, g, b))).to_string()
+        }
+        (Color::Rgb(r, g, b), ColorDepth::TrueColor) => color::Fg(color::Rgb(r, g, b)).to_string(),
+    }
+}
+
+// Same again, for the background.
+fn bg(color: Color, depth: ColorDepth) -> String {
+    match (color, depth) {
+        (Color::Default, _) => color::Bg(color::Reset).to_string(),
+        (Color::Ansi(n), ColorDepth::Basic) => basic(n, 40),
+        (Color::Ansi(n), _) => color::Bg(color::AnsiValue(n)).to_string(),
+        (Color::Rgb(r, g, b), ColorDepth::Basic) => basic(nearest_basic(r, g, b), 40),
+        (Color::Rgb(r, g, b), ColorDepth::Palette256) => {
+            color::Bg(color::AnsiValue(nearest_256(r, g, b))).to_string()
+        }
+        (Color::Rgb(r, g, b), ColorDepth::TrueColor) => color::Bg(color::Rgb(r, g, b)).to_string(),
+    }
+}
+
+// The old short escapes for the 16, 30-37 and 90-97 for foreground, `base`
+// 40 for background.
+fn basic(n: u8, base: u8) -> String {
+    match n {
+        0..=7 => format!("\x1b[{}m", base + n),
+        _ => format!("\x1b[{}m", base + 60 + n - 8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::testing::Capture;
+    use crate::Game;
+
+    // Frames finished, and where each cell went.
+    type Drawn = (usize, Vec<(u16, u16)>);
+
+    // Keeps what's in Drawn, drops the rest.
+    #[derive(Clone, Default)]
+    struct Recorder(Rc<RefCell<Drawn>>);
+
+    impl Renderer for Recorder {
+        fn end_frame(&mut self) -> io::Result<()> {
+            self.0.borrow_mut().0 += 1;
+            Ok(())
+        }
+
+        fn clear(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn hide_cursor(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn move_to(&mut self, _x: u16, _y: u16) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn draw_text(&mut self, _x: u16, _y: u16, _text: &str, _style: Style) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn draw_cell(&mut self, x: u16, y: u16, _cell: &Styled) -> io::Result<()> {
+            self.0.borrow_mut().1.push((x, y));
+            Ok(())
+        }
+
+        fn write_line(&mut self, _line: &str) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_game_draws_through_any_renderer() {
+        let recorder = Recorder::default();
+        let mut game = Game::with_renderer(10, 20, Box::new(recorder.clone())).unwrap();
+        // Spawn, then a few rows down into sight.
+        for _ in 0..6 {
+            game.frame(Duration::from_secs(1));
+        }
+
+        let (frames, cells) = recorder.0.take();
+        assert_eq!(frames, 6);
+        // The piece goes last, over its ghost.
+        let piece: Vec<(u16, u16)> = game
+            .falling
+            .as_ref()
+            .unwrap()
+            .cells()
+            .iter()
+            .map(|block| game.cell_pos(block.x, block.y).unwrap())
+            .collect();
+        assert_eq!(cells[cells.len() - 4..], piece);
+    }
+
+    #[test]
+    fn styles_come_out_as_escapes() {
+        let out = Capture::default();
+        let mut renderer = TermionRenderer::new(Box::new(out.clone()));
+        renderer.draw_text(3, 2, "hi", Style::default()).unwrap();
+        let cyan = Style::color(Color::CYAN).bold();
+        renderer.draw_text(1, 1, "Score", cyan).unwrap();
+        drop(renderer);
+
+        let drawn = String::from_utf8(out.0.take()).unwrap();
+        // Plain text as is, the rest put back to plain after.
+        assert!(drawn.contains("\x1b[2;3Hhi\x1b[1;1H"), "{:?}", drawn);
+        assert!(
+            drawn.contains("\x1b[1m\x1b[38;5;6mScore\x1b[m"),
+            "{:?}",
+            drawn
+        );
+    }
+
+    #[test]
+    fn colors_go_out_as_the_terminal_can_show_them() {
+        // No orange in the 16, the L goes yellow.
+        let orange = Color::Rgb(255, 165, 0);
+        assert_eq!(fg(orange, ColorDepth::Basic), "\x1b[33m");
+        assert_eq!(fg(Color::Rgb(250, 10, 10), ColorDepth::Basic), "\x1b[91m");
+        assert_eq!(fg(Color::CYAN, ColorDepth::Basic), "\x1b[36m");
+
+        // The 256 palette has it, near enough.
+        assert_eq!(fg(orange, ColorDepth::Palette256), "\x1b[38;5;214m");
+        assert_eq!(fg(orange, ColorDepth::TrueColor), "\x1b[38;2;255;165;0m");
+
+        // Named ones go out as they always have on anything better.
+        assert_eq!(fg(Color::CYAN, ColorDepth::TrueColor), "\x1b[38;5;6m");
+        assert_eq!(fg(Color::Default, ColorDepth::Basic), "\x1b[39m");
+        assert_eq!(bg(Color::LIGHT_RED, ColorDepth::Basic), "\x1b[101m");
+        assert_eq!(bg(Color::Ansi(0), ColorDepth::TrueColor), "\x1b[48;5;0m");
+    }
+}
@@ -0,0 +1,365 @@
+// Board snapshots.
+// With --snapshot, the board as it was when the game ended is saved as a PNG:
+// a square per cell in the theme's colors and the score, lines and time
+// under it. Nothing here knows about terminals, the board goes straight to
+// pixels and the pixels to a PNG.
+//
+// The PNG is written by hand, uncompressed (stored deflate blocks), which
+// keeps it to std. A 10x20 board comes to about 100K. All of it is behind
+// the snapshot feature, on by default.
+// Ref: https://www.w3.org/TR/png/
+use std::io::{self, Write};
+
+use crate::theme::Theme;
+use crate::Cell;
+
+// Pixels a side for a cell, a pixel of which is the gap around it.
+const CELL: usize = 12;
+// Footer text: pixels per font pixel, and the room around each line.
+const TEXT_SCALE: usize = 2;
+const TEXT_PAD: usize = 4;
+const BLACK: Rgb = (0, 0, 0);
+const WHITE: Rgb = (229, 229, 229);
+
+type Rgb = (u8, u8, u8);
+
+// 3x5 pixel letters, a row each, top first, the highest of the 3 bits on
+// the left.
+// Only what the footer says; anything else is a space.
+const FONT: [(char, [u8; 5]); 23] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('C', [0b111, 0b100, 0b100, 0b100, 0b111]),
+    ('E', [0b111, 0b100, 0b111, 0b100, 0b111]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b111, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+];
+
+pub(crate) struct Image {
+    pub width: usize,
+    pub height: usize,
+    // Row by row, top first.
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize, color: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; width * height],
+        }
+    }
+
+    #[cfg(test)]
+    fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    // A `w` by `h` box from (x, y), cut off at the edges.
+    fn fill(&mut self, x: usize, y: usize, w: usize, h: usize, color: Rgb) {
+        for y in y..(y + h).min(self.height) {
+            for x in x..(x + w).min(self.width) {
+                self.pixels[y * self.width + x] = color;
+            }
+        }
+    }
+
+    // `text` from (x, y), as far as it fits.
+    fn text(&mut self, x: usize, y: usize, text: &str, color: Rgb) {
+        for (i, c) in text.chars().enumerate() {
+            let rows = FONT
+                .iter()
+                .find(|&&(letter, _)| letter == c)
+                .map_or([0; 5], |&(_, rows)| rows);
+            let left = x + i * 4 * TEXT_SCALE;
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        self.fill(
+                            left + col * TEXT_SCALE,
+                            y + row * TEXT_SCALE,
+                            TEXT_SCALE,
+                            TEXT_SCALE,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn write_png(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits a channel, RGB, the usual compression, filter and no
+        // interlacing.
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        chunk(out, b"IHDR", &header)?;
+
+        // Each row starts with its filter, none.
+        let mut raw = Vec::with_capacity(self.height * (1 + self.width * 3));
+        for row in self.pixels.chunks(self.width) {
+            raw.push(0);
+            for &(r, g, b) in row {
+                raw.extend_from_slice(&[r, g, b]);
+            }
+        }
+        chunk(out, b"IDAT", &zlib_stored(&raw))?;
+        chunk(out, b"IEND", &[])
+    }
+}
+
+// The visible rows of the board, top first, in `theme`'s colors, with a
+// line of `footer` text per entry under it.
+pub(crate) fn board(board: &[&[Cell]], theme: &Theme, footer: &[String]) -> Image {
+    let background = theme.background().map_or(BLACK, |color| color.rgb(BLACK));
+    let width = board.first().map_or(0, |row| row.len()) * CELL;
+    let line = 5 * TEXT_SCALE + TEXT_PAD;
+    let height = board.len() * CELL + TEXT_PAD + footer.len() * line;
+    let mut image = Image::new(width, height, background);
+
+    for (y, row) in board.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            let color = match cell {
+                Cell::Empty => continue,
+                Cell::Filled(shape) => theme.piece(shape),
+                Cell::Garbage => theme.garbage(),
+            };
+            image.fill(
+                x * CELL + 1,
+                y * CELL + 1,
+                CELL - 2,
+                CELL - 2,
+                color.rgb(WHITE),
+            );
+        }
+    }
+
+    let text = theme.text().rgb(WHITE);
+    for (i, words) in footer.iter().enumerate() {
+        let y = board.len() * CELL + TEXT_PAD + i * line;
+        image.text(TEXT_PAD, y, words, text);
+    }
+    image
+}
+
+// One PNG chunk: length, type, data and the CRC of the last two.
+fn chunk(out: &mut dyn Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(&[&kind[..], data].concat());
+    out.write_all(&crc.to_be_bytes())
+}
+
+// `data` as a zlib stream of stored (not compressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 65535;
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = match data.is_empty() {
+        true => vec![&[]],
+        false => data.chunks(BLOCK).collect(),
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        out.push((i == blocks.len() - 1) as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Color;
+    use crate::{Game, Shape};
+
+    // Read back what write_png wrote: each chunk checked, the stored blocks
+    // joined up and the filter bytes taken off. Width, height and the
+    // pixels, row by row.
+    fn decode(png: &[u8]) -> (usize, usize, Vec<Rgb>) {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let be = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+        let (mut at, mut size, mut zlib) = (8, (0, 0), Vec::new());
+        while at < png.len() {
+            let len = be(at) as usize;
+            let (kind, data) = (&png[at + 4..at + 8], &png[at + 8..at + 8 + len]);
+            assert_eq!(be(at + 8 + len), crc32(&png[at + 4..at + 8 + len]));
+            match kind {
+                b"IHDR" => size = (be(at + 8) as usize, be(at + 12) as usize),
+                b"IDAT" => zlib.extend_from_slice(data),
+                _ => {}
+            }
+            at += 12 + len;
+        }
+
+        let mut raw = Vec::new();
+        let mut at = 2;
+        loop {
+            let last = zlib[at] & 1 == 1;
+            assert_eq!(zlib[at] >> 1, 0, "not a stored block");
+            let len = u16::from_le_bytes([zlib[at + 1], zlib[at + 2]]) as usize;
+            raw.extend_from_slice(&zlib[at + 5..at + 5 + len]);
+            at += 5 + len;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(zlib[at..], adler32(&raw).to_be_bytes());
+
+        let (width, height) = size;
+        let mut pixels = Vec::new();
+        for row in raw.chunks(1 + 3 * width) {
+            assert_eq!(row[0], 0, "filtered row");
+            pixels.extend(row[1..].chunks(3).map(|p| (p[0], p[1], p[2])));
+        }
+        assert_eq!(pixels.len(), width * height);
+        (width, height, pixels)
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn cells_are_squares_in_the_theme_colors() {
+        let theme = Theme::default();
+        let cells: [&[Cell]; 2] = [
+            &[Cell::Empty, Cell::Empty, Cell::Empty],
+            &[Cell::Filled(Shape::T), Cell::Empty, Cell::Garbage],
+        ];
+        let footer = ["SCORE 100".to_string()];
+        let image = board(&cells, &theme, &footer);
+
+        assert_eq!(image.width, 3 * CELL);
+        assert_eq!(
+            image.height,
+            2 * CELL + TEXT_PAD + 5 * TEXT_SCALE + TEXT_PAD
+        );
+
+        let middle = |x: usize, y: usize| image.pixel(x * CELL + CELL / 2, y * CELL + CELL / 2);
+        assert_eq!(middle(0, 1), theme.piece(Shape::T).rgb(WHITE));
+        assert_eq!(middle(2, 1), theme.garbage().rgb(WHITE));
+        assert_eq!(middle(1, 1), BLACK);
+        // The gap between cells.
+        assert_eq!(image.pixel(0, CELL), BLACK);
+
+        // The S's top left corner.
+        let text = theme.text().rgb(WHITE);
+        assert_eq!(image.pixel(TEXT_PAD, 2 * CELL + TEXT_PAD), text);
+    }
+
+    #[test]
+    fn png_has_the_size_in_its_header() {
+        let image = Image::new(3, 2, (255, 0, 0));
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..20], 3u32.to_be_bytes());
+        assert_eq!(png[20..24], 2u32.to_be_bytes());
+        assert!(png.ends_with(&[b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+
+        // The pixels are in there as is: filter byte, then red.
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        assert_eq!(png[idat + 4..idat + 6], [0x78, 0x01]);
+        assert_eq!(png[idat + 11..idat + 15], [0, 255, 0, 0]);
+    }
+
+    #[test]
+    fn saved_board_has_every_cell_in_its_color() {
+        // A piece of each shape, garbage and a gap, in Shape::ALL order.
+        let mut game = Game::from_matrix(&["....", "....", "....", "IOTJLSZG."]).unwrap();
+        let path = std::env::temp_dir().join(format!("tetris-cells-{}.png", std::process::id()));
+        game.set_snapshot(&path);
+        game.save_snapshot().unwrap();
+        let png = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (width, height, pixels) = decode(&png);
+        assert_eq!(
+            (width, height),
+            (
+                9 * CELL,
+                4 * CELL + TEXT_PAD + 3 * (5 * TEXT_SCALE + TEXT_PAD)
+            )
+        );
+        let middle =
+            |x: usize, y: usize| pixels[(y * CELL + CELL / 2) * width + x * CELL + CELL / 2];
+
+        // The classic theme, as xterm draws the 16, and L's orange.
+        let want = [
+            (0, 205, 205),
+            (205, 205, 0),
+            (205, 0, 205),
+            (0, 0, 238),
+            (255, 165, 0),
+            (0, 205, 0),
+            (205, 0, 0),
+            (127, 127, 127),
+        ];
+        for (x, &rgb) in want.iter().enumerate() {
+            assert_eq!(middle(x, 3), rgb, "cell {}", x);
+        }
+        assert_eq!(middle(8, 3), BLACK);
+        assert_eq!(middle(0, 0), BLACK);
+    }
+
+    #[test]
+    fn colors_as_rgb() {
+        assert_eq!(Color::Ansi(1).rgb(WHITE), (205, 0, 0));
+        assert_eq!(Color::Ansi(214).rgb(WHITE), (255, 175, 0));
+        assert_eq!(Color::Ansi(244).rgb(WHITE), (128, 128, 128));
+        assert_eq!(Color::Default.rgb(WHITE), WHITE);
+    }
+}
@@ -0,0 +1,150 @@
+// Game stats as CSV.
+// With --stats-csv, every finished game adds a row to a CSV file, for
+// keeping track of how you're doing in a spreadsheet. A new (or empty) file
+// gets the header first:
+//
+//     timestamp,mode,seed,score,lines,level,pieces,duration
+//     2026-10-15T18:04:51Z,Marathon,1234,8400,36,4,97,95.002
+//
+// The timestamp is when the game ended, UTC. The duration is play time in
+// seconds, pauses left out.
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::highscore;
+
+const HEADER: &str = "timestamp,mode,seed,score,lines,level,pieces,duration";
+
+// One finished game.
+pub struct GameRecord {
+    // Seconds since 1970, UTC.
+    pub ended: u64,
+    pub mode: &'static str,
+    pub seed: u64,
+    pub score: i64,
+    pub lines: usize,
+    pub level: usize,
+    pub pieces: u64,
+    pub duration: Duration,
+}
+
+pub struct StatsCsv {
+    path: PathBuf,
+}
+
+impl StatsCsv {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    // Add `game` to the end of the file, header first if there's nothing in
+    // it yet. The row goes in with a single write, so a failure part way
+    // leaves no half rows.
+    pub fn append(&self, game: &GameRecord) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut text = String::new();
+        if file.metadata()?.len() == 0 {
+            text += HEADER;
+            text += "\n";
+        }
+        text += &row(game);
+        file.write_all(text.as_bytes())
+    }
+}
+
+// `game` as a line of the file.
+fn row(game: &GameRecord) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{:.3}\n",
+        timestamp(game.ended),
+        game.mode,
+        game.seed,
+        game.score,
+        game.lines,
+        game.level,
+        game.pieces,
+        game.duration.as_secs_f64()
+    )
+}
+
+// Seconds since 1970 as YYYY-MM-DDTHH:MM:SSZ.
+fn timestamp(secs: u64) -> String {
+    let (year, month, day) = highscore::civil_from_days((secs / 86400) as i64);
+    let time = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+// Now, for GameRecord::ended.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+
+    fn record(score: i64) -> GameRecord {
+        GameRecord {
+            ended: 1_792_087_491,
+            mode: "Marathon",
+            seed: 1234,
+            score,
+            lines: 36,
+            level: 4,
+            pieces: 97,
+            duration: Duration::from_millis(95_002),
+        }
+    }
+
+    #[test]
+    fn a_row_per_game() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            row(&record(8400)),
+            "2026-10-15T18:04:51Z,Marathon,1234,8400,36,4,97,95.002\n"
+        );
+    }
+
+    #[test]
+    fn header_only_once() {
+        let path = env::temp_dir().join(format!("tetris-stats-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let csv = StatsCsv::new(&path);
+        csv.append(&record(100)).unwrap();
+        csv.append(&record(200)).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], HEADER);
+        assert!(lines[1].contains(",100,"));
+        assert!(lines[2].contains(",200,"));
+    }
+
+    #[test]
+    fn unwritable_is_an_error_not_a_panic() {
+        // A directory can't be appended to.
+        let csv = StatsCsv::new(env::temp_dir());
+        assert!(csv.append(&record(100)).is_err());
+    }
+}
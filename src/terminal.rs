@@ -0,0 +1,170 @@
+// Terminal guard.
+// Owns whatever the game draws to, so on any way out (quit, game over, panic)
+// the cursor comes back and, for the real terminal, raw mode and the
+// alternate screen go away with it.
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::io::{self, BufWriter, Write};
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread;
+
+use termion::cursor;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+
+use crate::asciicast::{Asciicast, Tee};
+
+// Room for a full frame. A redraw of the whole screen with colors comes in
+// well under this.
+const FRAME_BUFFER: usize = 64 * 1024;
+
+// Number of live guards. The panic hook only steps in while there is one.
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+// A panic that happened while a guard was alive. Printing it right away would
+// land on the alternate screen in raw mode (i.e. gone, or a staircase), so the
+// hook parks it here and the guard prints it once the terminal is restored.
+static PANIC_MESSAGE: Mutex<Option<String>> = Mutex::new(None);
+
+pub struct TerminalGuard {
+    out: Option<Box<dyn Write>>,
+    // Bytes written so far, for the debug overlay.
+    written: u64,
+    // The title from before ours is on the terminal's title stack, to come
+    // back on the way out.
+    title_pushed: bool,
+}
+
+impl TerminalGuard {
+    // The real terminal: raw mode on the alternate screen.
+    pub fn stdout() -> Self {
+        Self::new(raw_stdout())
+    }
+
+    // Guard any other output.
+    pub fn new(out: Box<dyn Write>) -> Self {
+        install_panic_hook();
+        GUARDS.fetch_add(1, Ordering::SeqCst);
+
+        Self {
+            out: Some(out),
+            written: 0,
+            title_pushed: false,
+        }
+    }
+
+    // Window/tab title. The first one saves whatever was there before, on
+    // terminals with a title stack (xterm and most others, the rest ignore
+    // it).
+    pub fn set_title(&mut self, title: &str) -> io::Result<()> {
+        if !self.title_pushed {
+            self.title_pushed = true;
+            write!(self, "\x1b[22;0t")?;
+        }
+        write!(self, "\x1b]0;{}\x07", title)
+    }
+
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    // Everything from here on goes to `cast` as well.
+    pub fn record(&mut self, cast: Asciicast) {
+        if let Some(out) = self.out.take() {
+            self.out = Some(Box::new(Tee::new(out, cast)));
+        }
+    }
+
+    fn out(&mut self) -> &mut Box<dyn Write> {
+        // Only None mid-drop.
+        self.out.as_mut().unwrap()
+    }
+}
+
+impl Write for TerminalGuard {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.out().write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out().flush()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Some(mut out) = self.out.take() {
+            // Errors don't matter here, we are on the way out.
+            let _ = write!(out, "{}", cursor::Show);
+            if self.title_pushed {
+                let _ = write!(out, "\x1b[23;0t");
+            }
+            let _ = out.flush();
+
+            // Dropping the output is what leaves the alternate screen and
+            // raw mode. That last write can still be sitting in stdout's
+            // buffer though, and anything sent to stderr after would land
+            // before it, on the alternate screen.
+            drop(out);
+            let _ = io::stdout().flush();
+        }
+        let last = GUARDS.fetch_sub(1, Ordering::SeqCst) == 1;
+
+        // Terminal is sane again. Now the panic message is actually readable.
+        // Guards sharing one terminal (versus) only get it back with the
+        // last one.
+        if last && thread::panicking() {
+            if let Some(message) = PANIC_MESSAGE
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+            {
+                eprintln!("{}", message);
+            }
+        }
+    }
+}
+
+// Raw mode on the alternate screen, buffered so a whole frame goes out in one
+// write on flush rather than a syscall per write!.
+pub fn raw_stdout() -> Box<dyn Write> {
+    Box::new(BufWriter::with_capacity(
+        FRAME_BUFFER,
+        io::stdout()
+            .into_raw_mode()
+            .unwrap()
+            .into_alternate_screen()
+            .unwrap(),
+    ))
+}
+
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if GUARDS.load(Ordering::SeqCst) == 0 {
+                default_hook(info);
+                return;
+            }
+
+            let thread = thread::current();
+            let name = thread.name().unwrap_or("<unnamed>");
+            let backtrace = Backtrace::capture();
+            let message = match backtrace.status() {
+                BacktraceStatus::Captured => {
+                    format!("thread '{}' {}\nstack backtrace:\n{}", name, info, backtrace)
+                }
+                _ => format!(
+                    "thread '{}' {}\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace",
+                    name, info
+                ),
+            };
+            *PANIC_MESSAGE.lock().unwrap_or_else(|e| e.into_inner()) = Some(message);
+        }));
+    });
+}
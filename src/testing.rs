@@ -0,0 +1,210 @@
+// Test helpers.
+// Play a scripted game without a terminal and get back everything it drew,
+// so the layout can be pinned down in tests and golden files.
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+use termion::event::Key;
+
+use crate::input::ScriptedInput;
+use crate::{Cell, Error, Game, Shape, BUFFER_ROWS, FRAME_RATE};
+
+// Output that can still be read while the game owns it.
+#[derive(Clone, Default)]
+pub(crate) struct Capture(pub(crate) Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Game {
+    // A game with the board filled in from `rows`, top row first. `.` is an
+    // empty cell, `G` garbage, a shape letter is that shape and anything else
+    // is an I. render_to_string() gives the same back.
+    // Drawing goes nowhere.
+    pub fn from_matrix(rows: &[&str]) -> Result<Game, Error> {
+        let height = rows.len();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut game = Game::with_output(width, height, Box::new(io::sink()))?;
+
+        for (j, row) in rows.iter().enumerate() {
+            for (i, c) in row.chars().enumerate() {
+                if c == 'G' {
+                    game.board.set(i, j + BUFFER_ROWS, Cell::Garbage);
+                } else if c != '.' {
+                    let shape = Shape::ALL
+                        .into_iter()
+                        .find(|shape| shape.letter() == c)
+                        .unwrap_or(Shape::I);
+                    game.board.set(i, j + BUFFER_ROWS, Cell::Filled(shape));
+                }
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+// Clear the completed lines on `game`'s board right away, skipping the flash.
+// Returns how many went.
+pub fn clear_lines(game: &mut Game) -> usize {
+    let before = game.lines_cleared;
+    game.clear_completed_lines();
+    game.lines_cleared - before
+}
+
+// Run a `width` x `height` game for `steps` frames, pressing `keys` one per
+// frame (None is a frame with nothing pressed), and return everything drawn,
+// escapes and all. Pieces are still random, so only the parts that don't
+// depend on them are worth comparing.
+pub fn render(
+    width: usize,
+    height: usize,
+    keys: impl IntoIterator<Item = Option<Key>>,
+    steps: usize,
+) -> Result<String, Error> {
+    render_with(width, height, keys, steps, |_| {})
+}
+
+// Same as render(), with `setup` getting a go at the game first.
+pub fn render_with(
+    width: usize,
+    height: usize,
+    keys: impl IntoIterator<Item = Option<Key>>,
+    steps: usize,
+    setup: impl FnOnce(&mut Game),
+) -> Result<String, Error> {
+    let capture = Capture::default();
+    let mut game = Game::with_output(width, height, Box::new(capture.clone()))?;
+    game.set_input(Box::new(ScriptedInput::new(keys)));
+    // Small boards would fit big on 80x24, keep them the usual size unless
+    // `setup` says otherwise.
+    game.set_scale(Some(1))?;
+    setup(&mut game);
+
+    let frame = Duration::from_millis(1000 / FRAME_RATE as u64);
+    game.init_screen();
+    for _ in 0..steps {
+        game.frame(frame);
+    }
+    drop(game);
+
+    let bytes = capture.0.take();
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Drop the escape sequences, leaving only the text that was printed.
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters, then a final byte in @..~.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: runs to BEL or ESC \.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                }
+            }
+            // Anything else is ESC plus one char, e.g. the alternate screen
+            // keypad modes.
+            _ => {}
+        }
+    }
+
+    out
+}
+
+// What a `cols` x `rows` terminal would show after `out`, a line of text per
+// row. Knows enough for what the game draws: cursor moves, clearing the
+// screen or to the end of a line, and text. Everything else is dropped.
+// Every char is taken to be one column.
+pub fn screen(out: &str, cols: usize, rows: usize) -> Vec<String> {
+    let mut grid = vec![vec![' '; cols]; rows];
+    let (mut x, mut y) = (0, 0);
+    let mut chars = out.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut command = ' ';
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            command = c;
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    let n: Vec<usize> = params.split(';').map(|n| n.parse().unwrap_or(1)).collect();
+                    match command {
+                        'H' => {
+                            y = n[0].max(1) - 1;
+                            x = n.get(1).copied().unwrap_or(1).max(1) - 1;
+                        }
+                        'J' if params == "2" => grid = vec![vec![' '; cols]; rows],
+                        'K' if y < rows => grid[y][x.min(cols)..].fill(' '),
+                        _ => {}
+                    }
+                }
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' {
+                            chars.next_if_eq(&'\\');
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => x = 0,
+            '\n' => y += 1,
+            _ => {
+                if x < cols && y < rows {
+                    grid[y][x] = c;
+                }
+                x += 1;
+            }
+        }
+    }
+
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>().trim_end().to_string())
+        .collect()
+}
@@ -0,0 +1,475 @@
+// Color themes.
+// What color each piece is drawn in, plus the ghost, garbage, the border and
+// the HUD text. Pieces don't know their own color anymore, the board looks
+// it up in the theme when it draws.
+use std::env;
+
+use crate::glyphs::{self, Glyphs};
+use crate::renderer::Style;
+use crate::{Error, Shape};
+
+// A terminal color. One of the 16 named ANSI colors, a 24-bit RGB one, or
+// whatever the terminal's default is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Default,
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+// How many colors the terminal can do. Anything a theme asks for that the
+// terminal can't show gets swapped for the nearest one it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    // The 16 named ones, and only with the old short escapes.
+    Basic,
+    // xterm's 256: the 16, a 6x6x6 cube and a grey ramp.
+    Palette256,
+    #[default]
+    TrueColor,
+}
+
+impl ColorDepth {
+    pub const NAMES: [&'static str; 3] = ["basic", "256", "truecolor"];
+
+    pub fn by_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "basic" => Ok(ColorDepth::Basic),
+            "256" => Ok(ColorDepth::Palette256),
+            "truecolor" => Ok(ColorDepth::TrueColor),
+            _ => Err(Error::UnknownColorDepth(name.to_string())),
+        }
+    }
+
+    // Best guess from the environment. Terminals that do 24-bit mostly say
+    // so in $COLORTERM, and 256 color ones in $TERM.
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Palette256
+        } else {
+            ColorDepth::Basic
+        }
+    }
+}
+
+// What the 16 look like, xterm's take on them. Only used to find the nearest.
+const BASIC_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+// Steps on each axis of the 256 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// Behind every other column of empty cells with stripes on. Just off black,
+// enough to see and no more.
+const STRIPE: Color = Color::Rgb(30, 30, 30);
+
+// Names for Color::Ansi, by number.
+pub const COLOR_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "light_black",
+    "light_red",
+    "light_green",
+    "light_yellow",
+    "light_blue",
+    "light_magenta",
+    "light_cyan",
+    "light_white",
+];
+
+impl Color {
+    pub const RED: Color = Color::Ansi(1);
+    pub const GREEN: Color = Color::Ansi(2);
+    pub const YELLOW: Color = Color::Ansi(3);
+    pub const BLUE: Color = Color::Ansi(4);
+    pub const MAGENTA: Color = Color::Ansi(5);
+    pub const CYAN: Color = Color::Ansi(6);
+    pub const WHITE: Color = Color::Ansi(7);
+    pub const LIGHT_BLACK: Color = Color::Ansi(8);
+    pub const LIGHT_RED: Color = Color::Ansi(9);
+    pub const LIGHT_GREEN: Color = Color::Ansi(10);
+    pub const LIGHT_YELLOW: Color = Color::Ansi(11);
+    pub const LIGHT_BLUE: Color = Color::Ansi(12);
+    pub const LIGHT_MAGENTA: Color = Color::Ansi(13);
+    pub const LIGHT_CYAN: Color = Color::Ansi(14);
+    pub const LIGHT_WHITE: Color = Color::Ansi(15);
+
+    // "red", "light_blue", "default" or an RGB triple like "255, 165, 0".
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let s = s.trim();
+        if s == "default" {
+            return Ok(Color::Default);
+        }
+        if let Some(i) = COLOR_NAMES.iter().position(|&name| name == s) {
+            return Ok(Color::Ansi(i as u8));
+        }
+
+        let rgb: Vec<u8> = s
+            .split(',')
+            .map(|part| part.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| Error::UnknownColor(s.to_string()))?;
+        match rgb[..] {
+            [r, g, b] => Ok(Color::Rgb(r, g, b)),
+            _ => Err(Error::UnknownColor(s.to_string())),
+        }
+    }
+
+    // What it looks like, for drawing somewhere that isn't a terminal. The
+    // 16 as xterm has them, the rest of the 256 off the cube and the greys.
+    // Default is whatever the terminal's would have been, `default`.
+    pub fn rgb(self, default: (u8, u8, u8)) -> (u8, u8, u8) {
+        match self {
+            Color::Default => default,
+            Color::Ansi(n @ 0..=15) => BASIC_RGB[n as usize],
+            Color::Ansi(n @ 16..=231) => {
+                let n = (n - 16) as usize;
+                (
+                    CUBE_LEVELS[n / 36],
+                    CUBE_LEVELS[n / 6 % 6],
+                    CUBE_LEVELS[n % 6],
+                )
+            }
+            Color::Ansi(n) => {
+                let grey = 8 + (n - 232) * 10;
+                (grey, grey, grey)
+            }
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+// Closest of the 16, by number.
+pub fn nearest_basic(r: u8, g: u8, b: u8) -> u8 {
+    (0..16)
+        .min_by_key(|&i| distance((r, g, b), BASIC_RGB[i]))
+        .unwrap() as u8
+}
+
+// Closest of the 256 palette's cube and greys, by number.
+pub fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap()
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    // Greys run 8, 18, .. 238.
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let step = ((average.saturating_sub(3)) / 10).min(23);
+    let grey = (8 + step * 10) as u8;
+
+    if distance((r, g, b), (grey, grey, grey)) < distance((r, g, b), cube) {
+        232 + step as u8
+    } else {
+        16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    // Indexed by shape.
+    pieces: [Color; 7],
+    // None draws the ghost in its piece's color.
+    ghost: Option<Color>,
+    garbage: Color,
+    // The empty cell dots.
+    empty: Color,
+    // Behind the board's cells. None leaves the terminal's own.
+    background: Option<Color>,
+    border: Color,
+    text: Color,
+}
+
+// Presets, by name.
+pub const PRESETS: [&str; 4] = ["classic", "pastel", "monochrome", "high-contrast"];
+
+impl Theme {
+    // One of the PRESETS.
+    pub fn preset(name: &str) -> Result<Self, Error> {
+        match name {
+            // The guideline colors. No orange in the 16, so L gets RGB.
+            "classic" => Ok(Self {
+                pieces: [
+                    Color::CYAN,
+                    Color::YELLOW,
+                    Color::MAGENTA,
+                    Color::BLUE,
+                    Color::Rgb(255, 165, 0),
+                    Color::GREEN,
+                    Color::RED,
+                ],
+                ghost: None,
+                garbage: Color::LIGHT_BLACK,
+                empty: Color::Default,
+                background: None,
+                border: Color::Default,
+                text: Color::Default,
+            }),
+            "pastel" => Ok(Self {
+                pieces: [
+                    Color::Rgb(150, 220, 230),
+                    Color::Rgb(250, 230, 150),
+                    Color::Rgb(200, 170, 230),
+                    Color::Rgb(150, 170, 230),
+                    Color::Rgb(250, 190, 140),
+                    Color::Rgb(170, 220, 160),
+                    Color::Rgb(240, 150, 150),
+                ],
+                ghost: None,
+                garbage: Color::Rgb(160, 160, 160),
+                empty: Color::Default,
+                background: None,
+                border: Color::Rgb(190, 180, 210),
+                text: Color::Default,
+            }),
+            "monochrome" => Ok(Self {
+                pieces: [Color::Default; 7],
+                ghost: Some(Color::LIGHT_BLACK),
+                garbage: Color::LIGHT_BLACK,
+                empty: Color::Default,
+                background: None,
+                border: Color::Default,
+                text: Color::Default,
+            }),
+            // Bright colors only, on black whatever the terminal's
+            // background is. Dark grey is kept for what isn't a piece: the
+            // ghost, garbage and the dots, so none of them passes for one.
+            "high-contrast" => Ok(Self {
+                pieces: [
+                    Color::LIGHT_CYAN,
+                    Color::LIGHT_YELLOW,
+                    Color::LIGHT_MAGENTA,
+                    Color::LIGHT_BLUE,
+                    Color::LIGHT_WHITE,
+                    Color::LIGHT_GREEN,
+                    Color::LIGHT_RED,
+                ],
+                ghost: Some(Color::LIGHT_BLACK),
+                garbage: Color::LIGHT_BLACK,
+                empty: Color::LIGHT_BLACK,
+                background: Some(Color::Ansi(0)),
+                border: Color::LIGHT_WHITE,
+                text: Color::LIGHT_WHITE,
+            }),
+            _ => Err(Error::UnknownTheme(name.to_string())),
+        }
+    }
+
+    // The one to use when nobody picked. The basic 16 on some terminals'
+    // color schemes are hard to tell apart, so those get high contrast.
+    pub fn default_for(depth: ColorDepth) -> Self {
+        match depth {
+            ColorDepth::Basic => Self::preset("high-contrast").unwrap(),
+            _ => Self::default(),
+        }
+    }
+
+    // Change one color. `key` is a piece letter or ghost, garbage, empty,
+    // background, border or text. The ghost can also be "piece" to go back to
+    // the piece's color, and the background "none" for the terminal's own.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        if key == "ghost" && value.trim() == "piece" {
+            self.ghost = None;
+            return Ok(());
+        }
+        if key == "background" && value.trim() == "none" {
+            self.background = None;
+            return Ok(());
+        }
+
+        let color = Color::parse(value)?;
+        match key {
+            "ghost" => self.ghost = Some(color),
+            "garbage" => self.garbage = color,
+            "empty" => self.empty = color,
+            "background" => self.background = Some(color),
+            "border" => self.border = color,
+            "text" => self.text = color,
+            _ => match Shape::ALL
+                .iter()
+                .find(|shape| key == shape.letter().to_string())
+            {
+                Some(&shape) => self.pieces[shape as usize] = color,
+                None => return Err(Error::UnknownSetting(format!("color.{}", key))),
+            },
+        }
+        Ok(())
+    }
+
+    pub fn piece(&self, shape: Shape) -> Color {
+        self.pieces[shape as usize]
+    }
+
+    pub fn ghost(&self, shape: Shape) -> Color {
+        self.ghost.unwrap_or(self.piece(shape))
+    }
+
+    pub fn garbage(&self) -> Color {
+        self.garbage
+    }
+
+    pub fn empty(&self) -> Color {
+        self.empty
+    }
+
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+
+    pub fn border(&self) -> Color {
+        self.border
+    }
+
+    pub fn text(&self) -> Color {
+        self.text
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset("classic").unwrap()
+    }
+}
+
+// A theme worked out into styles, along with the glyph each shape's cells
+// get. Drawing happens every frame for every cell, so this is built once and
+// drawing only looks things up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pieces: [Style; 7],
+    ghosts: [Style; 7],
+    cells: [String; 7],
+    ghost_cells: [String; 7],
+    garbage: Style,
+    garbage_cell: String,
+    empty: Style,
+    empty_cell: String,
+    stripe: Option<Color>,
+    border: Style,
+    text: Style,
+}
+
+impl Palette {
+    // `colors` off is no colors at all. Every shape looks the same then, so
+    // they get their letter for a cell instead of the filled glyph.
+    // `colorblind` gives every shape its own glyph, ghost included, color or
+    // not.
+    pub fn new(theme: &Theme, glyphs: &Glyphs, colors: bool, colorblind: bool) -> Self {
+        // Board cells get the background too, when the theme has one.
+        let fg = |color: Color| match colors {
+            true => Style::color(color),
+            false => Style::default(),
+        };
+        let cell_fg = |color: Color| match (colors, theme.background()) {
+            (true, Some(background)) => fg(color).background(background),
+            _ => fg(color),
+        };
+        let cell = |shape: Shape| match (colorblind, colors) {
+            (true, _) => glyphs::PER_SHAPE[shape as usize].to_string(),
+            (false, true) => glyphs.filled().to_string(),
+            (false, false) => shape.letter().to_string().repeat(2),
+        };
+        let ghost_cell = |shape: Shape| match colorblind {
+            true => glyphs::PER_SHAPE[shape as usize].to_string(),
+            false => glyphs.ghost().to_string(),
+        };
+
+        Self {
+            pieces: Shape::ALL.map(|shape| cell_fg(theme.piece(shape))),
+            ghosts: Shape::ALL.map(|shape| cell_fg(theme.ghost(shape))),
+            cells: Shape::ALL.map(cell),
+            ghost_cells: Shape::ALL.map(ghost_cell),
+            garbage: cell_fg(theme.garbage()),
+            garbage_cell: glyphs.filled().to_string(),
+            empty: cell_fg(theme.empty()),
+            empty_cell: glyphs.empty().to_string(),
+            stripe: colors.then_some(STRIPE),
+            border: fg(theme.border()),
+            text: fg(theme.text()),
+        }
+    }
+
+    pub fn piece(&self, shape: Shape) -> Style {
+        self.pieces[shape as usize]
+    }
+
+    pub fn ghost(&self, shape: Shape) -> Style {
+        self.ghosts[shape as usize]
+    }
+
+    // A locked or falling cell of `shape`.
+    pub fn cell(&self, shape: Shape) -> &str {
+        &self.cells[shape as usize]
+    }
+
+    // A cell of `shape`'s ghost.
+    pub fn ghost_cell(&self, shape: Shape) -> &str {
+        &self.ghost_cells[shape as usize]
+    }
+
+    pub fn garbage(&self) -> Style {
+        self.garbage
+    }
+
+    // A cell of garbage. No shape, so no glyph of its own or letter either.
+    pub fn garbage_cell(&self) -> &str {
+        &self.garbage_cell
+    }
+
+    pub fn empty(&self) -> Style {
+        self.empty
+    }
+
+    // An empty board cell.
+    pub fn empty_cell(&self) -> &str {
+        &self.empty_cell
+    }
+
+    // The background on every other column, with stripes on. None without
+    // colors.
+    pub fn stripe(&self) -> Option<Color> {
+        self.stripe
+    }
+
+    pub fn border(&self) -> Style {
+        self.border
+    }
+
+    pub fn text(&self) -> Style {
+        self.text
+    }
+}
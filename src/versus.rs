@@ -0,0 +1,359 @@
+// Two player versus.
+// Two boards side by side on one terminal, one keyboard. Player 1 is on the
+// left with WASD, player 2 on the right with the arrows. Clearing two or more
+// lines at once sends garbage over to the other side. First to top out loses.
+//
+// Both boards are plain Games, each laid out in its own half of the screen and
+// drawing through the same terminal handle. Versus reads the keys and hands
+// each board its own.
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::prelude::*;
+use termion::event::Key;
+
+use crate::highscore::HighScores;
+use crate::input::{InputSource, ScriptedInput, StdinInput};
+use crate::renderer::{Style, TermionRenderer};
+use crate::terminal;
+use crate::{Error, Game, GameState, BOARD_HEIGHT, BOARD_WIDTH, FRAME_RATE};
+
+// One terminal, written to by both boards.
+#[derive(Clone)]
+struct SharedOutput(Rc<RefCell<Box<dyn Write>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+pub struct Versus {
+    players: [Game; 2],
+    input: Box<dyn InputSource>,
+    paused: bool,
+    quit: bool,
+    // Wipe the whole screen before the next frame, both halves.
+    needs_clear: bool,
+    // Where the holes in sent garbage go.
+    rng: StdRng,
+}
+
+impl Default for Versus {
+    fn default() -> Self {
+        Self::new(BOARD_WIDTH, BOARD_HEIGHT).unwrap()
+    }
+}
+
+impl Versus {
+    // The real terminal, split down the middle.
+    pub fn new(width: usize, height: usize) -> Result<Self, Error> {
+        Game::validate_size(width, height)?;
+        let out = terminal::raw_stdout();
+        let (cols, rows) = termion::terminal_size().unwrap_or((80, 24));
+        let mut versus = Self::build(width, height, out, Box::new(StdinInput::new()), cols, rows);
+        for game in versus.players.iter_mut() {
+            game.detect_colors();
+        }
+        Ok(versus)
+    }
+
+    // Draw somewhere else, laid out for a 160x30 screen. No keys until
+    // set_input().
+    pub fn with_output(width: usize, height: usize, out: Box<dyn Write>) -> Result<Self, Error> {
+        Game::validate_size(width, height)?;
+        Ok(Self::build(
+            width,
+            height,
+            out,
+            Box::new(ScriptedInput::default()),
+            160,
+            30,
+        ))
+    }
+
+    fn build(
+        width: usize,
+        height: usize,
+        out: Box<dyn Write>,
+        input: Box<dyn InputSource>,
+        cols: u16,
+        rows: u16,
+    ) -> Self {
+        let out = SharedOutput(Rc::new(RefCell::new(out)));
+        let half = cols / 2;
+        let player = |x| {
+            let mut game = Game::build(
+                width,
+                height,
+                Box::new(TermionRenderer::new(Box::new(out.clone()))),
+                Box::new(ScriptedInput::default()),
+                HighScores::in_memory(),
+            );
+            game.share_screen(x, half, rows);
+            game
+        };
+
+        let mut versus = Self {
+            players: [player(0), player(half)],
+            input,
+            paused: false,
+            quit: false,
+            needs_clear: true,
+            rng: StdRng::from_entropy(),
+        };
+        versus.set_seed(rand::thread_rng().gen());
+        versus
+    }
+
+    pub fn set_input(&mut self, input: Box<dyn InputSource>) {
+        self.input = input;
+    }
+
+    // Both players get the same pieces in the same order.
+    pub fn set_seed(&mut self, seed: u64) {
+        for game in self.players.iter_mut() {
+            game.set_seed(seed);
+        }
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // 0 or 1, once the other one has topped out. None while it's still on,
+    // and for a draw.
+    pub fn winner(&self) -> Option<usize> {
+        match self.players.each_ref().map(lost) {
+            [false, true] => Some(0),
+            [true, false] => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn player(&self, i: usize) -> &Game {
+        &self.players[i]
+    }
+
+    fn over(&self) -> bool {
+        self.players.iter().any(lost)
+    }
+
+    // Start over, both of them, with new pieces.
+    fn restart(&mut self) {
+        let seed = self.rng.gen();
+        self.set_seed(seed);
+        for game in self.players.iter_mut() {
+            game.reset();
+        }
+        self.paused = false;
+        self.needs_clear = true;
+    }
+
+    // One frame: read a key, give it to whoever it belongs to and step both
+    // boards.
+    pub fn update(&mut self, dt: Duration) {
+        let key = self.input.next_key();
+        match key {
+            Some(Key::Char('q')) => self.quit = true,
+            Some(Key::Char('r')) => self.restart(),
+            Some(Key::Char('p')) if !self.over() => {
+                self.paused = !self.paused;
+                self.needs_clear = true;
+            }
+            _ => {}
+        }
+        // Hold on while either board doesn't fit its half.
+        let hidden = self.players.iter().any(|game| !game.layout.fits);
+        if self.quit || self.paused || hidden || self.over() {
+            return;
+        }
+
+        // Either player's key cuts the countdown short for both, so neither
+        // gets a head start.
+        if key.is_some() && self.players.iter().any(|game| !game.countdown.is_zero()) {
+            for game in self.players.iter_mut() {
+                game.countdown = Duration::ZERO;
+            }
+            return;
+        }
+
+        let keys = route(key);
+        let mut sent = [0; 2];
+        for (i, game) in self.players.iter_mut().enumerate() {
+            let result = game.step(keys[i], dt);
+            sent[i] = garbage_for(result.lines_cleared as usize);
+        }
+
+        // Garbage goes over after both have moved, so neither one gets the
+        // jump on the other.
+        for (i, &rows) in sent.iter().enumerate() {
+            let other = &mut self.players[1 - i];
+            if rows > 0 && !lost(other) {
+                let hole = self.rng.gen_range(0..other.width);
+                other.add_garbage(rows, hole).unwrap();
+            }
+        }
+    }
+
+    pub fn run(&mut self) {
+        let mut old_time = Instant::now();
+        while !self.quit {
+            let now = Instant::now();
+            self.update(now - old_time);
+            old_time = now;
+
+            self.render();
+
+            thread::sleep(Duration::from_millis(1000 / (FRAME_RATE as u64)));
+        }
+    }
+
+    fn render(&mut self) {
+        if self.needs_clear {
+            self.needs_clear = false;
+            self.players[0].renderer.clear().unwrap();
+            for game in self.players.iter_mut() {
+                game.needs_redraw = true;
+            }
+        }
+
+        for game in self.players.iter_mut() {
+            game.render();
+        }
+
+        let winner = self.winner();
+        for (i, game) in self.players.iter_mut().enumerate() {
+            if self.paused {
+                banner(game, "PAUSED");
+            } else if winner == Some(i) {
+                banner(game, "WINNER!");
+            }
+            game.renderer.flush().unwrap();
+        }
+    }
+
+    // Who won, for after the terminal is back.
+    pub fn summary(&self) -> String {
+        let scores = format!(
+            "P1: {}  P2: {}",
+            self.players[0].score(),
+            self.players[1].score()
+        );
+        match self.winner() {
+            Some(i) => format!("Player {} wins!  {}", i + 1, scores),
+            None if self.over() => format!("Draw.  {}", scores),
+            None => scores,
+        }
+    }
+}
+
+fn lost(game: &Game) -> bool {
+    matches!(game.state, GameState::Lose)
+}
+
+// Garbage sent for clearing `lines` at once. A tetris is worth the most.
+fn garbage_for(lines: usize) -> usize {
+    match lines {
+        0 | 1 => 0,
+        2 => 1,
+        3 => 2,
+        _ => 4,
+    }
+}
+
+// Split a key between the players, in the keys a single player game uses.
+// Player 1: WASD, e and space. Player 2: the arrows, / to rotate the other
+// way and enter to hard drop.
+fn route(key: Option<Key>) -> [Option<Key>; 2] {
+    match key {
+        Some(Key::Char('a' | 'd' | 's' | 'w' | 'e' | ' ')) => [key, None],
+        Some(Key::Left | Key::Right | Key::Down | Key::Up) => [None, key],
+        Some(Key::Char('/')) => [None, Some(Key::Char('e'))],
+        Some(Key::Char('\n')) => [None, Some(Key::Char(' '))],
+        _ => [None, None],
+    }
+}
+
+// Text across the middle of a board.
+fn banner(game: &mut Game, text: &str) {
+    let cols = game.layout.inner.0 as usize;
+    let (x, y) = (
+        game.layout.left + 1,
+        game.layout.top + 1 + game.layout.inner.1 / 2,
+    );
+    let text = format!("{:^cols$}", text);
+    game.put_text(x, y, &text, Style::default().bold().invert());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cell;
+
+    fn versus(keys: Vec<Option<Key>>) -> Versus {
+        let mut versus = Versus::with_output(10, 20, Box::new(io::sink())).unwrap();
+        versus.set_input(Box::new(ScriptedInput::new(keys)));
+        versus
+    }
+
+    #[test]
+    fn keys_go_to_their_player() {
+        assert_eq!(route(Some(Key::Char('a'))), [Some(Key::Char('a')), None]);
+        assert_eq!(route(Some(Key::Left)), [None, Some(Key::Left)]);
+        assert_eq!(route(Some(Key::Char('\n'))), [None, Some(Key::Char(' '))]);
+        assert_eq!(route(Some(Key::Char('/'))), [None, Some(Key::Char('e'))]);
+        assert_eq!(route(Some(Key::Char('x'))), [None, None]);
+    }
+
+    #[test]
+    fn double_sends_garbage_across() {
+        let mut versus = versus(vec![]);
+        let width = versus.players[0].width;
+        let height = versus.players[0].board.height();
+        for j in height - 2..height {
+            versus.players[0].board.fill_row(j, Cell::Garbage);
+        }
+
+        for _ in 0..30 {
+            versus.update(Duration::from_millis(16));
+        }
+        assert_eq!(versus.player(0).lines_cleared(), 2);
+        let bottom = versus.player(1).board.rows().last().unwrap();
+        assert_eq!(
+            bottom.iter().filter(|&&c| c == Cell::Garbage).count(),
+            width - 1
+        );
+    }
+
+    #[test]
+    fn first_to_top_out_loses() {
+        let mut versus = versus(vec![Some(Key::Char(' ')); 400]);
+        assert_eq!(versus.winner(), None);
+
+        for _ in 0..400 {
+            versus.update(Duration::from_millis(1));
+        }
+        assert!(matches!(versus.player(0).state, GameState::Lose));
+        assert_eq!(versus.winner(), Some(1));
+        assert!(versus.summary().starts_with("Player 2 wins!"));
+
+        // r is a rematch.
+        versus.set_input(Box::new(ScriptedInput::new([Some(Key::Char('r'))])));
+        versus.update(Duration::from_millis(1));
+        assert_eq!(versus.winner(), None);
+        assert!(!versus.over());
+    }
+
+    #[test]
+    fn either_key_skips_both_countdowns() {
+        let mut versus = versus(vec![Some(Key::Char('a'))]);
+        versus.restart();
+        versus.update(Duration::from_millis(16));
+        assert!(versus.players.iter().all(|game| game.countdown.is_zero()));
+    }
+}
@@ -0,0 +1,82 @@
+use std::io;
+use std::time::Duration;
+
+use termion::event::Key;
+
+use tetris::{Error, Game, Point, Shape, Tetromino};
+
+fn game(width: usize, height: usize) -> Result<Game, Error> {
+    Game::with_output(width, height, Box::new(io::sink()))
+}
+
+#[test]
+fn rejects_boards_below_minimum_size() {
+    for (width, height) in [(0, 20), (1, 20), (3, 20), (10, 0), (10, 3), (3, 3)] {
+        assert_eq!(
+            game(width, height).err(),
+            Some(Error::BoardTooSmall { width, height })
+        );
+    }
+}
+
+#[test]
+fn rejects_boards_too_wide_for_the_row_masks() {
+    assert_eq!(game(17, 20).err(), Some(Error::BoardTooWide { width: 17 }));
+    assert!(game(16, 20).is_ok());
+}
+
+#[test]
+fn accepts_boards_at_minimum_size() {
+    assert!(game(4, 4).is_ok());
+    assert!(game(4, 20).is_ok());
+    assert!(game(10, 4).is_ok());
+}
+
+#[test]
+fn from_matrix_lines_clear() {
+    let mut game = Game::from_matrix(&["....", "I...", "IOTJ", "..SZ", "LLLL"]).unwrap();
+    assert_eq!(tetris::testing::clear_lines(&mut game), 2);
+    assert_eq!(game.lines_cleared(), 2);
+    assert_eq!(game.score(), 200);
+}
+
+#[test]
+fn board_as_text_round_trips() {
+    let rows = ["....", "....", "I...", "IOTJ", "G.SZ"];
+    let game = Game::from_matrix(&rows).unwrap();
+    assert_eq!(game.render_to_string(), rows.join("\n") + "\n");
+
+    let text = game.render_to_string();
+    let again = Game::from_matrix(&text.lines().collect::<Vec<_>>()).unwrap();
+    assert_eq!(again.render_to_string(), text);
+}
+
+#[test]
+fn board_as_text_shows_the_falling_piece() {
+    let mut game = Game::from_matrix(&["....", "....", "....", "L...", "LL.G"]).unwrap();
+    game.spawn(Shape::O);
+    // Half of it still up in the buffer, out of the picture.
+    assert_eq!(game.render_to_string(), ".OO.\n....\n....\nL...\nLL.G\n");
+
+    game.step(Some(Key::Char(' ')), Duration::from_millis(16));
+    assert_eq!(game.render_to_string(), "....\n....\n.OO.\nLOO.\nLL.G\n");
+}
+
+#[test]
+fn every_shape_builds_four_distinct_blocks() {
+    for shape in Shape::ALL {
+        let t = Tetromino::of(shape);
+        assert_eq!(t.shape(), shape);
+        assert_eq!(t.rotation(), 0);
+
+        let blocks = t.cells();
+        for (i, a) in blocks.iter().enumerate() {
+            assert!(blocks[i + 1..].iter().all(|b| b != a));
+        }
+
+        // Top-left of the bounding box is the origin.
+        let min_x = blocks.iter().map(|b| b.x).min().unwrap();
+        let min_y = blocks.iter().map(|b| b.y).min().unwrap();
+        assert_eq!(Point::new(min_x, min_y), Point::new(0, 0));
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "panic-test")]
+
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tetris::Game;
+
+// Output that fails the first flush, i.e. the end of the first frame, and
+// records everything written to it.
+struct FailingSink {
+    written: Arc<Mutex<Vec<u8>>>,
+    dropped: Arc<AtomicBool>,
+    failed: bool,
+}
+
+impl Write for FailingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.failed {
+            self.failed = true;
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "boom"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FailingSink {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn guard_restores_terminal_on_panic() {
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let dropped = Arc::new(AtomicBool::new(false));
+    let sink = FailingSink {
+        written: written.clone(),
+        dropped: dropped.clone(),
+        failed: false,
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(move || {
+        let mut game = Game::with_output(10, 20, Box::new(sink)).unwrap();
+        game.run();
+    }));
+
+    assert!(result.is_err(), "the failed flush should panic the loop");
+    assert!(
+        dropped.load(Ordering::SeqCst),
+        "the guard should drop the output"
+    );
+
+    let written = String::from_utf8_lossy(&written.lock().unwrap()).into_owned();
+    assert!(
+        written.ends_with(&termion::cursor::Show.to_string()),
+        "the guard should show the cursor last"
+    );
+}
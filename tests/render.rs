@@ -0,0 +1,290 @@
+use termion::event::Key;
+use termion::{color, cursor, style};
+
+use tetris::testing::{render, render_with, screen, strip_ansi};
+
+#[test]
+fn strip_ansi_keeps_only_text() {
+    let s = format!(
+        "{}{}Score{} 12{}\x1b]0;title\x07!",
+        cursor::Goto(3, 4),
+        color::Fg(color::Rgb(255, 165, 0)),
+        style::Reset,
+        cursor::Hide
+    );
+    assert_eq!(strip_ansi(&s), "Score 12!");
+}
+
+#[test]
+fn render_draws_the_board_and_hud() {
+    let out = strip_ansi(&render(4, 4, [], 1).unwrap());
+
+    // Border, an empty row and the HUD are all in there.
+    assert!(out.starts_with("┌────────┐"));
+    assert!(out.contains("└────────┘"));
+    assert!(out.contains("· · · · "));
+    for label in ["Score", "Best", "Level", "Lines", "Time"] {
+        assert!(out.contains(label), "no {} in {:?}", label, out);
+    }
+}
+
+// The screen before anything happens, against tests/golden/empty_board.txt.
+// UPDATE_GOLDEN=1 writes it out fresh instead, look it over before
+// committing it.
+#[test]
+fn empty_board_matches_the_golden_file() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/empty_board.txt");
+    let drawn = screen(&render(10, 20, [], 0).unwrap(), 80, 24).join("\n") + "\n";
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(path, &drawn).unwrap();
+    }
+    let golden = std::fs::read_to_string(path).unwrap();
+    assert_eq!(drawn, golden, "drawn:\n{}", drawn);
+}
+
+#[test]
+fn piece_stats_keep_the_pace() {
+    // Spawn, drop, spawn, drop.
+    let drop = Some(Key::Char(' '));
+    let out = strip_ansi(&render(10, 20, [None, drop, None, drop], 5).unwrap());
+    assert!(out.contains("Pieces 2"), "{:?}", out);
+    assert!(out.contains("PPS    "), "{:?}", out);
+}
+
+#[test]
+fn render_follows_the_script() {
+    let out = strip_ansi(&render(10, 20, [None, Some(Key::Char('p'))], 3).unwrap());
+    assert!(out.contains("Resume"));
+    assert!(out.contains("Restart"));
+}
+
+#[test]
+fn game_over_shows_final_stats() {
+    // Drop until it's over, then sign the high score table (the spaces
+    // left over are the name).
+    let keys = std::iter::repeat_n(Some(Key::Char(' ')), 400).chain([Some(Key::Char('\n'))]);
+    let out = strip_ansi(&render(4, 4, keys, 402).unwrap());
+
+    assert!(out.contains("GAME OVER"));
+    for label in [
+        "Score",
+        "Lines",
+        "Level",
+        "Time",
+        "Pieces",
+        "PPS",
+        "Combo",
+        "r retry · q quit",
+    ] {
+        assert!(out.contains(label), "no {} in the game over panel", label);
+    }
+    // Too short a board for the high score table.
+    assert!(!out.contains("HIGH SCORES"));
+}
+
+#[test]
+fn game_over_has_room_for_high_scores_on_a_full_size_board() {
+    let keys = std::iter::repeat_n(Some(Key::Char(' ')), 400).chain([Some(Key::Char('\n'))]);
+    let out = strip_ansi(&render(10, 20, keys, 402).unwrap());
+    assert!(out.contains("GAME OVER"));
+    assert!(out.contains("Combo"));
+    assert!(out.contains("HIGH SCORES"));
+}
+
+#[test]
+fn ascii_only_is_all_ascii() {
+    let keys = std::iter::repeat_n(Some(Key::Char(' ')), 400).chain([Some(Key::Char('\n'))]);
+    let out = render_with(10, 20, keys, 402, |game| {
+        game.set_ascii(true);
+        game.set_stripes(true);
+    })
+    .unwrap();
+    let out = strip_ansi(&out);
+    assert!(out.is_ascii(), "{:?}", out.chars().find(|c| !c.is_ascii()));
+    assert!(out.starts_with("+--------------------+"));
+    assert!(out.contains(". . . "));
+    assert!(out.contains("<> move"));
+    assert!(out.contains("GAME OVER :("));
+}
+
+#[test]
+fn game_over_emoji_can_go() {
+    let keys = || std::iter::repeat_n(Some(Key::Char(' ')), 400).chain([Some(Key::Char('\n'))]);
+    let out = strip_ansi(&render(4, 4, keys(), 402).unwrap());
+    assert!(out.contains("GAME OVER ☹️"));
+
+    let out = render_with(4, 4, keys(), 402, |game| game.set_emoji(false)).unwrap();
+    let out = strip_ansi(&out);
+    assert!(out.contains("GAME OVER"));
+    assert!(!out.contains('☹'));
+}
+
+#[test]
+fn border_styles_by_name() {
+    let rounded = tetris::border::by_name("rounded").unwrap();
+    let out = strip_ansi(&render_with(4, 4, [], 1, |game| game.set_border(rounded)).unwrap());
+    assert!(out.starts_with("╭────────╮"), "{:?}", out);
+    assert!(out.contains("╰────────╯"));
+
+    let ascii = tetris::border::by_name("ascii").unwrap();
+    let out = strip_ansi(&render_with(4, 4, [], 1, |game| game.set_border(ascii)).unwrap());
+    assert!(out.starts_with("+--------+"), "{:?}", out);
+    assert!(!out.contains('─'));
+}
+
+#[test]
+fn no_color_means_no_color_codes() {
+    // A few pieces down, a pause and a game over, so most of what can be
+    // drawn has been.
+    let keys = std::iter::repeat_n(Some(Key::Char(' ')), 400)
+        .chain([Some(Key::Char('\n')), Some(Key::Char('p'))]);
+    let out = render_with(10, 20, keys, 403, |game| game.set_color(false)).unwrap();
+
+    assert!(!has_color(&out), "color escape in {:?}", out);
+    // Still reset after bold and such.
+    assert!(out.contains(&style::Reset.to_string()));
+    // Pieces by their letters.
+    let text = strip_ansi(&out);
+    assert!(["II", "OO", "TT", "JJ", "LL", "SS", "ZZ"]
+        .iter()
+        .any(|cell| text.contains(cell)));
+
+    // And the same thing in color does have them.
+    let out = render(10, 20, [Some(Key::Char(' '))], 2).unwrap();
+    assert!(has_color(&out));
+}
+
+// Any SGR escape setting a foreground color, \x1b[3...m. The \x1b[3;1H
+// kind is the cursor going to row 3.
+fn has_color(s: &str) -> bool {
+    s.split("\x1b[").skip(1).any(|escape| {
+        let end = escape.find(|c: char| ('@'..='~').contains(&c));
+        escape.starts_with('3') && end.is_some_and(|end| escape[end..].starts_with('m'))
+    })
+}
+
+#[test]
+fn colorblind_glyph_per_shape() {
+    // The piece stats show every shape.
+    let out = strip_ansi(&render_with(10, 20, [], 1, |game| game.set_colorblind(true)).unwrap());
+    for (letter, glyph) in ["I", "O", "T", "J", "L", "S", "Z"]
+        .iter()
+        .zip(tetris::glyphs::PER_SHAPE)
+    {
+        assert!(
+            out.contains(&format!("{} {}", glyph, letter)),
+            "no {} for {}",
+            glyph,
+            letter
+        );
+    }
+}
+
+#[test]
+fn capital_f_shows_the_frame_time() {
+    let out = strip_ansi(&render(10, 20, [], 2).unwrap());
+    assert!(!out.contains("fps"));
+
+    // One frame at FRAME_RATE is 16ms. The timings are whatever they were.
+    let out = strip_ansi(&render(10, 20, [Some(Key::Char('F'))], 2).unwrap());
+    assert!(out.contains(" fps           62.5"), "{:?}", out);
+    assert!(out.contains(" frame max"), "{:?}", out);
+    assert!(out.contains(" gravity      400ms"), "{:?}", out);
+
+    // F3 works the same.
+    let out = strip_ansi(&render(10, 20, [Some(Key::F(3))], 2).unwrap());
+    assert!(out.contains(" bytes/frame"), "{:?}", out);
+}
+
+#[test]
+fn keys_help_under_the_board() {
+    let out = strip_ansi(&render(10, 20, [], 1).unwrap());
+    assert!(
+        out.contains("←→ move · ↑ rotate · ↓ soft drop"),
+        "{:?}",
+        out
+    );
+
+    let out = render_with(10, 20, [], 1, |game| game.set_hint(false));
+    assert!(!strip_ansi(&out.unwrap()).contains("move"));
+}
+
+#[test]
+fn pause_fades_the_board_under_the_menu() {
+    let faint = |keys: [Option<Key>; 2]| {
+        let out = render_with(10, 20, keys, 2, |game| game.set_dim_locked(false)).unwrap();
+        out.matches("\x1b[2m").count()
+    };
+    let playing = faint([None, None]);
+    let paused = faint([None, Some(Key::Char('p'))]);
+    assert!(paused > playing, "{} vs {}", paused, playing);
+}
+
+#[test]
+fn score_goes_up_in_the_title_when_asked() {
+    let out = render(10, 20, [], 3).unwrap();
+    assert!(!out.contains("\x1b]0;"));
+
+    // Sent once, while nothing changes, with the old title saved around it.
+    let out = render_with(10, 20, [], 3, |game| game.set_title(true)).unwrap();
+    assert_eq!(out.matches("\x1b]0;").count(), 1);
+    assert!(out.contains("\x1b]0;tetris — Marathon — 0 pts — lvl 1\x07"));
+    let push = out.find("\x1b[22;0t").unwrap();
+    assert!(push < out.find("\x1b]0;").unwrap());
+    assert!(out.ends_with("\x1b[23;0t"), "{:?}", out);
+}
+
+#[test]
+fn stripes_shade_every_other_column() {
+    let out = render_with(10, 20, [], 1, |game| {
+        game.set_stripes(true);
+        game.set_color(false);
+    });
+    assert!(strip_ansi(&out.unwrap()).contains("· . · . · . · . · . "));
+
+    // With colors it's the background, the dots stay.
+    let out = render_with(10, 20, [], 1, |game| game.set_stripes(true)).unwrap();
+    assert!(out.contains("\x1b[48;"), "{:?}", out);
+    assert!(!strip_ansi(&out).contains(". "));
+}
+
+#[test]
+fn practice_numbers_the_piece_stats() {
+    let out = render_with(10, 20, [], 1, |game| {
+        game.set_mode(tetris::GameMode::Practice)
+    });
+    let out = strip_ansi(&out.unwrap());
+    assert!(out.contains("1 [] I"), "{:?}", out);
+    assert!(out.contains("7 [] Z"), "{:?}", out);
+}
+
+#[test]
+fn no_grid_leaves_empty_cells_blank() {
+    // A few pieces down, so there's a stack to draw around.
+    let drop = Some(Key::Char(' '));
+    let keys = [None, drop, None, drop];
+    // The keys help has dots of its own.
+    let out = render_with(10, 20, keys, 5, |game| {
+        game.set_grid(false);
+        game.set_hint(false);
+    });
+    let out = strip_ansi(&out.unwrap());
+    assert!(!out.contains('·'), "{:?}", out);
+    assert!(out.contains("[]"));
+    assert!(out.contains("Pieces 2"));
+}
+
+#[test]
+fn scale_two_draws_every_cell_twice_the_size() {
+    let out = render_with(4, 4, [], 3, |game| game.set_scale(Some(2)).unwrap());
+    let out = strip_ansi(&out.unwrap());
+
+    // Four cells across is sixteen columns.
+    assert!(
+        out.starts_with(&format!("┌{}┐", "─".repeat(16))),
+        "{:?}",
+        out
+    );
+    assert!(out.contains(&"· ".repeat(8)));
+}